@@ -1,3 +1,28 @@
 use gpui::*;
 
-actions!(jid, [Save, ToggleTheme, ToggleFocusMode, OpenConfig, Quit]);
+actions!(
+    jid,
+    [
+        Save,
+        ToggleTheme,
+        ToggleFocusMode,
+        ToggleForwardOnly,
+        OpenConfig,
+        Quit,
+        NewDocument,
+        CloseDocument,
+        NextTab,
+        PreviousTab,
+        SearchLibrary,
+        ToggleOutline,
+        ShowStats,
+        StartSprint,
+        ToggleWriteOrLose,
+        SuggestSpelling,
+        ToggleStyleAnalysis,
+        ExportHtml,
+        ExportEpub,
+        ExportDocx,
+        ExportOdt,
+    ]
+);
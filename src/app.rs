@@ -1,32 +1,127 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use gpui::*;
 
 use std::process::Command;
 
-use crate::actions::{OpenConfig, Save, ToggleTheme};
-use crate::config::Config;
-use crate::document::Document;
-use crate::editor::{EditorEvent, EditorView};
+use std::path::{Path, PathBuf};
+
+use crate::actions::{
+    CloseDocument, ExportDocx, ExportEpub, ExportHtml, ExportOdt, NewDocument, NextTab, OpenConfig, PreviousTab,
+    Save, SearchLibrary, ShowStats, StartSprint, SuggestSpelling, ToggleOutline, ToggleTheme, ToggleWriteOrLose,
+};
+use crate::config::{Config, FadeMode, Keymap, StartupMode};
+use crate::document::{DiskChange, Document, front_matter_entry};
+use crate::editor::{EditorEvent, EditorView, Quotes};
+use crate::export::{ExportFormat, export_book, export_document};
+use crate::outline::OutlineView;
+use crate::pressure::Pressure;
+use crate::readability::Analysis;
+use crate::search::{SearchEvent, SearchIndex, SearchView};
+use crate::session::{Session, SessionDocument, WindowState};
+use crate::spell::{SpellChecker, SpellingEvent, SpellingView};
+use crate::sprint::{Sprint, SprintEvent, SprintView};
+use crate::stats::{StatsEvent, StatsLog, StatsView, count_words};
 use crate::theme::Theme;
 
 const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5);
+const NOTICE_DURATION: Duration = Duration::from_secs(4);
+const GOAL_BAR_WIDTH: f32 = 48.0;
+const SPRINT_TICK: Duration = Duration::from_secs(1);
+const PRESSURE_TICK: Duration = Duration::from_millis(250);
 
-pub struct Jid {
-    editor: Entity<EditorView>,
+struct Tab {
     document: Document,
+    editor: Entity<EditorView>,
+    /// The word count as of the last edit, so each edit's change in words
+    /// can go to the stats log.
+    words: usize,
+}
+
+impl Tab {
+    /// Takes the current word count as the baseline without logging it, for
+    /// text that arrived from disk rather than from typing.
+    fn reset_word_count(&mut self, cx: &App) {
+        self.words = count_words(self.editor.read(cx).content());
+    }
+}
+
+pub struct Jid {
+    tabs: Vec<Tab>,
+    active_tab: usize,
     theme: Theme,
     config: Config,
+    window_state: Option<WindowState>,
+    saved_session: Session,
+    search_index: SearchIndex,
+    search: Option<Entity<SearchView>>,
+    /// The outline panel beside the editor, while it's shown.
+    outline: Option<Entity<OutlineView>>,
+    stats: StatsLog,
+    stats_view: Option<Entity<StatsView>>,
+    sprint: Option<Sprint>,
+    sprint_view: Option<Entity<SprintView>>,
+    pressure: Option<Pressure>,
+    /// Shared by every editor; `None` when spell checking is off.
+    spell_checker: Option<Entity<SpellChecker>>,
+    spelling: Option<Entity<SpellingView>>,
+    notice: Option<SharedString>,
 }
 
 impl Jid {
-    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+    pub fn new(session: Session, window: &mut Window, cx: &mut Context<Self>) -> Self {
         let config = Config::load();
         let theme = Theme::from_mode(config.theme);
-        let document = Document::new(config.documents_dir.clone());
-        let editor = cx.new(|cx| EditorView::new(cx, theme.clone(), config.focus_mode));
+        let spell_checker = config
+            .spell_check
+            .then(|| cx.new(|_| SpellChecker::load(&config.spell_language)));
 
-        cx.subscribe(&editor, Self::on_editor_event).detach();
+        let mut tabs = Vec::new();
+        let mut active_tab = 0;
+        if config.startup == StartupMode::Resume {
+            for (ix, saved) in session.documents.iter().enumerate() {
+                let (document, content) = match Document::open(saved.path.clone()) {
+                    Ok(opened) => opened,
+                    Err(e) => {
+                        if e.kind() != std::io::ErrorKind::NotFound {
+                            eprintln!("Failed to reopen {}: {}", saved.path.display(), e);
+                        }
+                        continue;
+                    }
+                };
+                let mut tab = Self::new_tab(document, &theme, &config, spell_checker.as_ref(), cx);
+                tab.editor.update(cx, |editor, cx| {
+                    editor.set_content(content, cx);
+                    editor.restore_folds(&saved.folded, cx);
+                    editor.restore_position(saved.cursor, px(saved.scroll_y), cx);
+                });
+                tab.reset_word_count(cx);
+                if ix == session.active {
+                    active_tab = tabs.len();
+                }
+                tabs.push(tab);
+            }
+        }
+        if tabs.is_empty() {
+            let document = Document::new(config.documents_dir.clone());
+            tabs.push(Self::new_tab(document, &theme, &config, spell_checker.as_ref(), cx));
+        }
+
+        cx.observe_window_bounds(window, |this, window, _cx| {
+            if let WindowBounds::Windowed(bounds) = window.window_bounds() {
+                this.window_state = Some(WindowState::from_bounds(bounds));
+            }
+        })
+        .detach();
+
+        cx.on_app_quit(|this, cx| {
+            this.save_all_modified(cx);
+            this.save_session(cx);
+            this.search_index.save();
+            this.stats.save();
+            async {}
+        })
+        .detach();
 
         let entity = cx.entity().downgrade();
         window.spawn(cx, {
@@ -35,7 +130,11 @@ impl Jid {
                 loop {
                     cx.background_executor().timer(AUTOSAVE_INTERVAL).await;
                     let result = entity.update(cx, |this, cx| {
-                        this.save_if_modified(cx);
+                        this.reload_changed_documents(cx);
+                        this.save_all_modified(cx);
+                        this.save_session(cx);
+                        this.search_index.save();
+                        this.stats.save();
                     });
                     if result.is_err() {
                         break;
@@ -44,68 +143,744 @@ impl Jid {
             }
         }).detach();
 
+        let pressure = config
+            .write_or_lose
+            .then(|| Pressure::new(config.write_or_lose_grace));
+        if let Some(pressure) = &pressure {
+            Self::watch_pressure(pressure.started, window, cx);
+        }
+
+        let window_state = session.window;
         Self {
-            editor,
-            document,
+            tabs,
+            active_tab,
             theme,
             config,
+            window_state,
+            saved_session: session,
+            search_index: SearchIndex::load(),
+            search: None,
+            outline: None,
+            stats: StatsLog::load(),
+            stats_view: None,
+            sprint: None,
+            sprint_view: None,
+            pressure,
+            spell_checker,
+            spelling: None,
+            notice: None,
+        }
+    }
+
+    fn new_tab(
+        document: Document,
+        theme: &Theme,
+        config: &Config,
+        spell_checker: Option<&Entity<SpellChecker>>,
+        cx: &mut Context<Self>,
+    ) -> Tab {
+        let editor = cx.new(|cx| EditorView::new(cx, theme.clone(), config.focus_mode));
+        editor.update(cx, |editor, cx| {
+            editor.set_forward_only(config.forward_only, cx);
+            editor.set_style_analysis(config.style_analysis, cx);
+            editor.set_smart_punctuation(
+                config
+                    .smart_punctuation
+                    .then(|| Quotes::for_language(&config.spell_language)),
+            );
+            editor.set_auto_pair(config.auto_pair);
+            editor.set_vim(config.keymap == Keymap::Vim, cx);
+            editor.set_emacs(config.keymap == Keymap::Emacs, cx);
+            editor.set_spell_checker(spell_checker.cloned(), cx);
+        });
+        cx.subscribe(&editor, Self::on_editor_event).detach();
+        Tab {
+            document,
+            editor,
+            words: 0,
         }
     }
 
     pub fn editor(&self) -> &Entity<EditorView> {
-        &self.editor
+        &self.tabs[self.active_tab].editor
     }
 
     fn on_editor_event(
         &mut self,
-        _editor: Entity<EditorView>,
+        editor: Entity<EditorView>,
         event: &EditorEvent,
-        _cx: &mut Context<Self>,
+        cx: &mut Context<Self>,
     ) {
         match event {
             EditorEvent::Modified => {
-                self.document.mark_modified();
+                if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.editor == editor) {
+                    tab.document.mark_modified();
+                    let words = count_words(editor.read(cx).content());
+                    let added = words.saturating_sub(tab.words);
+                    self.stats.record(tab.document.path(), added, tab.words.saturating_sub(words));
+                    tab.words = words;
+                    if let Some(sprint) = &mut self.sprint {
+                        sprint.words += added;
+                    }
+                    let path = tab.document.path().to_path_buf();
+                    self.check_goals(&path, added, cx);
+                }
+                if let Some(pressure) = &mut self.pressure {
+                    pressure.input();
+                    let started = pressure.started;
+                    self.apply_pressure(started, cx);
+                }
+            }
+            // Nothing new was written, so the stats only catch up with the
+            // new count.
+            EditorEvent::Rearranged => {
+                if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.editor == editor) {
+                    tab.document.mark_modified();
+                    tab.words = count_words(editor.read(cx).content());
+                }
             }
             EditorEvent::FocusModeChanged(enabled) => {
                 self.config.focus_mode = *enabled;
                 self.config.save();
+                for tab in self.tabs.iter().filter(|tab| tab.editor != editor) {
+                    tab.editor.update(cx, |editor, cx| {
+                        editor.set_focus_mode(*enabled, cx);
+                    });
+                }
+            }
+            EditorEvent::StyleAnalysisChanged(enabled) => {
+                self.config.style_analysis = *enabled;
+                self.config.save();
+                for tab in self.tabs.iter().filter(|tab| tab.editor != editor) {
+                    tab.editor.update(cx, |editor, cx| {
+                        editor.set_style_analysis(*enabled, cx);
+                    });
+                }
+                cx.notify();
+            }
+            EditorEvent::ForwardOnlyChanged(enabled) => {
+                self.config.forward_only = *enabled;
+                self.config.save();
+                for tab in self.tabs.iter().filter(|tab| tab.editor != editor) {
+                    tab.editor.update(cx, |editor, cx| {
+                        editor.set_forward_only(*enabled, cx);
+                    });
+                }
+                cx.notify();
+            }
+            EditorEvent::VimModeChanged => cx.notify(),
+        }
+    }
+
+    fn toggle_write_or_lose(&mut self, _: &ToggleWriteOrLose, window: &mut Window, cx: &mut Context<Self>) {
+        self.config.write_or_lose = !self.config.write_or_lose;
+        self.config.save();
+        if self.config.write_or_lose {
+            let pressure = Pressure::new(self.config.write_or_lose_grace);
+            Self::watch_pressure(pressure.started, window, cx);
+            self.pressure = Some(pressure);
+        } else {
+            self.pressure = None;
+            for tab in &self.tabs {
+                tab.editor.update(cx, |editor, cx| {
+                    editor.set_theme(self.theme.clone(), cx);
+                    editor.set_fade(0.0, cx);
+                });
+            }
+            if let Some(outline) = &self.outline {
+                outline.update(cx, |outline, cx| outline.set_theme(self.theme.clone(), cx));
             }
         }
+        cx.notify();
     }
 
-    fn save_if_modified(&mut self, cx: &mut Context<Self>) {
-        let is_modified = self.editor.read(cx).is_modified();
+    /// Runs write-or-lose's clock, which like the sprint's lives on a window
+    /// timer and stops once the mode it was started for is switched off.
+    fn watch_pressure(started: Instant, window: &mut Window, cx: &mut Context<Self>) {
+        let entity = cx.entity().downgrade();
+        window.spawn(cx, async move |cx: &mut AsyncWindowContext| {
+            loop {
+                cx.background_executor().timer(PRESSURE_TICK).await;
+                let running = entity.update(cx, |this, cx| this.apply_pressure(started, cx));
+                if !matches!(running, Ok(true)) {
+                    break;
+                }
+            }
+        }).detach();
+    }
+
+    /// Shifts the colors toward the warning theme as far as the time since
+    /// the last keystroke calls for, fading or erasing the words written
+    /// since the previous pause if the config asks. Erasing is skipped in
+    /// forward-only mode, where undo couldn't bring them back. Open overlays
+    /// hold the clock. Returns false if the pressure that `started` then is
+    /// gone.
+    fn apply_pressure(&mut self, started: Instant, cx: &mut Context<Self>) -> bool {
+        let overlay_open = self.search.is_some()
+            || self.stats_view.is_some()
+            || self.sprint_view.is_some()
+            || self.spelling.is_some();
+        let Some(pressure) = self.pressure.as_mut().filter(|pressure| pressure.started == started) else {
+            return false;
+        };
+        if overlay_open {
+            pressure.hold();
+        }
+        let level = pressure.level();
+        if level == pressure.shown {
+            return true;
+        }
+        let warning_began = pressure.shown == 0.0;
+        pressure.shown = level;
+
+        let theme = self.shown_theme();
+        let fade = match self.config.write_or_lose_fade {
+            FadeMode::Off => 0.0,
+            FadeMode::Dim | FadeMode::Erase => level,
+        };
+        let active = self.tabs[self.active_tab].editor.clone();
+        for tab in &self.tabs {
+            tab.editor.update(cx, |editor, cx| {
+                if warning_began && tab.editor == active {
+                    editor.close_recent();
+                }
+                editor.set_theme(theme.clone(), cx);
+                editor.set_fade(fade, cx);
+            });
+        }
+        if let Some(outline) = &self.outline {
+            outline.update(cx, |outline, cx| outline.set_theme(theme.clone(), cx));
+        }
+
+        if self.config.write_or_lose_fade == FadeMode::Erase && !self.config.forward_only && level >= 1.0 {
+            let tab = &mut self.tabs[self.active_tab];
+            if tab.editor.update(cx, |editor, cx| editor.erase_recent(cx)) {
+                tab.document.mark_modified();
+                tab.reset_word_count(cx);
+                self.show_notice("Recent words erased · Cmd+Z brings them back", cx);
+            }
+            if let Some(pressure) = &mut self.pressure {
+                pressure.hold();
+            }
+        }
+        cx.notify();
+        true
+    }
+
+    /// The theme as currently drawn, shifted by any write-or-lose warning.
+    fn shown_theme(&self) -> Theme {
+        match &self.pressure {
+            Some(pressure) if pressure.shown > 0.0 => self.theme.blend(&self.theme.warning(), pressure.shown),
+            _ => self.theme.clone(),
+        }
+    }
+
+    /// Quietly notes in the header when an edit carries today's word count
+    /// past the daily or per-document goal.
+    fn check_goals(&mut self, path: &Path, added: usize, cx: &mut Context<Self>) {
+        if added == 0 {
+            return;
+        }
+        let reached = |goal: Option<usize>, words: usize| {
+            goal.filter(|&goal| goal > 0 && words >= goal && words - added < goal)
+        };
+        if let Some(goal) = reached(self.config.daily_goal, self.stats.words_today()) {
+            self.show_notice(format!("Daily goal reached: {} words today", goal), cx);
+        } else if let Some(goal) = reached(self.config.document_goal, self.stats.document_words_today(path)) {
+            self.show_notice(format!("Goal reached: {} words in this document today", goal), cx);
+        }
+    }
+
+    /// Picks up edits made to open documents outside this window, such as
+    /// `jid append`. Appends merge into the buffer even with unsaved typing;
+    /// a wholesale rewrite only replaces a buffer that has no unsaved changes.
+    fn reload_changed_documents(&mut self, cx: &mut Context<Self>) {
+        for tab in &mut self.tabs {
+            let Some(change) = tab.document.check_disk() else { continue };
+            tab.editor.update(cx, |editor, cx| match change {
+                DiskChange::Appended(text) => editor.append_external(&text, cx),
+                DiskChange::Replaced(content) => {
+                    if editor.is_modified() {
+                        eprintln!(
+                            "{} changed on disk; keeping unsaved edits",
+                            tab.document.filename()
+                        );
+                    } else {
+                        editor.reload(content, cx);
+                    }
+                }
+            });
+            tab.reset_word_count(cx);
+        }
+    }
+
+    fn save_all_modified(&mut self, cx: &mut Context<Self>) {
+        for ix in 0..self.tabs.len() {
+            let _ = self.save_if_modified(ix, cx);
+        }
+    }
+
+    fn save_if_modified(&mut self, ix: usize, cx: &mut Context<Self>) -> std::io::Result<()> {
+        let is_modified = self.tabs[ix].editor.read(cx).is_modified();
         if is_modified {
-            self.save(cx);
+            self.save(ix, cx)?;
         }
+        Ok(())
     }
 
-    fn save(&mut self, cx: &mut Context<Self>) {
-        let content = self.editor.read(cx).content().to_string();
-        if let Err(e) = self.document.save(&content) {
+    fn save(&mut self, ix: usize, cx: &mut Context<Self>) -> std::io::Result<()> {
+        let tab = &mut self.tabs[ix];
+        let content = tab.editor.read(cx).content().to_string();
+        if let Err(e) = tab.document.save(&content) {
             eprintln!("Failed to save: {}", e);
-            return;
+            return Err(e);
         }
-        self.editor.update(cx, |editor, _| {
+        self.search_index.update(tab.document.path(), &content);
+        tab.editor.update(cx, |editor, _| {
             editor.mark_saved();
         });
         cx.notify();
+        Ok(())
+    }
+
+    /// Writes the open documents and window placement to the session file,
+    /// skipping the write when nothing changed since the last one.
+    fn save_session(&mut self, cx: &mut Context<Self>) {
+        let documents = self
+            .tabs
+            .iter()
+            .map(|tab| {
+                let editor = tab.editor.read(cx);
+                SessionDocument {
+                    path: tab.document.path().to_path_buf(),
+                    cursor: editor.cursor_offset(),
+                    scroll_y: editor.scroll_y().into(),
+                    folded: editor.folded_headings(),
+                }
+            })
+            .collect();
+        let session = Session {
+            active: self.active_tab,
+            window: self.window_state,
+            documents,
+        };
+        if session != self.saved_session {
+            session.save();
+            self.saved_session = session;
+        }
     }
 
     fn manual_save(&mut self, _: &Save, _window: &mut Window, cx: &mut Context<Self>) {
-        self.save(cx);
+        if self.save(self.active_tab, cx).is_err() {
+            self.show_notice("Save failed", cx);
+        }
+    }
+
+    fn activate_tab(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        if ix >= self.tabs.len() {
+            return;
+        }
+        self.active_tab = ix;
+        let editor = self.tabs[ix].editor.clone();
+        self.show_plain_text(cx);
+        if let Some(outline) = &self.outline {
+            outline.update(cx, |outline, cx| outline.set_editor(editor.clone(), cx));
+        }
+        window.focus(&editor.focus_handle(cx));
+        cx.notify();
+    }
+
+    /// Switches to the tab showing `path`, opening it in a new tab first if
+    /// it isn't open yet.
+    fn open_path(&mut self, path: PathBuf, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        if let Some(ix) = self.tabs.iter().position(|tab| tab.document.path() == path) {
+            self.activate_tab(ix, window, cx);
+            return true;
+        }
+        let (document, content) = match Document::open(path) {
+            Ok(opened) => opened,
+            Err(e) => {
+                eprintln!("Failed to open: {}", e);
+                return false;
+            }
+        };
+        let mut tab = Self::new_tab(document, &self.theme, &self.config, self.spell_checker.as_ref(), cx);
+        tab.editor.update(cx, |editor, cx| editor.set_content(content, cx));
+        tab.reset_word_count(cx);
+        self.tabs.insert(self.active_tab + 1, tab);
+        self.activate_tab(self.active_tab + 1, window, cx);
+        true
+    }
+
+    fn search_library(&mut self, _: &SearchLibrary, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(search) = &self.search {
+            window.focus(&search.focus_handle(cx));
+            return;
+        }
+        self.refresh_search_index(cx);
+        let search = cx.new(|cx| SearchView::new(cx, self.theme.clone()));
+        cx.subscribe_in(&search, window, Self::on_search_event).detach();
+        window.focus(&search.focus_handle(cx));
+        self.search = Some(search);
+        cx.notify();
+    }
+
+    /// Catches the index up with changes made outside jid. Reading the
+    /// library happens in the background; the open overlay's hits are
+    /// redone once it's done.
+    fn refresh_search_index(&mut self, cx: &mut Context<Self>) {
+        let indexed = self.search_index.mtimes();
+        let documents_dir = self.config.documents_dir.clone();
+        cx.spawn(async move |this, cx| {
+            let changes = cx
+                .background_executor()
+                .spawn(async move { SearchIndex::scan(&indexed, &documents_dir) })
+                .await;
+            let _ = this.update(cx, |this, cx| {
+                this.search_index.apply(changes);
+                if let Some(search) = this.search.clone() {
+                    let query = search.read(cx).query().to_string();
+                    let hits = this.search_index.search(&query);
+                    search.update(cx, |search, cx| search.set_hits(hits, cx));
+                }
+            });
+        })
+        .detach();
+    }
+
+    fn on_search_event(
+        &mut self,
+        search: &Entity<SearchView>,
+        event: &SearchEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            SearchEvent::QueryChanged(query) => {
+                let hits = self.search_index.search(query);
+                search.update(cx, |search, cx| search.set_hits(hits, cx));
+            }
+            SearchEvent::Open { path, offset } => {
+                self.close_search(window, cx);
+                if self.open_path(path.clone(), window, cx) {
+                    self.editor().update(cx, |editor, cx| editor.jump_to(*offset, cx));
+                }
+            }
+            SearchEvent::Dismissed => {
+                self.close_search(window, cx);
+            }
+        }
+    }
+
+    fn close_search(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.search = None;
+        window.focus(&self.editor().focus_handle(cx));
+        cx.notify();
+    }
+
+    /// Offers corrections for the misspelled word at the cursor.
+    fn suggest_spelling(&mut self, _: &SuggestSpelling, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(checker) = &self.spell_checker else {
+            self.show_notice("Spell checking is off", cx);
+            return;
+        };
+        if !checker.read(cx).has_dictionary() {
+            self.show_notice(format!("No {} dictionary found", self.config.spell_language), cx);
+            return;
+        }
+        let editor = self.editor().read(cx);
+        let Some(range) = editor.misspelling_at_cursor() else { return };
+        let word = editor.content()[range.clone()].to_string();
+        let suggestions = checker.read(cx).suggest(&word);
+        let spelling = cx.new(|cx| SpellingView::new(cx, self.theme.clone(), word, range, suggestions));
+        cx.subscribe_in(&spelling, window, Self::on_spelling_event).detach();
+        window.focus(&spelling.focus_handle(cx));
+        self.spelling = Some(spelling);
+        cx.notify();
+    }
+
+    fn on_spelling_event(
+        &mut self,
+        _: &Entity<SpellingView>,
+        event: &SpellingEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            SpellingEvent::Replace { range, text } => {
+                self.editor().update(cx, |editor, cx| editor.replace(range.clone(), text, cx));
+            }
+            SpellingEvent::Learn(word) => {
+                if let Some(checker) = &self.spell_checker {
+                    checker.update(cx, |checker, cx| {
+                        checker.add_word(word);
+                        cx.notify();
+                    });
+                }
+            }
+            SpellingEvent::Dismissed => {}
+        }
+        self.spelling = None;
+        window.focus(&self.editor().focus_handle(cx));
+        cx.notify();
+    }
+
+    /// Shows the outline of the current document beside it, or hides it.
+    fn toggle_outline(&mut self, _: &ToggleOutline, _window: &mut Window, cx: &mut Context<Self>) {
+        self.outline = match self.outline.take() {
+            Some(_) => None,
+            None => {
+                let editor = self.editor().clone();
+                let theme = self.shown_theme();
+                Some(cx.new(|cx| OutlineView::new(cx, theme, editor)))
+            }
+        };
+        cx.notify();
+    }
+
+    /// Opens the writing stats overlay, or closes it if it is already open.
+    fn show_stats(&mut self, _: &ShowStats, window: &mut Window, cx: &mut Context<Self>) {
+        if self.stats_view.is_some() {
+            self.close_stats(window, cx);
+            return;
+        }
+        let log = self.stats.clone();
+        let document = self.tabs[self.active_tab].document.path().to_path_buf();
+        let stats_view = cx.new(|cx| StatsView::new(cx, self.theme.clone(), log, document));
+        cx.subscribe_in(&stats_view, window, |this, _, event: &StatsEvent, window, cx| match event {
+            StatsEvent::Dismissed => this.close_stats(window, cx),
+        })
+        .detach();
+        window.focus(&stats_view.focus_handle(cx));
+        self.stats_view = Some(stats_view);
+        cx.notify();
+    }
+
+    fn close_stats(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.stats_view = None;
+        window.focus(&self.editor().focus_handle(cx));
+        cx.notify();
+    }
+
+    /// Offers to start a writing sprint, or ends the one underway early.
+    fn start_sprint(&mut self, _: &StartSprint, window: &mut Window, cx: &mut Context<Self>) {
+        if self.sprint.is_some() {
+            self.finish_sprint(window, cx);
+            return;
+        }
+        if let Some(sprint_view) = &self.sprint_view {
+            window.focus(&sprint_view.focus_handle(cx));
+            return;
+        }
+        let minutes = self.config.sprint_minutes;
+        let sprint_view = cx.new(|cx| SprintView::setup(cx, self.theme.clone(), minutes));
+        self.show_sprint_view(sprint_view, window, cx);
+    }
+
+    fn show_sprint_view(&mut self, sprint_view: Entity<SprintView>, window: &mut Window, cx: &mut Context<Self>) {
+        cx.subscribe_in(&sprint_view, window, Self::on_sprint_event).detach();
+        window.focus(&sprint_view.focus_handle(cx));
+        self.sprint_view = Some(sprint_view);
+        cx.notify();
+    }
+
+    fn on_sprint_event(
+        &mut self,
+        _: &Entity<SprintView>,
+        event: &SprintEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.sprint_view = None;
+        window.focus(&self.editor().focus_handle(cx));
+        if let SprintEvent::Start(minutes) = event {
+            self.begin_sprint(*minutes, window, cx);
+        }
+        cx.notify();
+    }
+
+    /// Starts the countdown. Like autosave it runs on a window timer, ticking
+    /// every second to redraw the clock until this sprint is over.
+    fn begin_sprint(&mut self, minutes: u32, window: &mut Window, cx: &mut Context<Self>) {
+        if self.config.sprint_minutes != minutes {
+            self.config.sprint_minutes = minutes;
+            self.config.save();
+        }
+        let path = self.tabs[self.active_tab].document.path().to_path_buf();
+        let sprint = Sprint::new(minutes, path);
+        let started = sprint.started;
+        self.sprint = Some(sprint);
+        self.show_plain_text(cx);
+
+        let entity = cx.entity().downgrade();
+        window.spawn(cx, async move |cx: &mut AsyncWindowContext| {
+            loop {
+                cx.background_executor().timer(SPRINT_TICK).await;
+                let running = entity.update_in(cx, |this, window, cx| {
+                    let Some(sprint) = this.sprint.as_ref().filter(|sprint| sprint.started == started) else {
+                        return false;
+                    };
+                    if sprint.remaining().is_zero() {
+                        this.finish_sprint(window, cx);
+                        return false;
+                    }
+                    cx.notify();
+                    true
+                });
+                if !matches!(running, Ok(true)) {
+                    break;
+                }
+            }
+        }).detach();
+        cx.notify();
+    }
+
+    /// A sprint hides everything but the text, including the outline drawn
+    /// in `render` and the editors' tints and underlines.
+    fn show_plain_text(&self, cx: &mut Context<Self>) {
+        let plain = self.sprint.is_some();
+        for tab in &self.tabs {
+            tab.editor.update(cx, |editor, cx| editor.set_plain(plain, cx));
+        }
+    }
+
+    /// Ends the sprint, records the result in the front matter of the
+    /// document it started in, and shows a summary.
+    fn finish_sprint(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(sprint) = self.sprint.take() else { return };
+        self.show_plain_text(cx);
+        let result = sprint.finish();
+        let metadata = result.metadata();
+        if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.document.path() == sprint.path) {
+            let (range, text) = front_matter_entry(tab.editor.read(cx).content(), "sprint", &metadata);
+            tab.editor.update(cx, |editor, cx| editor.replace_quietly(range, &text, cx));
+            tab.document.mark_modified();
+            tab.reset_word_count(cx);
+        } else if let Ok(mut content) = std::fs::read_to_string(&sprint.path) {
+            let (range, text) = front_matter_entry(&content, "sprint", &metadata);
+            content.replace_range(range, &text);
+            if let Err(e) = std::fs::write(&sprint.path, content) {
+                eprintln!("Failed to record sprint: {}", e);
+            }
+        }
+        let sprint_view = cx.new(|cx| SprintView::summary(cx, self.theme.clone(), result));
+        self.show_sprint_view(sprint_view, window, cx);
+    }
+
+    fn new_document(&mut self, _: &NewDocument, window: &mut Window, cx: &mut Context<Self>) {
+        let taken: Vec<_> = self.tabs.iter().map(|tab| tab.document.path()).collect();
+        let document = Document::new_excluding(self.config.documents_dir.clone(), &taken);
+        let tab = Self::new_tab(document, &self.theme, &self.config, self.spell_checker.as_ref(), cx);
+        self.tabs.insert(self.active_tab + 1, tab);
+        self.activate_tab(self.active_tab + 1, window, cx);
+    }
+
+    /// Closes the current document, saving it first. If the save fails the
+    /// document stays open, so nothing unsaved is lost.
+    fn close_document(&mut self, _: &CloseDocument, window: &mut Window, cx: &mut Context<Self>) {
+        if self.save_if_modified(self.active_tab, cx).is_err() {
+            self.show_notice("Save failed, so the document stays open", cx);
+            return;
+        }
+        self.tabs.remove(self.active_tab);
+        if self.tabs.is_empty() {
+            let document = Document::new(self.config.documents_dir.clone());
+            let tab = Self::new_tab(document, &self.theme, &self.config, self.spell_checker.as_ref(), cx);
+            self.tabs.push(tab);
+        }
+        let ix = self.active_tab.min(self.tabs.len() - 1);
+        self.activate_tab(ix, window, cx);
+    }
+
+    fn next_tab(&mut self, _: &NextTab, window: &mut Window, cx: &mut Context<Self>) {
+        let ix = (self.active_tab + 1) % self.tabs.len();
+        self.activate_tab(ix, window, cx);
+    }
+
+    fn previous_tab(&mut self, _: &PreviousTab, window: &mut Window, cx: &mut Context<Self>) {
+        let ix = (self.active_tab + self.tabs.len() - 1) % self.tabs.len();
+        self.activate_tab(ix, window, cx);
     }
 
     fn toggle_theme(&mut self, _: &ToggleTheme, _window: &mut Window, cx: &mut Context<Self>) {
         self.theme = self.theme.toggled();
         self.config.theme = self.theme.mode;
         self.config.save();
-        self.editor.update(cx, |editor, cx| {
-            editor.set_theme(self.theme.clone(), cx);
-        });
+        let theme = self.shown_theme();
+        for tab in &self.tabs {
+            tab.editor.update(cx, |editor, cx| {
+                editor.set_theme(theme.clone(), cx);
+            });
+        }
+        if let Some(outline) = &self.outline {
+            outline.update(cx, |outline, cx| outline.set_theme(theme, cx));
+        }
         cx.notify();
     }
 
+    /// Shows a short message in the header for a few seconds.
+    fn show_notice(&mut self, notice: impl Into<SharedString>, cx: &mut Context<Self>) {
+        let notice = notice.into();
+        self.notice = Some(notice.clone());
+        cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(NOTICE_DURATION).await;
+            let _ = this.update(cx, |this, cx| {
+                if this.notice.as_ref() == Some(&notice) {
+                    this.notice = None;
+                    cx.notify();
+                }
+            });
+        })
+        .detach();
+        cx.notify();
+    }
+
+    fn export(&mut self, format: ExportFormat, cx: &mut Context<Self>) {
+        let tab = &self.tabs[self.active_tab];
+        let content = tab.editor.read(cx).content().to_string();
+        let result = export_document(tab.document.path(), &content, format, &self.theme, None);
+        self.show_export_result(result, cx);
+    }
+
+    fn show_export_result(&mut self, result: std::io::Result<PathBuf>, cx: &mut Context<Self>) {
+        match result {
+            Ok(path) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                self.show_notice(format!("Exported {}", name), cx);
+            }
+            Err(e) => {
+                eprintln!("Failed to export: {}", e);
+                self.show_notice("Export failed", cx);
+            }
+        }
+    }
+
+    fn export_html(&mut self, _: &ExportHtml, _window: &mut Window, cx: &mut Context<Self>) {
+        self.export(ExportFormat::Html, cx);
+    }
+
+    fn export_docx(&mut self, _: &ExportDocx, _window: &mut Window, cx: &mut Context<Self>) {
+        self.export(ExportFormat::Docx, cx);
+    }
+
+    fn export_odt(&mut self, _: &ExportOdt, _window: &mut Window, cx: &mut Context<Self>) {
+        self.export(ExportFormat::Odt, cx);
+    }
+
+    /// Exports every open document, in tab order, as the chapters of one book.
+    fn export_epub(&mut self, _: &ExportEpub, _window: &mut Window, cx: &mut Context<Self>) {
+        let chapters: Vec<_> = self
+            .tabs
+            .iter()
+            .map(|tab| {
+                let content = tab.editor.read(cx).content().to_string();
+                (tab.document.path().to_path_buf(), content)
+            })
+            .collect();
+        let result = export_book(&chapters, ExportFormat::Epub, &self.theme, None);
+        self.show_export_result(result, cx);
+    }
+
     fn open_config(&mut self, _: &OpenConfig, _window: &mut Window, _cx: &mut Context<Self>) {
         let config_path = Config::config_path();
         if !config_path.exists() {
@@ -113,38 +888,196 @@ impl Jid {
         }
         let _ = Command::new("open").arg("-t").arg(&config_path).spawn();
     }
-}
 
-impl Render for Jid {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let modified = self.editor.read(cx).is_modified();
-        let filename = self.document.filename();
-        let title = if modified {
+    fn tab_title(&self, ix: usize, cx: &App) -> String {
+        let tab = &self.tabs[ix];
+        let filename = tab.document.filename();
+        if tab.editor.read(cx).is_modified() {
             format!("{} •", filename)
         } else {
             filename
+        }
+    }
+
+    fn render_header(&self, cx: &mut Context<Self>) -> AnyElement {
+        let vim_mode = self.editor().read(cx).vim_mode().map(|mode| (true, mode.label()));
+        let modes: Vec<_> = vim_mode
+            .into_iter()
+            .chain([
+                (self.config.forward_only, "FORWARD ONLY"),
+                (self.config.write_or_lose, "WRITE OR LOSE"),
+            ])
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, label)| div().text_xs().text_color(self.theme.muted).child(label))
+            .collect();
+        if let Some(sprint) = &self.sprint {
+            return div()
+                .flex()
+                .items_center()
+                .gap_4()
+                .text_color(self.theme.muted)
+                .child(sprint.countdown())
+                .children(modes)
+                .into_any_element();
+        }
+        if let Some(notice) = &self.notice {
+            return div()
+                .text_color(self.theme.muted)
+                .child(notice.clone())
+                .into_any_element();
+        }
+        let title = if self.tabs.len() == 1 {
+            div()
+                .text_color(self.theme.muted)
+                .child(self.tab_title(0, cx))
+        } else {
+            self.render_tabs(cx)
         };
+        let path = self.tabs[self.active_tab].document.path();
+        let goals = [
+            self.config.daily_goal.map(|goal| (self.stats.words_today(), goal)),
+            self.config.document_goal.map(|goal| (self.stats.document_words_today(path), goal)),
+        ];
+        let grade = self
+            .editor()
+            .update(cx, |editor, _| editor.analysis().and_then(Analysis::grade))
+            .map(|grade| {
+                div()
+                    .text_xs()
+                    .text_color(self.theme.muted)
+                    .child(format!("Grade {:.0}", grade))
+            });
 
+        div()
+            .flex()
+            .items_center()
+            .gap_4()
+            .child(title)
+            .children(
+                goals
+                    .into_iter()
+                    .flatten()
+                    .filter(|&(_, goal)| goal > 0)
+                    .map(|(words, goal)| self.render_goal(words, goal)),
+            )
+            .children(grade)
+            .children(modes)
+            .into_any_element()
+    }
+
+    /// A thin bar filling up as today's words approach `goal`.
+    fn render_goal(&self, words: usize, goal: usize) -> impl IntoElement {
+        let progress = (words as f32 / goal as f32).min(1.0);
+        let color = if words >= goal {
+            self.theme.foreground
+        } else {
+            self.theme.muted
+        };
+        div()
+            .flex()
+            .items_center()
+            .gap_2()
+            .text_xs()
+            .text_color(self.theme.muted)
+            .child(
+                div()
+                    .w(px(GOAL_BAR_WIDTH))
+                    .h(px(3.0))
+                    .rounded_full()
+                    .bg(self.theme.muted.opacity(0.25))
+                    .child(div().h_full().w(px(GOAL_BAR_WIDTH * progress)).rounded_full().bg(color)),
+            )
+            .child(format!("{}/{}", words, goal))
+    }
+
+    fn render_tabs(&self, cx: &mut Context<Self>) -> Div {
+        div()
+            .flex()
+            .gap_6()
+            .children((0..self.tabs.len()).map(|ix| {
+                let color = if ix == self.active_tab {
+                    self.theme.foreground
+                } else {
+                    self.theme.muted
+                };
+                div()
+                    .id(("tab", ix))
+                    .cursor_pointer()
+                    .text_color(color)
+                    .child(self.tab_title(ix, cx))
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.activate_tab(ix, window, cx);
+                    }))
+            }))
+    }
+}
+
+/// Floats a panel such as search or stats over the top of the editor.
+fn overlay(panel: impl IntoElement) -> Div {
+    div()
+        .absolute()
+        .top(px(48.0))
+        .left_0()
+        .right_0()
+        .flex()
+        .justify_center()
+        .child(panel)
+}
+
+/// Docks a panel such as the outline along the left edge, below the
+/// header. The editor takes the width that's left rather than sitting
+/// under the panel.
+fn beside_panel(panel: Option<impl IntoElement>, editor: impl IntoElement) -> Div {
+    div()
+        .flex()
+        .flex_1()
+        .w_full()
+        .min_h_0()
+        .children(panel.map(|panel| div().flex_none().h_full().child(panel)))
+        .child(div().flex_1().min_w_0().h_full().overflow_hidden().child(editor))
+}
+
+impl Render for Jid {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = self.shown_theme();
         div()
             .id("jid")
             .key_context("jid")
             .on_action(cx.listener(Self::toggle_theme))
             .on_action(cx.listener(Self::manual_save))
             .on_action(cx.listener(Self::open_config))
+            .on_action(cx.listener(Self::new_document))
+            .on_action(cx.listener(Self::close_document))
+            .on_action(cx.listener(Self::next_tab))
+            .on_action(cx.listener(Self::previous_tab))
+            .on_action(cx.listener(Self::search_library))
+            .on_action(cx.listener(Self::toggle_outline))
+            .on_action(cx.listener(Self::show_stats))
+            .on_action(cx.listener(Self::start_sprint))
+            .on_action(cx.listener(Self::toggle_write_or_lose))
+            .on_action(cx.listener(Self::suggest_spelling))
+            .on_action(cx.listener(Self::export_html))
+            .on_action(cx.listener(Self::export_epub))
+            .on_action(cx.listener(Self::export_docx))
+            .on_action(cx.listener(Self::export_odt))
+            .relative()
             .size_full()
             .flex()
             .flex_col()
             .items_center()
-            .bg(self.theme.background)
-            .text_color(self.theme.foreground)
+            .bg(theme.background)
+            .text_color(theme.foreground)
             .child(
                 div()
                     .pt_2()
                     .pb_4()
                     .text_sm()
-                    .text_color(self.theme.muted)
-                    .child(title)
+                    .child(self.render_header(cx))
             )
-            .child(self.editor.clone())
+            .child(beside_panel(self.outline.clone().filter(|_| self.sprint.is_none()), self.editor().clone()))
+            .children(self.search.clone().map(overlay))
+            .children(self.stats_view.clone().map(overlay))
+            .children(self.sprint_view.clone().map(overlay))
+            .children(self.spelling.clone().map(overlay))
     }
 }
@@ -0,0 +1,281 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use chrono::NaiveDate;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::config::Config;
+use crate::document::{
+    Document, document_date, document_id, document_title, find_document, library_files,
+};
+use crate::export::{ExportFormat, export_book};
+use crate::search::SearchIndex;
+use crate::stats::StatsLog;
+use crate::theme::Theme;
+
+const USAGE: &str = "\
+Usage: jid [COMMAND]
+
+Without a command, jid opens the editor.
+
+Commands:
+  new [--title TITLE]   Create a new document and print its path
+  append [TEXT]         Add a timestamped entry to today's journal (or the
+                        configured inbox), reading stdin when TEXT is omitted
+  list [--since DATE]   List documents, optionally only those since YYYY-MM-DD
+  search QUERY          Search every document for QUERY
+  cat ID                Print a document
+  stats                 Summarize the document library and writing streaks
+  path [ID]             Print the documents folder, or a document's path
+  export ID... [--format FORMAT] [--output FILE] [--chapters FILE]
+                        Export a document; FORMAT is html (the default),
+                        docx, odt or epub, which takes several ids (or a
+                        file listing them, one per line) as chapters of
+                        one book
+  help                  Show this message";
+
+type CliResult = Result<(), String>;
+
+/// Runs a headless subcommand. Returns `None` when the arguments don't name
+/// one, in which case the caller should open the editor instead.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    // macOS passes a process serial number when launched from Finder.
+    if command.starts_with("-psn_") {
+        return None;
+    }
+
+    let config = Config::load();
+    let result = match command.as_str() {
+        "new" => new(&config, rest),
+        "append" => append(&config, rest),
+        "list" => list(&config, rest),
+        "search" => search(&config, rest),
+        "cat" => cat(&config, rest),
+        "stats" => stats(&config, rest),
+        "path" => path(&config, rest),
+        "export" => export(&config, rest),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        "--version" | "-V" => {
+            println!("jid {}", env!("CARGO_PKG_VERSION"));
+            Ok(())
+        }
+        other => Err(format!("unknown command '{}'\n\n{}", other, USAGE)),
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(message) => {
+            eprintln!("jid: {}", message);
+            Some(1)
+        }
+    }
+}
+
+/// Pulls `--name VALUE` out of `args`, returning the value and what's left.
+fn take_option(args: &[String], name: &str) -> Result<(Option<String>, Vec<String>), String> {
+    let mut value = None;
+    let mut rest = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == name {
+            let next = iter.next().ok_or_else(|| format!("{} needs a value", name))?;
+            value = Some(next.clone());
+        } else if let Some(inline) = arg.strip_prefix(&format!("{}=", name)) {
+            value = Some(inline.to_string());
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+    Ok((value, rest))
+}
+
+fn no_extra_args(args: &[String]) -> CliResult {
+    match args.first() {
+        Some(arg) => Err(format!("unexpected argument '{}'", arg)),
+        None => Ok(()),
+    }
+}
+
+fn single_arg<'a>(args: &'a [String], what: &str) -> Result<&'a str, String> {
+    match args {
+        [arg] => Ok(arg),
+        [] => Err(format!("missing {}", what)),
+        [_, extra, ..] => Err(format!("unexpected argument '{}'", extra)),
+    }
+}
+
+fn new(config: &Config, args: &[String]) -> CliResult {
+    let (title, rest) = take_option(args, "--title")?;
+    no_extra_args(&rest)?;
+
+    let mut document = Document::new(config.documents_dir.clone());
+    let content = title.map(|title| format!("# {}\n\n", title)).unwrap_or_default();
+    document
+        .save(&content)
+        .map_err(|e| format!("failed to create {}: {}", document.path().display(), e))?;
+    println!("{}", document.path().display());
+    Ok(())
+}
+
+fn append(config: &Config, args: &[String]) -> CliResult {
+    let text = if args.is_empty() {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("failed to read stdin: {}", e))?;
+        text
+    } else {
+        args.join(" ")
+    };
+    if text.trim().is_empty() {
+        return Err("nothing to append".to_string());
+    }
+
+    let mut document = match &config.inbox {
+        Some(inbox) => Document::at(config.documents_dir.join(inbox)),
+        None => Document::journal(&config.documents_dir),
+    };
+    document
+        .append_entry(&text)
+        .map_err(|e| format!("failed to append to {}: {}", document.path().display(), e))?;
+    println!("{}", document.path().display());
+    Ok(())
+}
+
+fn list(config: &Config, args: &[String]) -> CliResult {
+    let (since, rest) = take_option(args, "--since")?;
+    no_extra_args(&rest)?;
+    let since = since
+        .map(|date| {
+            NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .map_err(|_| format!("invalid date '{}', expected YYYY-MM-DD", date))
+        })
+        .transpose()?;
+
+    for path in library_files(&config.documents_dir) {
+        if since.as_ref().is_some_and(|since| document_date(&path) < *since) {
+            continue;
+        }
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        println!(
+            "{}\t{}\t{}",
+            document_id(&path),
+            content.unicode_words().count(),
+            document_title(&content)
+        );
+    }
+    Ok(())
+}
+
+fn search(config: &Config, args: &[String]) -> CliResult {
+    if args.is_empty() {
+        return Err("missing search query".to_string());
+    }
+    let query = args.join(" ");
+
+    let mut index = SearchIndex::load();
+    index.refresh(&config.documents_dir);
+    index.save();
+    for hit in index.search(&query) {
+        println!("{}:{}\t{}", document_id(&hit.path), hit.offset, hit.snippet);
+    }
+    Ok(())
+}
+
+fn cat(config: &Config, args: &[String]) -> CliResult {
+    let id = single_arg(args, "document id")?;
+    let path = find_document(&config.documents_dir, id)
+        .ok_or_else(|| format!("no document matches '{}'", id))?;
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    print!("{}", content);
+    Ok(())
+}
+
+fn stats(config: &Config, args: &[String]) -> CliResult {
+    no_extra_args(args)?;
+    let files = library_files(&config.documents_dir);
+    let mut words = 0;
+    let mut days = BTreeSet::new();
+    for path in &files {
+        words += fs::read_to_string(path)
+            .map(|content| content.unicode_words().count())
+            .unwrap_or(0);
+        days.insert(document_date(path));
+    }
+
+    println!("Documents:    {}", files.len());
+    println!("Words:        {}", words);
+    println!("Writing days: {}", days.len());
+    if let (Some(first), Some(last)) = (days.first(), days.last()) {
+        println!("First:        {}", first);
+        println!("Latest:       {}", last);
+    }
+
+    let log = StatsLog::load();
+    let (current, longest) = log.streaks();
+    println!("Today:        {} words", log.words_today());
+    println!("Streak:       {} days (longest {})", current, longest);
+    Ok(())
+}
+
+fn path(config: &Config, args: &[String]) -> CliResult {
+    match args {
+        [] => println!("{}", config.documents_dir.display()),
+        [id] => {
+            let path = find_document(&config.documents_dir, id)
+                .ok_or_else(|| format!("no document matches '{}'", id))?;
+            println!("{}", path.display());
+        }
+        [_, extra, ..] => return Err(format!("unexpected argument '{}'", extra)),
+    }
+    Ok(())
+}
+
+fn export(config: &Config, args: &[String]) -> CliResult {
+    let (format, rest) = take_option(args, "--format")?;
+    let (output, rest) = take_option(&rest, "--output")?;
+    let (chapter_list, mut ids) = take_option(&rest, "--chapters")?;
+    let format = match format {
+        Some(name) => ExportFormat::from_name(&name)
+            .ok_or_else(|| format!("unknown export format '{}'", name))?,
+        None => ExportFormat::Html,
+    };
+
+    if let Some(list) = chapter_list {
+        let list = fs::read_to_string(&list).map_err(|e| format!("failed to read {}: {}", list, e))?;
+        ids.extend(
+            list.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string),
+        );
+    }
+    if ids.is_empty() {
+        return Err("missing document id".to_string());
+    }
+    if ids.len() > 1 && format != ExportFormat::Epub {
+        return Err(format!("{} export takes a single document", format.extension()));
+    }
+
+    let mut chapters = Vec::new();
+    for id in &ids {
+        let path = find_document(&config.documents_dir, id)
+            .ok_or_else(|| format!("no document matches '{}'", id))?;
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        chapters.push((path, content));
+    }
+    let theme = Theme::from_mode(config.theme);
+    let written = export_book(&chapters, format, &theme, output.as_deref().map(Path::new))
+        .map_err(|e| format!("failed to export: {}", e))?;
+    println!("{}", written.display());
+    Ok(())
+}
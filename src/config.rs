@@ -5,12 +5,81 @@ use serde::{Deserialize, Serialize};
 
 use crate::theme::ThemeMode;
 
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartupMode {
+    /// Reopen the documents, cursor positions and window from last time.
+    Resume,
+    /// Always start on a fresh blank page.
+    New,
+}
+
+/// What write-or-lose mode does to the words written since the last pause
+/// while its warning builds.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FadeMode {
+    /// Leave them alone; only the colors change.
+    Off,
+    /// Fade them out on screen. The text itself is untouched.
+    Dim,
+    /// Fade them out, then delete them when the warning peaks. Undo brings
+    /// them back.
+    Erase,
+}
+
+/// How the keyboard edits text.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Keymap {
+    /// jid's own shortcuts, as in any Mac text field.
+    Default,
+    /// Vim's normal, insert and visual modes, on top of the shortcuts.
+    Vim,
+    /// Emacs and readline's Ctrl keys for moving, killing and yanking, on
+    /// top of the shortcuts.
+    Emacs,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
     pub theme: ThemeMode,
     pub focus_mode: bool,
+    /// Only the word being typed can be erased, and undo is off.
+    pub forward_only: bool,
+    /// Highlight long sentences, adverbs, passive voice and filler words.
+    pub style_analysis: bool,
+    /// Curl quotes and turn `--` and `...` into an em dash and an ellipsis
+    /// while typing. Quotes follow `spell_language`.
+    pub smart_punctuation: bool,
+    /// Close brackets, quotes, `*` and backticks as they're opened, and
+    /// wrap the selection in them.
+    pub auto_pair: bool,
+    pub keymap: Keymap,
     pub documents_dir: PathBuf,
+    pub startup: StartupMode,
+    /// Where `jid append` writes, relative to `documents_dir` unless
+    /// absolute. When unset it appends to today's journal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inbox: Option<PathBuf>,
+    /// Words to write each day, across all documents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_goal: Option<usize>,
+    /// Words to write each day in any one document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_goal: Option<usize>,
+    /// The length offered when starting a writing sprint.
+    pub sprint_minutes: u32,
+    /// Warn, with colors that slowly turn to alarm, when writing stops.
+    pub write_or_lose: bool,
+    /// Seconds without typing before the warning starts.
+    pub write_or_lose_grace: u64,
+    pub write_or_lose_fade: FadeMode,
+    pub spell_check: bool,
+    /// The Hunspell dictionary to check against, e.g. `en_US` for
+    /// `en_US.aff` and `en_US.dic`.
+    pub spell_language: String,
 }
 
 impl Default for Config {
@@ -18,7 +87,22 @@ impl Default for Config {
         Self {
             theme: ThemeMode::Dark,
             focus_mode: false,
+            forward_only: false,
+            style_analysis: false,
+            smart_punctuation: false,
+            auto_pair: false,
+            keymap: Keymap::Default,
             documents_dir: Self::default_documents_dir(),
+            startup: StartupMode::Resume,
+            inbox: None,
+            daily_goal: None,
+            document_goal: None,
+            sprint_minutes: 25,
+            write_or_lose: false,
+            write_or_lose_grace: 10,
+            write_or_lose_fade: FadeMode::Off,
+            spell_check: false,
+            spell_language: "en_US".to_string(),
         }
     }
 }
@@ -1,27 +1,240 @@
+use std::collections::hash_map::DefaultHasher;
 use std::fs;
-use std::io;
-use std::path::PathBuf;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use chrono::Local;
 
+/// Every markdown document under `documents_dir`, oldest first. Documents
+/// are named by timestamp, so sorting by path sorts them by creation time.
+pub fn library_files(documents_dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_markdown(documents_dir, &mut files);
+    files.sort();
+    files
+}
+
+/// The date a document was written, taken from its `YYYY-MM-DD` folder.
+pub fn document_date(path: &Path) -> String {
+    path.parent()
+        .and_then(|dir| dir.file_name())
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Splits a leading `---` fenced front matter block off a document, returning
+/// its `key: value` pairs and the text after it. Documents without front
+/// matter come back whole with no pairs.
+pub fn front_matter(content: &str) -> (Vec<(&str, &str)>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (Vec::new(), content);
+    };
+
+    let mut pairs = Vec::new();
+    let mut offset = content.len() - rest.len();
+    for line in rest.split_inclusive('\n') {
+        offset += line.len();
+        let line = line.trim_end();
+        if line == "---" || line == "..." {
+            return (pairs, &content[offset..]);
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            pairs.push((key.trim(), value));
+        }
+    }
+    (Vec::new(), content)
+}
+
+/// What to replace, and with what, to set `key: value` in a document's
+/// front matter: the key's line if it already has one, otherwise a new last
+/// entry in an existing block or a new block at the top.
+pub fn front_matter_entry(content: &str, key: &str, value: &str) -> (Range<usize>, String) {
+    let entry = format!("{}: {}\n", key, value);
+    if let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    {
+        let mut offset = content.len() - rest.len();
+        for line in rest.split_inclusive('\n') {
+            let fence = line.trim_end();
+            if fence == "---" || fence == "..." {
+                return (offset..offset, entry);
+            }
+            if line.split_once(':').is_some_and(|(name, _)| name.trim() == key) {
+                let end = offset + line.trim_end_matches(['\n', '\r']).len();
+                return (offset..end, entry.trim_end().to_string());
+            }
+            offset += line.len();
+        }
+    }
+    (0..0, format!("---\n{}---\n\n", entry))
+}
+
+/// The document's `title` front matter, or else its first line of text
+/// without any heading markers.
+pub fn document_title(content: &str) -> &str {
+    let (meta, body) = front_matter(content);
+    if let Some((_, title)) = meta.iter().find(|(key, _)| key.eq_ignore_ascii_case("title")) {
+        return title;
+    }
+    body
+        .lines()
+        .map(|line| line.trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or("")
+}
+
+/// A short handle for a document: its timestamped file stem.
+pub fn document_id(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Looks a document up by path, by id, or by a prefix of its id that
+/// matches exactly one document.
+pub fn find_document(documents_dir: &Path, id: &str) -> Option<PathBuf> {
+    let path = Path::new(id);
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    let files = library_files(documents_dir);
+    if let Some(exact) = files.iter().find(|file| document_id(file) == id) {
+        return Some(exact.clone());
+    }
+    let mut matches = files.into_iter().filter(|file| document_id(file).starts_with(id));
+    match (matches.next(), matches.next()) {
+        (Some(found), None) => Some(found),
+        _ => None,
+    }
+}
+
+fn collect_markdown(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_markdown(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            files.push(path);
+        }
+    }
+}
+
 pub struct Document {
     path: PathBuf,
     modified: bool,
+    disk: Option<DiskState>,
+}
+
+/// What the file looked like the last time this document read or wrote it.
+#[derive(Clone, Copy)]
+struct DiskState {
+    mtime: Option<SystemTime>,
+    len: usize,
+    hash: u64,
+}
+
+impl DiskState {
+    fn of(path: &Path, content: &str) -> Self {
+        Self {
+            mtime: fs::metadata(path).and_then(|meta| meta.modified()).ok(),
+            len: content.len(),
+            hash: hash_text(content),
+        }
+    }
+}
+
+fn hash_text(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// How a document's file changed underneath it since it was last read or saved.
+pub enum DiskChange {
+    /// Text was added to the end, e.g. by `jid append`.
+    Appended(String),
+    /// The file was rewritten; this is its new content.
+    Replaced(String),
 }
 
 impl Document {
     pub fn new(documents_dir: PathBuf) -> Self {
+        Self::new_excluding(documents_dir, &[])
+    }
+
+    /// Like `new`, but picks a filename that is neither on disk nor in
+    /// `taken`, so documents created within the same second don't collide.
+    pub fn new_excluding(documents_dir: PathBuf, taken: &[&Path]) -> Self {
         let now = Local::now();
         let date_folder = now.format("%Y-%m-%d").to_string();
-        let filename = format!("{}.md", now.format("%Y-%m-%d_%H-%M-%S"));
+        let stem = now.format("%Y-%m-%d_%H-%M-%S").to_string();
         let dir = documents_dir.join(&date_folder);
         let _ = fs::create_dir_all(&dir);
+
+        let mut path = dir.join(format!("{}.md", stem));
+        let mut n = 2;
+        while path.exists() || taken.contains(&path.as_path()) {
+            path = dir.join(format!("{}_{}.md", stem, n));
+            n += 1;
+        }
+        Self {
+            path,
+            modified: false,
+            disk: None,
+        }
+    }
+
+    /// Today's journal, the document `jid append` writes to by default.
+    pub fn journal(documents_dir: &Path) -> Self {
+        let date = Local::now().format("%Y-%m-%d").to_string();
+        let path = documents_dir
+            .join(&date)
+            .join(format!("{}_journal.md", date));
+        Self::at(path)
+    }
+
+    /// A document at a fixed path, which may not exist yet.
+    pub fn at(path: PathBuf) -> Self {
         Self {
-            path: dir.join(filename),
+            path,
             modified: false,
+            disk: None,
         }
     }
 
+    pub fn open(path: PathBuf) -> io::Result<(Self, String)> {
+        let content = fs::read_to_string(&path)?;
+        let disk = Some(DiskState::of(&path, &content));
+        Ok((
+            Self {
+                path,
+                modified: false,
+                disk,
+            },
+            content,
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     pub fn filename(&self) -> String {
         self.path
             .file_name()
@@ -40,7 +253,90 @@ impl Document {
         }
         fs::write(&self.path, content)?;
         self.modified = false;
+        self.disk = Some(DiskState::of(&self.path, content));
         Ok(())
     }
 
+    /// Appends a timestamped entry to the end of the file, creating it if needed.
+    pub fn append_entry(&mut self, text: &str) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let existing = fs::read_to_string(&self.path).unwrap_or_default();
+        let separator = if existing.is_empty() || existing.ends_with("\n\n") {
+            ""
+        } else if existing.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        let entry = format!(
+            "{}### {}\n\n{}\n",
+            separator,
+            Local::now().format("%H:%M"),
+            text.trim_end()
+        );
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(entry.as_bytes())
+    }
+
+    /// Checks whether the file changed on disk since this document last read
+    /// or saved it, and if so what changed. Returns `None` when it hasn't.
+    pub fn check_disk(&mut self) -> Option<DiskChange> {
+        let known = self.disk?;
+        let mtime = fs::metadata(&self.path).and_then(|meta| meta.modified()).ok();
+        if mtime == known.mtime {
+            return None;
+        }
+        let content = fs::read_to_string(&self.path).ok()?;
+        let current = DiskState::of(&self.path, &content);
+        self.disk = Some(current);
+        if current.len == known.len && current.hash == known.hash {
+            return None;
+        }
+        let appended = content.len() >= known.len
+            && content.is_char_boundary(known.len)
+            && hash_text(&content[..known.len]) == known.hash;
+        if appended {
+            Some(DiskChange::Appended(content[known.len..].to_string()))
+        } else {
+            Some(DiskChange::Replaced(content))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(content: &str, key: &str, value: &str) -> String {
+        let (range, text) = front_matter_entry(content, key, value);
+        let mut content = content.to_string();
+        content.replace_range(range, &text);
+        content
+    }
+
+    #[test]
+    fn front_matter_entry_starts_a_block() {
+        assert_eq!(set("Hello\n", "sprint", "25 min"), "---\nsprint: 25 min\n---\n\nHello\n");
+    }
+
+    #[test]
+    fn front_matter_entry_adds_to_a_block() {
+        assert_eq!(
+            set("---\ntitle: A\n---\nHello\n", "sprint", "25 min"),
+            "---\ntitle: A\nsprint: 25 min\n---\nHello\n"
+        );
+    }
+
+    #[test]
+    fn front_matter_entry_replaces_the_key() {
+        let once = set("---\nsprint: 10 min\ntitle: A\n---\nHello\n", "sprint", "25 min");
+        assert_eq!(once, "---\nsprint: 25 min\ntitle: A\n---\nHello\n");
+        assert_eq!(set(&once, "sprint", "5 min"), "---\nsprint: 5 min\ntitle: A\n---\nHello\n");
+        assert_eq!(front_matter(&once).0, vec![("sprint", "25 min"), ("title", "A")]);
+    }
 }
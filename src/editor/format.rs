@@ -0,0 +1,248 @@
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A change a formatting command makes: `range` becomes `text`, and then
+/// `selection` is selected.
+pub struct FormatEdit {
+    pub range: Range<usize>,
+    pub text: String,
+    pub selection: Range<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Emphasis {
+    Italic,
+    Bold,
+}
+
+impl Emphasis {
+    fn width(self) -> usize {
+        match self {
+            Emphasis::Italic => 1,
+            Emphasis::Bold => 2,
+        }
+    }
+
+    /// Whether a run of `run` emphasis markers on each side includes this.
+    fn in_run(self, run: usize) -> bool {
+        match self {
+            Emphasis::Italic => run % 2 == 1,
+            Emphasis::Bold => run >= 2,
+        }
+    }
+}
+
+/// The word touching `offset`, if there is one.
+pub fn word_at(content: &str, offset: usize) -> Option<Range<usize>> {
+    let line_start = content[..offset].rfind('\n').map_or(0, |ix| ix + 1);
+    let line_end = content[offset..].find('\n').map_or(content.len(), |ix| offset + ix);
+    content[line_start..line_end]
+        .unicode_word_indices()
+        .map(|(start, word)| line_start + start..line_start + start + word.len())
+        .find(|word| word.start <= offset && offset <= word.end)
+}
+
+/// The range a command works on: the selection, or else the word at the
+/// cursor. An empty range means there's nothing to act on.
+fn target(content: &str, selection: &Range<usize>) -> Range<usize> {
+    if selection.is_empty() {
+        word_at(content, selection.start).unwrap_or(selection.clone())
+    } else {
+        selection.clone()
+    }
+}
+
+/// Adds or removes italic or bold around the selection or the word at the
+/// cursor. Markers already inside the edges of the selection count as
+/// around it, so selecting `**word**` and toggling bold unwraps it.
+pub fn toggle_emphasis(content: &str, selection: Range<usize>, emphasis: Emphasis) -> FormatEdit {
+    let mut target = target(content, &selection);
+    let bytes = content.as_bytes();
+    let marker = |ix: usize| matches!(bytes.get(ix), Some(b'*' | b'_'));
+    while target.len() >= 2 && marker(target.start) && bytes[target.start] == bytes[target.end - 1] {
+        target = target.start + 1..target.end - 1;
+    }
+
+    let mark = match bytes.get(target.start.wrapping_sub(1)) {
+        Some(&b) if marker(target.start.wrapping_sub(1)) => b,
+        _ => b'*',
+    };
+    let before = content[..target.start].bytes().rev().take_while(|&b| b == mark).count();
+    let after = content[target.end..].bytes().take_while(|&b| b == mark).count();
+    let run = before.min(after);
+    let new_run = if emphasis.in_run(run) {
+        run - emphasis.width()
+    } else {
+        run + emphasis.width()
+    };
+
+    let markers = (mark as char).to_string().repeat(new_run);
+    let inner = &content[target.clone()];
+    let range = target.start - run..target.end + run;
+    let text = format!("{}{}{}", markers, inner, markers);
+    // Keep the cursor where it was in the word, or the same text selected.
+    let inner_start = range.start + new_run;
+    let selection = if selection.is_empty() {
+        let cursor = (inner_start + selection.start.saturating_sub(target.start)).min(inner_start + inner.len());
+        cursor..cursor
+    } else {
+        inner_start..inner_start + inner.len()
+    };
+    FormatEdit { range, text, selection }
+}
+
+/// Turns the selection or word into a link. The cursor lands where the URL
+/// goes, or, when the selection is itself a URL or there's nothing to
+/// link, where the link text goes.
+pub fn insert_link(content: &str, selection: Range<usize>) -> FormatEdit {
+    let target = target(content, &selection);
+    let inner = &content[target.clone()];
+    let is_url = inner.contains("://") || inner.starts_with("www.");
+    let (text, cursor) = if is_url || inner.is_empty() {
+        (format!("[]({})", inner), target.start + 1)
+    } else {
+        let text = format!("[{}]()", inner);
+        let cursor = target.start + text.len() - 1;
+        (text, cursor)
+    };
+    FormatEdit {
+        range: target,
+        text,
+        selection: cursor..cursor,
+    }
+}
+
+/// The heading level of a line, from its `#` markers, and how many bytes
+/// of the line they take up with the space after them.
+fn heading_level(line: &str) -> (usize, usize) {
+    let hashes = line.bytes().take_while(|&b| b == b'#').count();
+    let rest = &line[hashes..];
+    if (1..=6).contains(&hashes) && (rest.is_empty() || rest.starts_with(' ')) {
+        let spaces = rest.len() - rest.trim_start_matches(' ').len();
+        (hashes, hashes + spaces)
+    } else {
+        (0, 0)
+    }
+}
+
+/// Makes the line at `cursor` a heading of the given level, or body text
+/// for level 0. Asking for the level it already has makes it body text.
+/// With no level, steps to the next level down, and from the sixth back
+/// to body text.
+pub fn set_heading(content: &str, cursor: usize, level: Option<usize>) -> FormatEdit {
+    let line_start = content[..cursor].rfind('\n').map_or(0, |ix| ix + 1);
+    let line_end = content[cursor..].find('\n').map_or(content.len(), |ix| cursor + ix);
+    let (current, prefix_len) = heading_level(&content[line_start..line_end]);
+    let level = match level {
+        Some(level) if level == current => 0,
+        Some(level) => level.min(6),
+        None => (current + 1) % 7,
+    };
+    let text = if level == 0 {
+        String::new()
+    } else {
+        format!("{} ", "#".repeat(level))
+    };
+    let range = line_start..line_start + prefix_len;
+    let cursor = (cursor.max(range.end) + text.len()) - prefix_len;
+    FormatEdit {
+        range,
+        text,
+        selection: cursor..cursor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Splits `|` for a cursor, or `[` and `]` around a selection, out of
+    /// `marked`.
+    fn parse(marked: &str) -> (String, Range<usize>) {
+        if let Some(cursor) = marked.find('|') {
+            return (marked.replacen('|', "", 1), cursor..cursor);
+        }
+        let start = marked.find('[').unwrap();
+        let end = marked.find(']').unwrap() - 1;
+        (marked.replacen('[', "", 1).replacen(']', "", 1), start..end)
+    }
+
+    /// The text after `edit`, with its selection marked the way `parse` reads it.
+    fn show(content: &str, edit: FormatEdit) -> String {
+        let mut text = content.to_string();
+        text.replace_range(edit.range, &edit.text);
+        if edit.selection.is_empty() {
+            text.insert(edit.selection.start, '|');
+        } else {
+            text.insert(edit.selection.end, ']');
+            text.insert(edit.selection.start, '[');
+        }
+        text
+    }
+
+    fn toggled(marked: &str, emphasis: Emphasis) -> String {
+        let (content, selection) = parse(marked);
+        show(&content, toggle_emphasis(&content, selection, emphasis))
+    }
+
+    fn linked(marked: &str) -> String {
+        let (content, selection) = parse(marked);
+        show(&content, insert_link(&content, selection))
+    }
+
+    fn headed(marked: &str, level: Option<usize>) -> String {
+        let (content, selection) = parse(marked);
+        show(&content, set_heading(&content, selection.start, level))
+    }
+
+    #[test]
+    fn emphasis_wraps_the_word_at_the_cursor() {
+        assert_eq!(toggled("a wo|rd b", Emphasis::Bold), "a **wo|rd** b");
+        assert_eq!(toggled("a wo|rd b", Emphasis::Italic), "a *wo|rd* b");
+    }
+
+    #[test]
+    fn emphasis_adds_to_or_removes_from_existing_markers() {
+        assert_eq!(toggled("a *wo|rd* b", Emphasis::Italic), "a wo|rd b");
+        assert_eq!(toggled("a *wo|rd* b", Emphasis::Bold), "a ***wo|rd*** b");
+        assert_eq!(toggled("a **wo|rd** b", Emphasis::Bold), "a wo|rd b");
+        assert_eq!(toggled("a **wo|rd** b", Emphasis::Italic), "a ***wo|rd*** b");
+        assert_eq!(toggled("a ***wo|rd*** b", Emphasis::Italic), "a **wo|rd** b");
+        assert_eq!(toggled("a ***wo|rd*** b", Emphasis::Bold), "a *wo|rd* b");
+    }
+
+    #[test]
+    fn emphasis_unwraps_a_selection_that_includes_its_markers() {
+        assert_eq!(toggled("a [**word**] b", Emphasis::Bold), "a [word] b");
+    }
+
+    #[test]
+    fn emphasis_away_from_a_word_leaves_the_cursor_between_markers() {
+        assert_eq!(toggled("a | b", Emphasis::Bold), "a **|** b");
+        assert_eq!(toggled("a | b", Emphasis::Italic), "a *|* b");
+        assert_eq!(toggled("a **|** b", Emphasis::Bold), "a | b");
+    }
+
+    #[test]
+    fn links_put_the_cursor_where_the_missing_part_goes() {
+        assert_eq!(linked("a wo|rd b"), "a [word](|) b");
+        assert_eq!(linked("go [https://x.org] now"), "go [|](https://x.org) now");
+        assert_eq!(linked("a | b"), "a [|]() b");
+    }
+
+    #[test]
+    fn headings_set_change_and_clear_the_level() {
+        assert_eq!(headed("Ti|tle\nbody", Some(2)), "## Ti|tle\nbody");
+        assert_eq!(headed("## Ti|tle", Some(1)), "# Ti|tle");
+        assert_eq!(headed("## Ti|tle", Some(2)), "Ti|tle");
+        assert_eq!(headed("#|# Title", Some(3)), "### |Title");
+    }
+
+    #[test]
+    fn heading_cycle_wraps_from_six_to_body_text() {
+        assert_eq!(headed("Ti|tle", None), "# Ti|tle");
+        assert_eq!(headed("##### Ti|tle", None), "###### Ti|tle");
+        assert_eq!(headed("###### De|ep", None), "De|ep");
+    }
+}
@@ -0,0 +1,808 @@
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Elements that never have children or an end tag.
+const VOID: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+/// Elements whose contents aren't text to paste.
+const SKIPPED: [&str; 6] = ["head", "noscript", "script", "style", "template", "title"];
+/// How long to wait for the clipboard's HTML before pasting plain text.
+/// `xclip` can hang when the app that owns the clipboard doesn't answer.
+const CLIPBOARD_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The HTML on the clipboard, when whatever was copied offered some. GPUI
+/// only hands over plain text, so this asks the system for the HTML
+/// flavour directly. It runs a helper program, so call it off the UI
+/// thread.
+pub fn clipboard_html() -> Option<String> {
+    if cfg!(target_os = "macos") {
+        // AppleScript prints the data as «data HTML3C68746D6C3E…».
+        let printed = run_with_timeout(Command::new("osascript").args(["-e", "the clipboard as «class HTML»"]))?;
+        let printed = String::from_utf8_lossy(&printed);
+        let hex = printed.trim().strip_prefix("«data HTML")?.strip_suffix('»')?;
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        return Some(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    let output = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        run_with_timeout(Command::new("wl-paste").args(["--no-newline", "--type", "text/html"]))
+    } else {
+        run_with_timeout(Command::new("xclip").args(["-selection", "clipboard", "-target", "text/html", "-out"]))
+    }?;
+    Some(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// What `command` prints, if it succeeds within `CLIPBOARD_TIMEOUT`. One
+/// that takes longer is killed.
+fn run_with_timeout(command: &mut Command) -> Option<Vec<u8>> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    // Read as it prints, so a large clipboard can't fill the pipe and stall it.
+    let mut stdout = child.stdout.take()?;
+    let reader = thread::spawn(move || {
+        let mut printed = Vec::new();
+        stdout.read_to_end(&mut printed).map(|_| printed)
+    });
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < CLIPBOARD_TIMEOUT => thread::sleep(Duration::from_millis(10)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    };
+    let printed = reader.join().ok()?.ok()?;
+    status.success().then_some(printed)
+}
+
+/// Converts copied HTML to markdown: headings, lists, quotes, links,
+/// emphasis, code and tables. Returns `None` when the HTML has none of
+/// those, as when it only wraps plain text in styling, so the clipboard's
+/// own plain text can be pasted instead.
+pub fn html_to_markdown(html: &str) -> Option<String> {
+    let root = parse(html);
+    let mut writer = Writer::default();
+    let markdown = writer.blocks(&root.children).join("\n\n");
+    (writer.formatted && !markdown.trim().is_empty()).then(|| markdown.trim().to_string())
+}
+
+enum Node {
+    Text(String),
+    Element(Element),
+}
+
+#[derive(Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The `font-weight` or `font-style` an inline style sets, which is how
+    /// word processors mark bold and italic.
+    fn style(&self, property: &str) -> Option<String> {
+        self.attribute("style")?.split(';').find_map(|declaration| {
+            let (key, value) = declaration.split_once(':')?;
+            (key.trim().eq_ignore_ascii_case(property)).then(|| value.trim().to_ascii_lowercase())
+        })
+    }
+
+    fn is_bold(&self) -> bool {
+        let weight = self.style("font-weight");
+        match self.name.as_str() {
+            // Google Docs wraps everything it copies in a `<b>` that isn't.
+            "b" | "strong" => weight.as_deref() != Some("normal"),
+            _ => weight.is_some_and(|weight| weight == "bold" || weight.parse::<u16>().is_ok_and(|w| w >= 600)),
+        }
+    }
+
+    fn is_italic(&self) -> bool {
+        matches!(self.name.as_str(), "em" | "i" | "cite") || self.style("font-style").as_deref() == Some("italic")
+    }
+}
+
+/// Whether `name` starts a block of its own rather than flowing with the
+/// text around it.
+fn is_block(name: &str) -> bool {
+    matches!(
+        name,
+        "address"
+            | "article"
+            | "aside"
+            | "blockquote"
+            | "body"
+            | "dd"
+            | "div"
+            | "dl"
+            | "dt"
+            | "figcaption"
+            | "figure"
+            | "footer"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "header"
+            | "hr"
+            | "html"
+            | "li"
+            | "main"
+            | "nav"
+            | "ol"
+            | "p"
+            | "pre"
+            | "section"
+            | "table"
+            | "tbody"
+            | "tfoot"
+            | "thead"
+            | "tr"
+            | "ul"
+    )
+}
+
+/// Whether an element has blocks inside it, like the paragraphs in Google
+/// Docs' wrapper, and so is better read as a block itself.
+fn holds_blocks(element: &Element) -> bool {
+    element.children.iter().any(|child| match child {
+        Node::Element(child) => is_block(&child.name) || holds_blocks(child),
+        Node::Text(_) => false,
+    })
+}
+
+/// Builds a tree from `html`, forgiving the way browsers are: unclosed
+/// paragraphs, list items and cells end when the next one starts, and stray
+/// end tags are ignored.
+fn parse(html: &str) -> Element {
+    // Windows puts a header of offsets before the markup itself.
+    let mut rest = &html[html.find('<').unwrap_or(html.len())..];
+    let mut stack = vec![Element::default()];
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+        } else if let Some(tag) = rest.strip_prefix("</") {
+            let end = tag.find('>').unwrap_or(tag.len());
+            let name = tag[..end].trim().to_ascii_lowercase();
+            if let Some(ix) = stack.iter().rposition(|open| open.name == name) {
+                close(&mut stack, ix);
+            }
+            rest = tag.get(end + 1..).unwrap_or("");
+        } else if rest.starts_with('<') && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let (element, self_closing, after) = start_tag(&rest[1..]);
+            rest = after;
+            if SKIPPED.contains(&element.name.as_str()) {
+                let closing = format!("</{}", element.name);
+                rest = rest.to_ascii_lowercase().find(&closing).map_or("", |end| &rest[end..]);
+                continue;
+            }
+            end_implied(&mut stack, &element.name);
+            if self_closing || VOID.contains(&element.name.as_str()) {
+                stack.last_mut().unwrap().children.push(Node::Element(element));
+            } else {
+                stack.push(element);
+            }
+        } else {
+            let end = rest
+                .char_indices()
+                .skip(1)
+                .find(|&(_, c)| c == '<')
+                .map_or(rest.len(), |(ix, _)| ix);
+            let text = decode_entities(&rest[..end]);
+            stack.last_mut().unwrap().children.push(Node::Text(text));
+            rest = &rest[end..];
+        }
+    }
+    close(&mut stack, 1);
+    stack.pop().unwrap()
+}
+
+/// Ends the open element at `ix` in the stack and everything inside it.
+fn close(stack: &mut Vec<Element>, ix: usize) {
+    while stack.len() > ix.max(1) {
+        let element = stack.pop().unwrap();
+        stack.last_mut().unwrap().children.push(Node::Element(element));
+    }
+}
+
+/// Ends the elements that starting a `name` element closes without an end
+/// tag: a list item ends the one before it, a block ends a paragraph.
+fn end_implied(stack: &mut Vec<Element>, name: &str) {
+    let (peers, scope): (&[&str], &[&str]) = match name {
+        "li" => (&["li"], &["ul", "ol"]),
+        "dt" | "dd" => (&["dt", "dd"], &["dl"]),
+        "tr" => (&["tr"], &["table"]),
+        "td" | "th" => (&["td", "th"], &["tr", "table"]),
+        name if is_block(name) => (&["p"], &["div", "li", "td", "th", "blockquote", "body"]),
+        _ => return,
+    };
+    let open = stack
+        .iter()
+        .rposition(|open| peers.contains(&open.name.as_str()) || scope.contains(&open.name.as_str()));
+    if let Some(ix) = open.filter(|&ix| peers.contains(&stack[ix].name.as_str())) {
+        close(stack, ix);
+    }
+}
+
+/// Reads a start tag from just after its `<`, returning the element, whether
+/// it closed itself with `/>`, and the text after it.
+fn start_tag(tag: &str) -> (Element, bool, &str) {
+    let name_end = tag
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(tag.len());
+    let mut element = Element {
+        name: tag[..name_end].to_ascii_lowercase(),
+        ..Element::default()
+    };
+    let mut rest = &tag[name_end..];
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            return (element, true, after);
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            return (element, false, after);
+        }
+        if rest.is_empty() {
+            return (element, false, rest);
+        }
+        if let Some(after) = rest.strip_prefix('/') {
+            rest = after;
+            continue;
+        }
+        let key_end = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/'))
+            .unwrap_or(rest.len())
+            .max(1);
+        let key = rest[..key_end].to_ascii_lowercase();
+        rest = rest[key_end..].trim_start();
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after[1..];
+                    let end = inner.find(quote).unwrap_or(inner.len());
+                    (&inner[..end], inner.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining;
+        }
+        element.attributes.push((key, value));
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            "ndash" => Some('–'),
+            "mdash" => Some('—'),
+            "hellip" => Some('…'),
+            "lsquo" => Some('‘'),
+            "rsquo" => Some('’'),
+            "ldquo" => Some('“'),
+            "rdquo" => Some('”'),
+            "bull" => Some('•'),
+            "middot" => Some('·'),
+            "copy" => Some('©'),
+            "reg" => Some('®'),
+            "trade" => Some('™'),
+            _ => {
+                let number = entity.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded.replace('\u{a0}', " ")
+}
+
+/// Writes markdown for a tree, noting whether it found anything plain text
+/// couldn't have said.
+#[derive(Default)]
+struct Writer {
+    formatted: bool,
+    bold: bool,
+    italic: bool,
+}
+
+impl Writer {
+    /// Markdown blocks for a run of nodes, with text between block elements
+    /// gathered into paragraphs.
+    fn blocks(&mut self, nodes: &[Node]) -> Vec<String> {
+        let mut blocks = Vec::new();
+        let mut text = String::new();
+        for node in nodes {
+            match node {
+                Node::Element(element) if is_block(&element.name) || holds_blocks(element) => {
+                    push_paragraph(&mut blocks, &mut text);
+                    self.block(element, &mut blocks);
+                }
+                node => text.push_str(&self.inline(node)),
+            }
+        }
+        push_paragraph(&mut blocks, &mut text);
+        blocks
+    }
+
+    fn block(&mut self, element: &Element, blocks: &mut Vec<String>) {
+        match element.name.as_str() {
+            name @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+                // Headings are bold already, so bold inside them is dropped.
+                let bold = self.bold;
+                self.bold = true;
+                let text = tidy(&self.inline_children(&element.children)).replace('\n', " ");
+                self.bold = bold;
+                if !text.is_empty() {
+                    self.formatted = true;
+                    let level = name[1..].parse().unwrap_or(1);
+                    blocks.push(format!("{} {}", "#".repeat(level), text));
+                }
+            }
+            "ul" | "ol" => {
+                let list = self.list(element);
+                if !list.is_empty() {
+                    self.formatted = true;
+                    blocks.push(list);
+                }
+            }
+            "blockquote" => {
+                let quoted = self.blocks(&element.children).join("\n\n");
+                if !quoted.is_empty() {
+                    self.formatted = true;
+                    let lines: Vec<String> = quoted
+                        .lines()
+                        .map(|line| {
+                            if line.is_empty() {
+                                ">".to_string()
+                            } else {
+                                format!("> {}", line)
+                            }
+                        })
+                        .collect();
+                    blocks.push(lines.join("\n"));
+                }
+            }
+            "pre" => {
+                let code = text_content(&element.children);
+                let code = code.strip_prefix('\n').unwrap_or(&code).trim_end();
+                if !code.is_empty() {
+                    self.formatted = true;
+                    blocks.push(format!("```\n{}\n```", code));
+                }
+            }
+            "table" => {
+                let table = self.table(element);
+                if !table.is_empty() {
+                    self.formatted = true;
+                    blocks.push(table);
+                }
+            }
+            "hr" => {
+                self.formatted = true;
+                blocks.push("---".to_string());
+            }
+            _ => blocks.extend(self.blocks(&element.children)),
+        }
+    }
+
+    /// A list, one line per item, with whatever else an item holds (a
+    /// nested list, a second paragraph) indented under it.
+    fn list(&mut self, list: &Element) -> String {
+        let mut number = list
+            .attribute("start")
+            .and_then(|start| start.parse().ok())
+            .unwrap_or(1);
+        let mut items = Vec::new();
+        for node in &list.children {
+            let Node::Element(item) = node else { continue };
+            let contents = if item.name == "li" {
+                self.blocks(&item.children)
+            } else {
+                // A list nested straight inside another, as some editors write.
+                let mut blocks = Vec::new();
+                self.block(item, &mut blocks);
+                blocks
+            };
+            let contents = contents.join("\n");
+            if contents.is_empty() {
+                continue;
+            }
+            let marker = if list.name == "ol" {
+                format!("{}. ", number)
+            } else {
+                "- ".to_string()
+            };
+            number += 1;
+            let indent = " ".repeat(marker.len());
+            let lines: Vec<String> = contents
+                .lines()
+                .enumerate()
+                .map(|(ix, line)| match ix {
+                    0 => format!("{}{}", marker, line),
+                    _ if line.is_empty() => String::new(),
+                    _ => format!("{}{}", indent, line),
+                })
+                .collect();
+            items.push(lines.join("\n"));
+        }
+        items.join("\n")
+    }
+
+    /// A pipe table, its first row the header.
+    fn table(&mut self, table: &Element) -> String {
+        let mut rows = Vec::new();
+        self.table_rows(&table.children, &mut rows);
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return String::new();
+        }
+        let mut lines = Vec::new();
+        for (ix, mut row) in rows.into_iter().enumerate() {
+            row.resize(columns, String::new());
+            lines.push(format!("| {} |", row.join(" | ")));
+            if ix == 0 {
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn table_rows(&mut self, nodes: &[Node], rows: &mut Vec<Vec<String>>) {
+        for node in nodes {
+            let Node::Element(element) = node else { continue };
+            match element.name.as_str() {
+                "thead" | "tbody" | "tfoot" => self.table_rows(&element.children, rows),
+                "tr" => {
+                    let cells = element
+                        .children
+                        .iter()
+                        .filter_map(|cell| match cell {
+                            Node::Element(cell) if cell.name == "td" || cell.name == "th" => Some(cell),
+                            _ => None,
+                        })
+                        .map(|cell| {
+                            self.blocks(&cell.children)
+                                .join(" ")
+                                .replace('\n', " ")
+                                .replace('|', "\\|")
+                        })
+                        .collect();
+                    rows.push(cells);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn inline_children(&mut self, nodes: &[Node]) -> String {
+        nodes.iter().map(|node| self.inline(node)).collect()
+    }
+
+    /// A node as it reads within a paragraph. Whitespace is collapsed as a
+    /// browser would, and `<br>` becomes a line break.
+    fn inline(&mut self, node: &Node) -> String {
+        let element = match node {
+            Node::Text(text) => return escape(&collapse(text)),
+            Node::Element(element) => element,
+        };
+        match element.name.as_str() {
+            "br" => "\n".to_string(),
+            "img" => {
+                let src = element.attribute("src").unwrap_or("");
+                if src.is_empty() || src.starts_with("data:") {
+                    return String::new();
+                }
+                self.formatted = true;
+                format!("![{}]({})", escape(element.attribute("alt").unwrap_or("")), src)
+            }
+            "a" => {
+                let text = self.inline_children(&element.children);
+                let href = element.attribute("href").unwrap_or("");
+                if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") || text.trim().is_empty()
+                {
+                    return text;
+                }
+                self.formatted = true;
+                wrap(&text, "[", &format!("]({})", href.replace(' ', "%20")))
+            }
+            "code" | "kbd" | "samp" => {
+                let code = text_content(&element.children);
+                if code.trim().is_empty() {
+                    return code;
+                }
+                self.formatted = true;
+                let fence = if code.contains('`') { "``" } else { "`" };
+                wrap(&code.replace('\n', " "), fence, fence)
+            }
+            "del" | "s" | "strike" => {
+                let text = self.inline_children(&element.children);
+                self.formatted |= !text.trim().is_empty();
+                wrap(&text, "~~", "~~")
+            }
+            _ if (element.is_bold() && !self.bold) || (element.is_italic() && !self.italic) => {
+                let bold = element.is_bold() && !self.bold;
+                let italic = element.is_italic() && !self.italic;
+                self.bold |= bold;
+                self.italic |= italic;
+                let mut text = self.inline_children(&element.children);
+                self.bold &= !bold;
+                self.italic &= !italic;
+                self.formatted |= !text.trim().is_empty();
+                if italic {
+                    text = wrap(&text, "*", "*");
+                }
+                if bold {
+                    text = wrap(&text, "**", "**");
+                }
+                text
+            }
+            _ if is_block(&element.name) => {
+                // A block inside inline markup, such as a `<div>` in a link.
+                format!(" {} ", self.blocks(&element.children).join(" "))
+            }
+            _ => self.inline_children(&element.children),
+        }
+    }
+}
+
+/// Gathered paragraph text, tidied, as a block of its own.
+fn push_paragraph(blocks: &mut Vec<String>, text: &mut String) {
+    let paragraph = tidy(text);
+    if !paragraph.is_empty() {
+        blocks.push(
+            paragraph
+                .split('\n')
+                .map(escape_line_start)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+    text.clear();
+}
+
+/// Backslashes the characters in copied text that markdown would read as
+/// emphasis, code, links or HTML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let tag = c == '<'
+            && chars
+                .peek()
+                .is_some_and(|next| next.is_ascii_alphabetic() || "/!?".contains(*next));
+        if tag || matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '~') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Backslashes whatever would make a line of a paragraph a heading, quote,
+/// list item or rule.
+fn escape_line_start(line: &str) -> String {
+    let run = |byte: u8| line.bytes().take_while(|&b| b == byte).count();
+    let space_after = |ix: usize| line[ix..].chars().next().is_none_or(char::is_whitespace);
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    let marker = match line.bytes().next() {
+        Some(b'#') if run(b'#') <= 6 && space_after(run(b'#')) => Some(0),
+        Some(b'>') => Some(0),
+        Some(b @ (b'-' | b'+' | b'=')) if space_after(1) || run(b) == line.len() => Some(0),
+        Some(b'0'..=b'9') if matches!(line.as_bytes().get(digits), Some(b'.' | b')')) && space_after(digits + 1) => {
+            Some(digits)
+        }
+        _ => None,
+    };
+    match marker {
+        Some(ix) => format!("{}\\{}", &line[..ix], &line[ix..]),
+        None => line.to_string(),
+    }
+}
+
+/// Trims each line and squeezes the spaces left where inline elements met.
+fn tidy(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            line.split(' ')
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+/// Squeezes each run of whitespace to a single space, keeping one at either
+/// end to separate the text from the elements beside it.
+fn collapse(text: &str) -> String {
+    let words = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if words.is_empty() {
+        return if text.is_empty() {
+            String::new()
+        } else {
+            " ".to_string()
+        };
+    }
+    let leading = if text.starts_with(char::is_whitespace) { " " } else { "" };
+    let trailing = if text.ends_with(char::is_whitespace) { " " } else { "" };
+    format!("{}{}{}", leading, words, trailing)
+}
+
+/// Puts markers around `text`, outside any space it starts or ends with,
+/// since `** bold **` isn't bold.
+fn wrap(text: &str, open: &str, close: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_string();
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+    format!("{}{}{}{}{}", leading, open, trimmed, close, trailing)
+}
+
+/// The text inside some nodes exactly as written, for code.
+fn text_content(nodes: &[Node]) -> String {
+    nodes
+        .iter()
+        .map(|node| match node {
+            Node::Text(text) => text.clone(),
+            Node::Element(element) if element.name == "br" => "\n".to_string(),
+            Node::Element(element) => text_content(&element.children),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markdown(html: &str) -> String {
+        html_to_markdown(html).unwrap_or_else(|| panic!("no markdown for {html:?}"))
+    }
+
+    #[test]
+    fn plain_text_is_left_to_the_clipboard() {
+        assert_eq!(
+            html_to_markdown("<p>Just <span style=\"color:red\">words</span></p>"),
+            None
+        );
+        assert_eq!(html_to_markdown("<meta charset=utf-8><b></b>"), None);
+        assert_eq!(html_to_markdown(""), None);
+    }
+
+    #[test]
+    fn headings_paragraphs_and_rules() {
+        assert_eq!(
+            markdown("<h1>Title</h1><p>One\n  paragraph.</p><hr><h3>Sub <b>bold</b></h3><p>Two<br>lines</p>"),
+            "# Title\n\nOne paragraph.\n\n---\n\n### Sub bold\n\nTwo\nlines"
+        );
+    }
+
+    #[test]
+    fn emphasis_links_and_code() {
+        assert_eq!(
+            markdown("<p><b>bold</b> <em>it </em><a href=\"https://a.b/c d\">link</a> <code>x*y</code> <s>old</s></p>"),
+            "**bold** *it* [link](https://a.b/c%20d) `x*y` ~~old~~"
+        );
+        assert_eq!(
+            markdown("<p><a href=\"#top\">skip</a> <img src=\"i.png\" alt=\"[pic]\"></p>"),
+            "skip ![\\[pic\\]](i.png)"
+        );
+        assert_eq!(
+            markdown("<pre><code>\nfn main() {\n    x\n}\n</code></pre>"),
+            "```\nfn main() {\n    x\n}\n```"
+        );
+    }
+
+    #[test]
+    fn word_processor_styles_and_wrappers() {
+        // Google Docs wraps everything in a bold that isn't, and styles spans.
+        let docs = "<b style=\"font-weight:normal\" id=\"docs\"><p><span style=\"font-weight:700\">Strong</span> \
+                    and <span style=\"font-style:italic\">slanted</span></p><p>Next</p></b>";
+        assert_eq!(markdown(docs), "**Strong** and *slanted*\n\nNext");
+    }
+
+    #[test]
+    fn lists_nest_and_keep_their_numbers() {
+        let html = "<ol start=\"3\"><li>three<ul><li>inner</li></ul></li><li>four</ol><ul><li><p>a</p><p>b</p></ul>";
+        assert_eq!(markdown(html), "3. three\n   - inner\n4. four\n\n- a\n  b");
+    }
+
+    #[test]
+    fn quotes_and_tables() {
+        assert_eq!(
+            markdown("<blockquote><p>One</p><p>Two</p></blockquote>"),
+            "> One\n>\n> Two"
+        );
+        let table = "<table><thead><tr><th>A</th><th>B</th></tr></thead>\
+                     <tbody><tr><td>1|2</td></tr></tbody></table>";
+        assert_eq!(markdown(table), "| A | B |\n| --- | --- |\n| 1\\|2 |  |");
+    }
+
+    #[test]
+    fn text_that_looks_like_markdown_is_escaped() {
+        assert_eq!(
+            markdown("<h2>x</h2><p>2 * 3 = 6, snake_case, [not a link] and `ticks` &lt;b&gt; a < b</p>"),
+            "## x\n\n2 \\* 3 = 6, snake\\_case, \\[not a link\\] and \\`ticks\\` \\<b> a < b"
+        );
+        assert_eq!(
+            markdown("<h2>x</h2><p># Not a heading</p><p>1. Not a list<br>- nor this<br>&gt; nor this</p><p>---</p>"),
+            "## x\n\n\\# Not a heading\n\n1\\. Not a list\n\\- nor this\n\\> nor this\n\n\\---"
+        );
+        assert_eq!(
+            markdown("<ul><li>#hashtag</li><li>2) two</li></ul>"),
+            "- #hashtag\n- 2\\) two"
+        );
+    }
+
+    #[test]
+    fn parsing_copes_with_sloppy_html() {
+        assert_eq!(
+            markdown("<!-- c --><style>p{}</style><ul><li>one<li>two &amp; &#233;&nbsp;three</ul><p>a<p>b"),
+            "- one\n- two & é three\n\na\n\nb"
+        );
+        assert_eq!(markdown("<h1>Ünïcödé — ok</h1>"), "# Ünïcödé — ok");
+    }
+}
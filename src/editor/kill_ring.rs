@@ -0,0 +1,58 @@
+/// How many kills are kept before the oldest is dropped.
+const MAX_KILLS: usize = 60;
+
+/// Text recently killed, cut or copied, newest last, for yanking back
+/// with Ctrl+Y and cycling through with Alt+Y.
+#[derive(Default)]
+pub struct KillRing {
+    kills: Vec<String>,
+    /// How many kills back from the newest the last yank reached.
+    yank: usize,
+}
+
+impl KillRing {
+    pub fn push(&mut self, text: String) {
+        if text.is_empty() || self.kills.last() == Some(&text) {
+            return;
+        }
+        self.kills.push(text);
+        if self.kills.len() > MAX_KILLS {
+            self.kills.remove(0);
+        }
+    }
+
+    /// Adds `text` to the end of the newest kill, so killing several times
+    /// in a row yanks back as one.
+    pub fn append(&mut self, text: &str) {
+        match self.kills.last_mut() {
+            Some(last) => last.push_str(text),
+            None => self.push(text.to_string()),
+        }
+    }
+
+    pub fn newest(&self) -> Option<&str> {
+        self.kills.last().map(String::as_str)
+    }
+
+    /// Notes that `text` was just yanked from the clipboard. Text copied in
+    /// another app joins the ring, so cycling from it reaches jid's kills.
+    pub fn yanked(&mut self, text: &str) {
+        self.push(text.to_string());
+        self.yank = 0;
+    }
+
+    /// The kill the last yank put in, if the ring still has it.
+    pub fn current(&self) -> Option<&str> {
+        let ix = self.kills.len().checked_sub(self.yank + 1)?;
+        Some(&self.kills[ix])
+    }
+
+    /// Steps to the next older kill, wrapping round to the newest.
+    pub fn rotate(&mut self) -> Option<&str> {
+        if self.kills.is_empty() {
+            return None;
+        }
+        self.yank = (self.yank + 1) % self.kills.len();
+        self.current()
+    }
+}
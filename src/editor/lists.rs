@@ -0,0 +1,439 @@
+use std::ops::Range;
+
+/// A change a list command makes: each range becomes its text, and the
+/// cursor ends up at `cursor`. The ranges are in order and don't overlap,
+/// so only what actually changes is touched.
+pub struct ListEdit {
+    pub edits: Vec<(Range<usize>, String)>,
+    pub cursor: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Marker {
+    Bullet(char),
+    Ordered { number: u64, delimiter: char },
+}
+
+/// The parts of a line in front of its text: blockquote markers, the
+/// indentation inside them, and a list marker with an optional task box.
+struct Prefix<'a> {
+    quote: &'a str,
+    indent: &'a str,
+    marker: Option<Marker>,
+    task: bool,
+    len: usize,
+}
+
+impl Prefix<'_> {
+    fn is_structural(&self) -> bool {
+        self.marker.is_some() || !self.quote.is_empty()
+    }
+
+    /// What the next line starts with: the same quote and indent, the next
+    /// number or the same bullet, and an unchecked box for tasks.
+    fn continuation(&self) -> String {
+        let mut next = format!("{}{}", self.quote, self.indent);
+        match self.marker {
+            Some(Marker::Bullet(bullet)) => next.push_str(&format!("{} ", bullet)),
+            Some(Marker::Ordered { number, delimiter }) => next.push_str(&format!("{}{} ", number + 1, delimiter)),
+            None => {}
+        }
+        if self.task {
+            next.push_str("[ ] ");
+        }
+        next
+    }
+
+    /// How far the text of this item sits from the start of its indent.
+    fn marker_width(&self) -> usize {
+        self.len - self.quote.len() - self.indent.len()
+    }
+}
+
+fn parse_prefix(line: &str) -> Prefix<'_> {
+    let bytes = line.as_bytes();
+    let skip_spaces = |mut ix: usize| {
+        while ix < bytes.len() && (bytes[ix] == b' ' || bytes[ix] == b'\t') {
+            ix += 1;
+        }
+        ix
+    };
+
+    let mut quote_end = 0;
+    let mut ix = skip_spaces(0);
+    while bytes.get(ix) == Some(&b'>') {
+        ix += 1;
+        if bytes.get(ix) == Some(&b' ') {
+            ix += 1;
+        }
+        quote_end = ix;
+        ix = skip_spaces(ix);
+    }
+    let indent_end = skip_spaces(quote_end);
+
+    let at_break = |ix: usize| ix == bytes.len() || bytes[ix] == b' ';
+    let mut ix = indent_end;
+    let marker = match bytes.get(ix) {
+        Some(&b) if matches!(b, b'-' | b'*' | b'+') && at_break(ix + 1) => {
+            ix += 1;
+            Some(Marker::Bullet(b as char))
+        }
+        Some(b) if b.is_ascii_digit() => {
+            let digits = bytes[ix..].iter().take_while(|b| b.is_ascii_digit()).count();
+            match bytes.get(ix + digits) {
+                Some(&delimiter) if digits <= 9 && matches!(delimiter, b'.' | b')') && at_break(ix + digits + 1) => {
+                    let number = line[ix..ix + digits].parse().unwrap_or(1);
+                    ix += digits + 1;
+                    Some(Marker::Ordered {
+                        number,
+                        delimiter: delimiter as char,
+                    })
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    let mut task = false;
+    if marker.is_some() {
+        if bytes.get(ix) == Some(&b' ') {
+            ix += 1;
+        }
+        let rest = &line[ix..];
+        if ["[ ] ", "[x] ", "[X] "].iter().any(|box_| rest.starts_with(box_)) || ["[ ]", "[x]", "[X]"].contains(&rest) {
+            task = true;
+            ix = (ix + 4).min(line.len());
+        }
+    } else {
+        ix = quote_end;
+    }
+
+    Prefix {
+        quote: &line[..quote_end],
+        indent: &line[quote_end..if marker.is_some() { indent_end } else { quote_end }],
+        marker,
+        task,
+        len: ix,
+    }
+}
+
+/// The lines of the paragraph around `offset`, with their start offsets.
+/// Lists are renumbered within it; a blank line ends it.
+fn paragraph_lines(content: &str, offset: usize) -> Vec<(usize, &str)> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for line in content.split('\n') {
+        let end = start + line.len();
+        if line.trim().is_empty() {
+            if end >= offset && start <= offset {
+                lines.push((start, line));
+                return lines;
+            }
+            if start > offset {
+                return lines;
+            }
+            lines.clear();
+        } else {
+            lines.push((start, line));
+        }
+        start = end + 1;
+    }
+    lines
+}
+
+/// Rewrites the numbers of ordered items so each list counts up from its
+/// first item, level by level.
+fn renumber(lines: &mut [String]) {
+    // (quote, indent width, next number for an ordered level)
+    let mut levels: Vec<(String, usize, Option<u64>)> = Vec::new();
+    for line in lines.iter_mut() {
+        let prefix = parse_prefix(line);
+        let Some(marker) = prefix.marker else { continue };
+        let quote = prefix.quote.to_string();
+        let indent = prefix.indent.len();
+        while levels.last().is_some_and(|(q, i, _)| *q != quote || *i > indent) {
+            levels.pop();
+        }
+        let same_level = levels.last_mut().filter(|(_, i, _)| *i == indent);
+        let Marker::Ordered { number, delimiter } = marker else {
+            match same_level {
+                Some(level) => level.2 = None,
+                None => levels.push((quote, indent, None)),
+            }
+            continue;
+        };
+        let number = match same_level {
+            Some((_, _, Some(next))) => {
+                let number = *next;
+                *next += 1;
+                number
+            }
+            Some(level) => {
+                level.2 = Some(number + 1);
+                number
+            }
+            None => {
+                levels.push((quote, indent, Some(number + 1)));
+                number
+            }
+        };
+        let marker_start = prefix.quote.len() + prefix.indent.len();
+        let digits_end = line[marker_start..].find(delimiter).map_or(marker_start, |ix| marker_start + ix);
+        if line[marker_start..digits_end] != number.to_string() {
+            line.replace_range(marker_start..digits_end, &number.to_string());
+        }
+    }
+}
+
+/// The smallest edit turning `old`, found at `offset`, into `new`: the
+/// part between what they start and end with in common.
+fn line_edit(offset: usize, old: &str, new: &str) -> Option<(Range<usize>, String)> {
+    if old == new {
+        return None;
+    }
+    let mut prefix = old.bytes().zip(new.bytes()).take_while(|(a, b)| a == b).count();
+    while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+    let (old_rest, new_rest) = (&old[prefix..], &new[prefix..]);
+    let mut suffix = old_rest.bytes().rev().zip(new_rest.bytes().rev()).take_while(|(a, b)| a == b).count();
+    while !old_rest.is_char_boundary(old_rest.len() - suffix) || !new_rest.is_char_boundary(new_rest.len() - suffix) {
+        suffix -= 1;
+    }
+    let text = new_rest[..new_rest.len() - suffix].to_string();
+    Some((offset + prefix..offset + old.len() - suffix, text))
+}
+
+/// Renumbers edited paragraph lines and works out the edits that turn the
+/// original lines into them, line by line. `split` is the original row, if
+/// any, that became two edited rows. `cursor` (an edited row and column)
+/// is mapped to an offset in the result.
+fn finish(
+    lines: &[(usize, &str)],
+    mut edited: Vec<String>,
+    split: Option<usize>,
+    cursor: (usize, usize),
+) -> Option<ListEdit> {
+    let (first, _) = lines.first()?;
+    // Renumbering may widen the line the cursor is on; keep it after the same text.
+    let before_renumber = edited[cursor.0].len();
+    renumber(&mut edited);
+    let column = (cursor.1 + edited[cursor.0].len()).saturating_sub(before_renumber);
+    let cursor = first + edited[..cursor.0].iter().map(|line| line.len() + 1).sum::<usize>() + column;
+
+    let mut edits = Vec::new();
+    let mut new_lines = edited.into_iter();
+    for (row, (start, old)) in lines.iter().enumerate() {
+        let mut new = new_lines.next()?;
+        if split == Some(row) {
+            new.push('\n');
+            new.push_str(&new_lines.next()?);
+        }
+        edits.extend(line_edit(*start, old, &new));
+    }
+    Some(ListEdit { edits, cursor })
+}
+
+/// What Enter does at `cursor` inside a list or quote, or `None` for a
+/// plain newline. An empty item ends the list instead of adding another.
+/// With `rewrite` off only the new line's prefix is inserted: nothing
+/// already written is renumbered or removed.
+pub fn continue_list(content: &str, cursor: usize, rewrite: bool) -> Option<ListEdit> {
+    let line_start = content[..cursor].rfind('\n').map_or(0, |ix| ix + 1);
+    let line_end = content[cursor..].find('\n').map_or(content.len(), |ix| cursor + ix);
+    let line = &content[line_start..line_end];
+    let prefix = parse_prefix(line);
+    if !prefix.is_structural() || cursor - line_start < prefix.len {
+        return None;
+    }
+
+    let empty = line[prefix.len..].trim().is_empty();
+    if !rewrite {
+        if empty {
+            return None;
+        }
+        let text = format!("\n{}", prefix.continuation());
+        return Some(ListEdit {
+            cursor: cursor + text.len(),
+            edits: vec![(cursor..cursor, text)],
+        });
+    }
+
+    let lines = paragraph_lines(content, cursor);
+    let row = lines.iter().position(|(start, _)| *start == line_start)?;
+    let mut edited: Vec<String> = lines.iter().map(|(_, line)| line.to_string()).collect();
+    if empty {
+        // An empty item drops its marker but stays in the quote; an empty
+        // quote line leaves the quote.
+        let kept = if prefix.marker.is_some() { prefix.quote } else { "" };
+        edited[row] = kept.to_string();
+        return finish(&lines, edited, None, (row, kept.len()));
+    }
+    let column = cursor - line_start;
+    let continuation = prefix.continuation();
+    let rest = edited[row].split_off(column);
+    edited.insert(row + 1, format!("{}{}", continuation, rest.trim_start()));
+    finish(&lines, edited, Some(row), (row + 1, continuation.len()))
+}
+
+/// Indents, or with `outdent` outdents, the list items on the lines
+/// `selection` touches. An item moves in by the width of the marker of
+/// the item above it, so it lines up as that item's child, and moves out
+/// to its parent's indent.
+pub fn indent_list(content: &str, selection: Range<usize>, outdent: bool) -> Option<ListEdit> {
+    let lines = paragraph_lines(content, selection.start);
+    let first = lines.iter().rposition(|(start, _)| *start <= selection.start)?;
+    let last = lines.iter().rposition(|(start, _)| *start <= selection.end)?.max(first);
+    let cursor_row = lines.iter().rposition(|(start, _)| *start <= selection.end)?;
+    let mut cursor_column = (selection.end - lines[cursor_row].0).min(lines[cursor_row].1.len());
+
+    let mut edited: Vec<String> = lines.iter().map(|(_, line)| line.to_string()).collect();
+    let mut changed = false;
+    for row in first..=last {
+        let line = edited[row].clone();
+        let prefix = parse_prefix(&line);
+        if prefix.marker.is_none() {
+            continue;
+        }
+        let indent = prefix.indent.len();
+        let items_above = edited[..row].iter().rev().map(|line| (line, parse_prefix(line)));
+        let new_indent = if outdent {
+            if indent == 0 {
+                continue;
+            }
+            items_above
+                .filter(|(_, above)| above.marker.is_some() && above.indent.len() < indent)
+                .map(|(_, above)| above.indent.len())
+                .next()
+                .unwrap_or(0)
+        } else {
+            let sibling = items_above
+                .filter(|(_, above)| above.marker.is_some() && above.indent.len() <= indent)
+                .map(|(_, above)| (above.indent.len(), above.marker_width()))
+                .next();
+            match sibling {
+                Some((above, width)) if above == indent => indent + width,
+                // The first item of a list has nothing to nest under.
+                _ => continue,
+            }
+        };
+        let quote_len = prefix.quote.len();
+        let mut item = line[quote_len + indent..].to_string();
+        // A nested ordered item counts from one unless it follows siblings,
+        // which renumbering then takes care of.
+        if let (false, Some(Marker::Ordered { delimiter, .. })) = (outdent, prefix.marker) {
+            let digits = item.find(delimiter).unwrap_or(0);
+            item.replace_range(..digits, "1");
+        }
+        edited[row] = format!("{}{}{}", prefix.quote, " ".repeat(new_indent), item);
+        if row == cursor_row {
+            cursor_column = (cursor_column + edited[row].len())
+                .saturating_sub(line.len())
+                .max(quote_len + new_indent);
+        }
+        changed = true;
+    }
+    if !changed {
+        return None;
+    }
+    finish(&lines, edited, None, (cursor_row, cursor_column))
+}
+
+/// Whether `offset` is on a list item line, where Tab indents the item.
+pub fn in_list_item(content: &str, offset: usize) -> bool {
+    let line_start = content[..offset].rfind('\n').map_or(0, |ix| ix + 1);
+    let line_end = content[offset..].find('\n').map_or(content.len(), |ix| offset + ix);
+    parse_prefix(&content[line_start..line_end]).marker.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Applies `edit` to `content`, marking the cursor with `|`.
+    fn apply(content: &str, edit: ListEdit) -> String {
+        let mut result = content.to_string();
+        for (range, text) in edit.edits.iter().rev() {
+            result.replace_range(range.clone(), text);
+        }
+        result.insert(edit.cursor, '|');
+        result
+    }
+
+    /// Runs `command` with the cursor at the `|` in `text`.
+    fn at_cursor(text: &str, command: impl Fn(&str, usize) -> Option<ListEdit>) -> Option<String> {
+        let cursor = text.find('|').unwrap();
+        let content = text.replace('|', "");
+        command(&content, cursor).map(|edit| apply(&content, edit))
+    }
+
+    fn enter(text: &str) -> Option<String> {
+        at_cursor(text, |content, cursor| continue_list(content, cursor, true))
+    }
+
+    fn tab(text: &str, outdent: bool) -> Option<String> {
+        at_cursor(text, |content, cursor| indent_list(content, cursor..cursor, outdent))
+    }
+
+    #[test]
+    fn enter_continues_items_and_quotes() {
+        assert_eq!(enter("- one|").as_deref(), Some("- one\n- |"));
+        assert_eq!(enter("* [x] done|").as_deref(), Some("* [x] done\n* [ ] |"));
+        assert_eq!(enter("> quoted|").as_deref(), Some("> quoted\n> |"));
+        assert_eq!(enter("1. split| here").as_deref(), Some("1. split\n2. |here"));
+        assert_eq!(enter("plain|"), None);
+        assert_eq!(enter("-|").as_deref(), Some("|"));
+    }
+
+    #[test]
+    fn enter_on_an_empty_item_ends_the_list() {
+        assert_eq!(enter("- one\n- |").as_deref(), Some("- one\n|"));
+        assert_eq!(enter("> - one\n> - |").as_deref(), Some("> - one\n> |"));
+        assert_eq!(enter("> one\n> |").as_deref(), Some("> one\n|"));
+    }
+
+    #[test]
+    fn enter_renumbers_only_the_numbers_that_change() {
+        let content = "1. a\n2. b\n9. c";
+        let edit = continue_list(content, 4, true).unwrap();
+        assert_eq!(edit.edits, [(4..4, "\n2. ".to_string()), (5..6, "3".to_string()), (10..11, "4".to_string())]);
+        assert_eq!(apply(content, edit), "1. a\n2. |\n3. b\n4. c");
+        assert_eq!(enter("9. a\n10. b|").as_deref(), Some("9. a\n10. b\n11. |"));
+    }
+
+    #[test]
+    fn enter_without_rewriting_only_inserts() {
+        let content = "1. a\n2. b";
+        let edit = continue_list(content, 4, false).unwrap();
+        assert_eq!(edit.edits, [(4..4, "\n2. ".to_string())]);
+        assert!(continue_list("1. a\n2. ", 8, false).is_none());
+    }
+
+    #[test]
+    fn tab_nests_under_the_item_above() {
+        assert_eq!(tab("- a\n- b|", false).as_deref(), Some("- a\n  - b|"));
+        assert_eq!(tab("1. a\n2. b|\n3. c", false).as_deref(), Some("1. a\n   1. b|\n2. c"));
+        assert_eq!(tab("- a|", false), None);
+        assert_eq!(tab("- a\n  - b|", false), None);
+    }
+
+    #[test]
+    fn shift_tab_moves_out_to_the_parent() {
+        assert_eq!(tab("- a\n  - b|", true).as_deref(), Some("- a\n- b|"));
+        assert_eq!(tab("1. a\n   1. b|\n2. c", true).as_deref(), Some("1. a\n2. b|\n3. c"));
+        assert_eq!(tab("- a|", true), None);
+    }
+
+    #[test]
+    fn indenting_only_touches_the_indent() {
+        let content = "- a\n- b\n- c";
+        let edit = indent_list(content, 6..6, false).unwrap();
+        assert_eq!(edit.edits, [(4..4, "  ".to_string())]);
+    }
+
+    #[test]
+    fn line_edit_keeps_to_char_boundaries() {
+        assert_eq!(line_edit(10, "é", "è"), Some((10..12, "è".to_string())));
+        assert_eq!(line_edit(0, "ab", "ab"), None);
+    }
+}
@@ -1,3 +1,17 @@
+mod format;
+mod html;
+mod kill_ring;
+mod lists;
+mod pairs;
+mod typography;
 mod view;
+mod vim;
 
+pub use format::*;
+pub use html::*;
+pub use kill_ring::*;
+pub use lists::*;
+pub use pairs::*;
+pub use typography::*;
 pub use view::*;
+pub use vim::*;
@@ -0,0 +1,26 @@
+use crate::editor::Quotes;
+
+/// The characters that auto-pair, each with its closer.
+const PAIRS: [(char, char); 5] = [('(', ')'), ('[', ']'), ('"', '"'), ('*', '*'), ('`', '`')];
+
+/// The pair typing `typed` starts, if any. With smart punctuation a double
+/// quote pairs as the language's curly quotes.
+pub fn pair_for(typed: char, quotes: Option<&Quotes>) -> Option<(char, char)> {
+    let pair = PAIRS.into_iter().find(|(open, _)| *open == typed)?;
+    Some(match (typed, quotes) {
+        ('"', Some(quotes)) => (quotes.open_double, quotes.close_double),
+        _ => pair,
+    })
+}
+
+/// Whether typing `typed` at `cursor`, with nothing selected, should insert
+/// its closer too. That's only before whitespace or closing punctuation,
+/// and a quote, star or backtick straight after a word more likely closes
+/// something than opens it.
+pub fn pairs_at(content: &str, cursor: usize, typed: char) -> bool {
+    let next = content[cursor..].chars().next();
+    let previous = content[..cursor].chars().next_back();
+    let open_after = next.is_none_or(|c| c.is_whitespace() || ")]}.,;:!?”»’".contains(c));
+    let symmetric = matches!(typed, '"' | '*' | '`');
+    open_after && !(symmetric && previous.is_some_and(char::is_alphanumeric))
+}
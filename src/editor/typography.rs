@@ -0,0 +1,151 @@
+use std::ops::Range;
+
+use crate::markdown::{LineKind, lines};
+
+/// The quotation marks a language uses, for smart punctuation.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Quotes {
+    pub open_double: char,
+    pub close_double: char,
+    pub open_single: char,
+    pub close_single: char,
+}
+
+impl Quotes {
+    /// Picks quotes by the language part of a locale such as `de_DE`,
+    /// falling back to English “curly” quotes.
+    pub fn for_language(locale: &str) -> Self {
+        let language = locale.split(['_', '-']).next().unwrap_or_default();
+        let [open_double, close_double, open_single, close_single] = match language {
+            "de" | "cs" | "sk" | "sl" | "lt" | "is" => ['„', '“', '‚', '‘'],
+            "pl" | "hu" | "ro" | "nl" | "hr" | "bg" => ['„', '”', '‚', '’'],
+            "fr" | "ru" | "uk" | "es" | "it" | "pt" | "el" | "no" | "nb" | "nn" => ['«', '»', '‹', '›'],
+            "sv" | "fi" => ['”', '”', '’', '’'],
+            _ => ['“', '”', '‘', '’'],
+        };
+        Self {
+            open_double,
+            close_double,
+            open_single,
+            close_single,
+        }
+    }
+}
+
+/// What the character just typed before `cursor` should become, if smart
+/// punctuation applies: the range to replace and its replacement. Straight
+/// quotes curl according to what precedes them, `--` becomes an em dash and
+/// `...` an ellipsis. Code spans, fences and front matter are left alone.
+pub fn smarten(content: &str, cursor: usize, quotes: &Quotes) -> Option<(Range<usize>, String)> {
+    let before = &content[..cursor];
+    let typed = before.chars().next_back()?;
+    if !matches!(typed, '"' | '\'' | '-' | '.') || in_code(content, cursor) {
+        return None;
+    }
+    let start = cursor - typed.len_utf8();
+    let previous = before[..start].chars().next_back();
+    let line = &before[before.rfind('\n').map_or(0, |ix| ix + 1)..];
+
+    match typed {
+        '"' | '\'' => {
+            let opening = previous.is_none_or(|c| {
+                c.is_whitespace() || "([{<—–/".contains(c) || [quotes.open_double, quotes.open_single].contains(&c)
+            });
+            let quote = match (typed, opening) {
+                ('"', true) => quotes.open_double,
+                ('"', false) => quotes.close_double,
+                (_, true) => quotes.open_single,
+                // After a letter it's an apostrophe, as in "don’t", whatever
+                // the language's quotes, unless it closes an open quote.
+                (_, false) if previous.is_some_and(char::is_alphanumeric) => {
+                    let unclosed = line.matches(quotes.open_single).count() > line.matches(quotes.close_single).count();
+                    if unclosed {
+                        quotes.close_single
+                    } else {
+                        '’'
+                    }
+                }
+                (_, false) => quotes.close_single,
+            };
+            Some((start..cursor, quote.to_string()))
+        }
+        '-' if previous == Some('-') => {
+            // Leave `---` rules and front matter, table rules and `<!--` be.
+            let lead = &line[..line.len() - 2];
+            let rule = lead.chars().all(|c| c == '-' || c.is_whitespace());
+            if rule || line.trim_start().starts_with('|') || lead.ends_with('!') {
+                return None;
+            }
+            Some((start - 1..cursor, "—".to_string()))
+        }
+        '.' if before.ends_with("...") && !before[..cursor - 3].ends_with('.') => {
+            Some((cursor - 3..cursor, "…".to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `offset` is inside front matter, a fenced code block or an
+/// inline code span, where punctuation has to stay as typed.
+fn in_code(content: &str, offset: usize) -> bool {
+    let before = &content[..offset];
+    let Some(line) = lines(before).last() else { return false };
+    line.kind != LineKind::Text || line.text.matches('`').count() % 2 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Types `text` a character at a time, smartening as the editor does.
+    fn typed(text: &str, quotes: &Quotes) -> String {
+        let mut content = String::new();
+        for c in text.chars() {
+            content.push(c);
+            if let Some((range, replacement)) = smarten(&content, content.len(), quotes) {
+                content.replace_range(range, &replacement);
+            }
+        }
+        content
+    }
+
+    #[test]
+    fn quotes_curl_by_what_precedes_them() {
+        let english = Quotes::for_language("en_US");
+        assert_eq!(typed("\"Hi,\" she said. 'Don't.'", &english), "“Hi,” she said. ‘Don’t.’");
+        assert_eq!(typed("(\"a\")", &english), "(“a”)");
+        let german = Quotes::for_language("de_DE");
+        assert_eq!(typed("\"Ja\" and it's", &german), "„Ja“ and it’s");
+    }
+
+    #[test]
+    fn dashes_and_ellipses() {
+        let quotes = Quotes::for_language("en");
+        assert_eq!(typed("wait--what...", &quotes), "wait—what…");
+        assert_eq!(typed("----", &quotes), "----");
+        assert_eq!(typed("| a | b |\n|--|", &quotes), "| a | b |\n|--|");
+        assert_eq!(typed("<!-- note", &quotes), "<!-- note");
+        assert_eq!(typed("....", &quotes), "….");
+    }
+
+    #[test]
+    fn code_is_left_alone() {
+        let quotes = Quotes::for_language("en");
+        assert_eq!(typed("`\"a\"` \"b\"", &quotes), "`\"a\"` “b”");
+        assert_eq!(typed("```\nx--y\n```\n'z'", &quotes), "```\nx--y\n```\n‘z’");
+        assert_eq!(typed("---\ntitle: 'x'\n---\n'y'", &quotes), "---\ntitle: 'x'\n---\n‘y’");
+    }
+
+    #[test]
+    fn in_code_spots_spans_fences_and_front_matter() {
+        let text = "---\na: b\n---\nsome `code` here\n```\nfenced\n```\nafter";
+        let at = |needle: &str| text.find(needle).unwrap();
+        assert!(in_code(text, at("a: b")));
+        assert!(!in_code(text, at("some")));
+        assert!(in_code(text, at("code`") + 2));
+        assert!(!in_code(text, at(" here")));
+        assert!(in_code(text, at("fenced")));
+        assert!(!in_code(text, at("after")));
+        assert!(in_code("---\nopen", 6));
+    }
+}
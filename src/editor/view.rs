@@ -1,20 +1,40 @@
 use gpui::*;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::ops::Range;
+use std::time::{Duration, Instant};
 use unicode_segmentation::*;
 
-use crate::actions::ToggleFocusMode;
+use crate::actions::{SuggestSpelling, ToggleFocusMode, ToggleForwardOnly, ToggleStyleAnalysis};
+use crate::editor::{
+    Emphasis, FormatEdit, InsertAt, KillRing, ListEdit, Mode, Operator, Parse, Quotes, Target, Vim, VimAction,
+    VimCommand, VimMotion, clipboard_html, continue_list, first_non_blank, html_to_markdown, in_list_item, indent_list,
+    insert_link, line_at, motion_target, next_char, operator_range, pair_for, pairs_at, parse_command, previous_char,
+    set_heading, smarten, text_object, toggle_emphasis, with_line_break, word_at,
+};
+use crate::outline::{headings, section_range};
+use crate::readability::{Analysis, Analyzer};
+use crate::spell::{SpellCache, SpellChecker};
 use crate::theme::{Theme, ThemeMode};
 
 pub enum EditorEvent {
     Modified,
+    /// The text changed without anything new being written: an undo or
+    /// redo, or text that was just cut pasted back in somewhere else.
+    Rearranged,
     FocusModeChanged(bool),
+    ForwardOnlyChanged(bool),
+    StyleAnalysisChanged(bool),
+    VimModeChanged,
 }
 
 impl EventEmitter<EditorEvent> for EditorView {}
 
 const PADDING: f32 = 48.0;
-const LINE_HEIGHT: f32 = 38.0;
-const FONT_SIZE: f32 = 21.0;
+pub const LINE_HEIGHT: f32 = 38.0;
+pub const FONT_SIZE: f32 = 21.0;
+const UNDO_GROUP_INTERVAL: Duration = Duration::from_millis(1000);
+/// Drawn after the heading of a folded section.
+const FOLD_MARKER: &str = " …";
 
 fn rot13(s: &str) -> String {
     s.chars()
@@ -26,6 +46,18 @@ fn rot13(s: &str) -> String {
         .collect()
 }
 
+/// Where `offset` ends up once `range` is replaced by `new_len` bytes.
+/// Offsets inside the range move to the end of the replacement.
+fn shifted(offset: usize, range: &Range<usize>, new_len: usize) -> usize {
+    if offset >= range.end {
+        offset + new_len - range.len()
+    } else if offset > range.start {
+        range.start + new_len
+    } else {
+        offset
+    }
+}
+
 pub struct EditorView {
     focus_handle: FocusHandle,
     content: String,
@@ -38,8 +70,155 @@ pub struct EditorView {
     theme: Theme,
     modified: bool,
     focus_mode: bool,
+    /// Just the text, without analysis tints or spelling underlines, as
+    /// during a sprint.
+    plain: bool,
+    /// Drafting without looking back: text can only be erased within the
+    /// word being typed, or by undo.
+    forward_only: bool,
+    /// In forward-only mode, the end of the last text typed. Nothing may be
+    /// written before it, and only the word it ends may be erased. `None`
+    /// until the first insertion, which can go anywhere.
+    frontier: Option<usize>,
+    /// The run of text typed since the writer last paused, which
+    /// write-or-lose mode fades by `fade` (0 to 1) while its warning builds.
+    recent: Option<Range<usize>>,
+    recent_closed: bool,
+    fade: f32,
+    spell: Option<Entity<SpellChecker>>,
+    misspelled: Vec<Range<usize>>,
+    /// The content hash and checker generation `misspelled` was found for.
+    spelled: Option<(u64, usize)>,
+    spell_cache: SpellCache,
+    /// The quotes smart punctuation curls into, or `None` while it's off.
+    smart_punctuation: Option<Quotes>,
+    auto_pair: bool,
+    /// Pairs inserted by auto-pairing that are still open, innermost last,
+    /// from the opener's start to the closer's end. Only these closers are
+    /// typed over.
+    pairs: Vec<Range<usize>>,
+    /// Folded sections, in order, each from the end of its heading line to
+    /// the end of the section. Offsets after `start` up to and including
+    /// `end` are hidden, so the cursor never rests there.
+    folds: Vec<Range<usize>>,
+    /// Cursors beside the main one, each with its own selection. Typing,
+    /// deleting and moving apply to all of them.
+    cursors: Vec<Cursor>,
+    /// Where an alt-drag column selection started, and the cursors there
+    /// were before it.
+    column_drag: Option<(Point<Pixels>, Vec<Cursor>)>,
+    /// Vim-style modal editing, present while it's on.
+    vim: Option<Vim>,
+    /// Whether the Emacs-style Ctrl shortcuts are on.
+    emacs: bool,
+    kill_ring: KillRing,
+    /// The cursor and document length just after the last Ctrl+K, so the
+    /// next one in the same place adds to that kill.
+    last_kill: Option<(usize, usize)>,
+    /// Where the last paste or yank put its text, for Alt+Y to replace.
+    last_yank: Option<Range<usize>>,
+    /// The text of the last cut, so pasting it back reads as a move.
+    cut: Option<String>,
+    /// Set while an edit is moving text rather than writing it.
+    moving: bool,
+    /// Present while style analysis is on.
+    analyzer: Option<Analyzer>,
+    analysis: Analysis,
+    /// The content hash `analysis` was made for.
+    analyzed: Option<u64>,
     scroll_y: Pixels,
     pending_scroll_to_cursor: bool,
+    /// Scroll the cursor's line to the top of the view rather than just
+    /// into it.
+    scroll_cursor_to_top: bool,
+    undo_stack: Vec<Edit>,
+    redo_stack: Vec<Edit>,
+}
+
+/// A single reversible change to `content`. `range` is where `new_text` now
+/// lives; undoing puts `old_text` back in its place.
+struct Edit {
+    range: Range<usize>,
+    old_text: String,
+    new_text: String,
+    selection_before: Range<usize>,
+    at: Instant,
+}
+
+impl Edit {
+    /// Folds `next` into this edit when both are part of the same run of
+    /// typing or deleting, so undo removes a burst of keystrokes at once.
+    fn absorb(&mut self, next: &Edit) -> bool {
+        if next.at.duration_since(self.at) > UNDO_GROUP_INTERVAL
+            || self.new_text.contains('\n')
+            || next.new_text.contains('\n')
+        {
+            return false;
+        }
+        let typing = self.old_text.is_empty() && next.old_text.is_empty();
+        let deleting = self.new_text.is_empty() && next.new_text.is_empty();
+        if typing && next.range.start == self.range.end {
+            self.new_text.push_str(&next.new_text);
+            self.range.end = next.range.end;
+        } else if deleting && next.range.start + next.old_text.len() == self.range.start {
+            self.old_text.insert_str(0, &next.old_text);
+            self.range = next.range.clone();
+        } else if deleting && next.range.start == self.range.start {
+            self.old_text.push_str(&next.old_text);
+        } else {
+            return false;
+        }
+        self.at = next.at;
+        true
+    }
+}
+
+/// A cursor at `head`, selecting back to `anchor`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Cursor {
+    anchor: usize,
+    head: usize,
+}
+
+impl Cursor {
+    fn at(offset: usize) -> Self {
+        Self {
+            anchor: offset,
+            head: offset,
+        }
+    }
+
+    fn range(&self) -> Range<usize> {
+        self.anchor.min(self.head)..self.anchor.max(self.head)
+    }
+
+    /// Whether the two would select the same text or sit in the same place.
+    fn overlaps(&self, other: &Cursor) -> bool {
+        let (a, b) = (self.range(), other.range());
+        (a.start < b.end && b.start < a.end) || a.start == b.start
+    }
+
+    /// One cursor covering both, facing the way this one does.
+    fn union(&self, other: &Cursor) -> Cursor {
+        let (a, b) = (self.range(), other.range());
+        let (start, end) = (a.start.min(b.start), a.end.max(b.end));
+        if self.head < self.anchor {
+            Cursor { anchor: end, head: start }
+        } else {
+            Cursor { anchor: start, head: end }
+        }
+    }
+}
+
+/// The ways every cursor can move at once.
+#[derive(Clone, Copy)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    LineStart,
+    LineEnd,
 }
 
 #[derive(Clone)]
@@ -62,8 +241,37 @@ impl EditorView {
             theme,
             modified: false,
             focus_mode,
+            plain: false,
+            forward_only: false,
+            frontier: None,
+            recent: None,
+            recent_closed: false,
+            fade: 0.0,
+            spell: None,
+            misspelled: Vec::new(),
+            spelled: None,
+            spell_cache: SpellCache::default(),
+            smart_punctuation: None,
+            auto_pair: false,
+            pairs: Vec::new(),
+            folds: Vec::new(),
+            cursors: Vec::new(),
+            column_drag: None,
+            vim: None,
+            emacs: false,
+            kill_ring: KillRing::default(),
+            last_kill: None,
+            last_yank: None,
+            cut: None,
+            moving: false,
+            analyzer: None,
+            analysis: Analysis::default(),
+            analyzed: None,
             scroll_y: px(0.0),
             pending_scroll_to_cursor: false,
+            scroll_cursor_to_top: false,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
         }
     }
 
@@ -76,6 +284,265 @@ impl EditorView {
         &self.content
     }
 
+    /// Replaces the whole buffer, e.g. with a document loaded from disk.
+    /// This starts a fresh undo history and leaves the editor unmodified.
+    pub fn set_content(&mut self, content: String, cx: &mut Context<Self>) {
+        self.content = content;
+        self.selected_range = 0..0;
+        self.selection_anchor = 0;
+        self.cursor_offset = 0;
+        self.marked_range = None;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.recent = None;
+        self.frontier = None;
+        self.folds.clear();
+        self.cursors.clear();
+        self.modified = false;
+        self.scroll_y = px(0.0);
+        cx.notify();
+    }
+
+    /// Adds text that another process appended to the file. The cursor,
+    /// selection and undo history are untouched since they all sit before it.
+    pub fn append_external(&mut self, text: &str, cx: &mut Context<Self>) {
+        self.content.push_str(text);
+        cx.notify();
+    }
+
+    /// Swaps in content that was rewritten on disk, keeping the cursor as
+    /// close to where it was as the new text allows.
+    pub fn reload(&mut self, content: String, cx: &mut Context<Self>) {
+        let cursor = self.cursor_offset;
+        let scroll_y = self.scroll_y;
+        self.set_content(content, cx);
+        self.restore_position(cursor, scroll_y, cx);
+    }
+
+    /// Replaces text on jid's behalf rather than the writer's, such as
+    /// metadata. It can be undone like any edit but isn't reported as
+    /// `Modified`, so it doesn't count as words written.
+    pub fn replace_quietly(&mut self, range: Range<usize>, text: &str, cx: &mut Context<Self>) {
+        let shift = |o: usize| shifted(o, &range, text.len());
+        self.record_edit(&range, text);
+        self.content.replace_range(range.clone(), text);
+        self.selected_range = shift(self.selected_range.start)..shift(self.selected_range.end);
+        self.selection_anchor = shift(self.selection_anchor);
+        self.cursor_offset = shift(self.cursor_offset);
+        for cursor in &mut self.cursors {
+            *cursor = Cursor {
+                anchor: shift(cursor.anchor),
+                head: shift(cursor.head),
+            };
+        }
+        self.recent = self.recent.take().map(|recent| shift(recent.start)..shift(recent.end));
+        self.frontier = self.frontier.map(shift);
+        self.marked_range = None;
+        self.modified = true;
+        cx.notify();
+    }
+
+    /// Ends the current run of recent text when the writer pauses. It can
+    /// still fade or be erased until the next edit starts a new run.
+    pub fn close_recent(&mut self) {
+        self.recent_closed = true;
+    }
+
+    pub fn set_fade(&mut self, fade: f32, cx: &mut Context<Self>) {
+        if self.fade != fade {
+            self.fade = fade;
+            cx.notify();
+        }
+    }
+
+    /// Deletes the recent run of text for write-or-lose mode. Like
+    /// `replace_quietly` it is undoable but not reported as `Modified`.
+    /// Returns whether anything was erased.
+    pub fn erase_recent(&mut self, cx: &mut Context<Self>) -> bool {
+        let Some(recent) = self.recent.take().filter(|recent| !recent.is_empty()) else {
+            return false;
+        };
+        let shift = |o: usize| {
+            if o >= recent.end {
+                o - recent.len()
+            } else {
+                o.min(recent.start)
+            }
+        };
+        self.record_edit(&recent, "");
+        self.content.replace_range(recent.clone(), "");
+        self.selected_range = shift(self.selected_range.start)..shift(self.selected_range.end);
+        self.selection_anchor = shift(self.selection_anchor);
+        self.cursor_offset = shift(self.cursor_offset);
+        for cursor in &mut self.cursors {
+            *cursor = Cursor {
+                anchor: shift(cursor.anchor),
+                head: shift(cursor.head),
+            };
+        }
+        self.merge_cursors();
+        self.marked_range = None;
+        self.fade = 0.0;
+        self.modified = true;
+        self.pending_scroll_to_cursor = true;
+        cx.notify();
+        true
+    }
+
+    /// Keeps `recent` covering the run of text being typed: edits inside
+    /// it grow or shrink it, and an edit anywhere else, or the first after
+    /// a pause, starts a new run. The forward-only frontier follows the
+    /// end of the edit.
+    fn track_recent(&mut self, range: &Range<usize>, new_text: &str) {
+        let inserted = range.start..range.start + new_text.len();
+        if self.forward_only {
+            self.frontier = Some(inserted.end);
+        }
+        self.recent = match self.recent.take() {
+            Some(recent) if !self.recent_closed && range.start >= recent.start && range.end <= recent.end => {
+                Some(recent.start..recent.end - range.len() + new_text.len())
+            }
+            _ => Some(inserted).filter(|inserted| !inserted.is_empty()),
+        };
+        self.recent_closed = false;
+    }
+
+    pub fn cursor_offset(&self) -> usize {
+        self.cursor_offset
+    }
+
+    pub fn scroll_y(&self) -> Pixels {
+        self.scroll_y
+    }
+
+    /// Places the cursor at `offset` and scrolls it into view, unfolding
+    /// the section it's in if need be.
+    pub fn jump_to(&mut self, offset: usize, cx: &mut Context<Self>) {
+        let mut offset = offset.min(self.content.len());
+        while !self.content.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        self.unfold_at(offset);
+        self.cursors.clear();
+        self.move_to(offset, cx);
+    }
+
+    /// Moves the cursor to `offset` and scrolls its line to the top.
+    pub fn reveal(&mut self, offset: usize, cx: &mut Context<Self>) {
+        self.jump_to(offset, cx);
+        self.scroll_cursor_to_top = true;
+    }
+
+    pub fn restore_position(&mut self, cursor: usize, scroll_y: Pixels, cx: &mut Context<Self>) {
+        let mut cursor = cursor.min(self.content.len());
+        while !self.content.is_char_boundary(cursor) {
+            cursor -= 1;
+        }
+        let cursor = self.skip_folds(cursor, false);
+        self.cursors.clear();
+        self.selected_range = cursor..cursor;
+        self.selection_anchor = cursor;
+        self.cursor_offset = cursor;
+        self.scroll_y = scroll_y.max(px(0.0));
+        cx.notify();
+    }
+
+    /// Where the headings of the folded sections start, to keep them folded
+    /// next time.
+    pub fn folded_headings(&self) -> Vec<usize> {
+        self.folds
+            .iter()
+            .map(|fold| self.content[..fold.start].rfind('\n').map_or(0, |ix| ix + 1))
+            .collect()
+    }
+
+    /// Folds the sections whose headings start at `starts` again, skipping
+    /// any that have since stopped being headings.
+    pub fn restore_folds(&mut self, starts: &[usize], cx: &mut Context<Self>) {
+        for &start in starts {
+            if let Some(fold) = self.section_fold(start) {
+                self.add_fold(fold);
+            }
+        }
+        cx.notify();
+    }
+
+    /// The range folding the section under the heading starting at
+    /// `heading_start` would hide, if it has anything to hide.
+    fn section_fold(&self, heading_start: usize) -> Option<Range<usize>> {
+        let headings = headings(&self.content);
+        let ix = headings.iter().position(|heading| heading.start == heading_start)?;
+        let section = section_range(&self.content, &headings, ix);
+        let heading_end = heading_start + self.content[heading_start..].find('\n')?;
+        // Keep the line break before the next heading so it stays on its own line.
+        let end = if section.end == self.content.len() {
+            section.end
+        } else {
+            section.end - 1
+        };
+        (end > heading_end).then_some(heading_end..end)
+    }
+
+    fn add_fold(&mut self, fold: Range<usize>) {
+        // Extra cursors could end up hidden in the fold.
+        self.cursors.clear();
+        self.folds.retain(|other| other.end < fold.start || other.start > fold.end);
+        let ix = self.folds.partition_point(|other| other.start < fold.start);
+        self.folds.insert(ix, fold);
+    }
+
+    /// Folds the section the cursor is in, leaving the cursor at the end of
+    /// its heading.
+    fn fold(&mut self, _: &Fold, _window: &mut Window, cx: &mut Context<Self>) {
+        let cursor = self.cursor_offset;
+        let Some(heading) = headings(&self.content).into_iter().rfind(|heading| heading.start <= cursor) else {
+            return;
+        };
+        let Some(fold) = self.section_fold(heading.start) else { return };
+        let heading_end = fold.start;
+        self.add_fold(fold);
+        if self.selected_range.end > heading_end {
+            self.move_to(heading_end, cx);
+        }
+        cx.notify();
+    }
+
+    /// Unfolds the section whose heading the cursor is on.
+    fn unfold(&mut self, _: &Unfold, _window: &mut Window, cx: &mut Context<Self>) {
+        let cursor = self.cursor_offset;
+        let line_end = self.content[cursor..].find('\n').map_or(self.content.len(), |ix| cursor + ix);
+        self.folds.retain(|fold| fold.start != line_end);
+        cx.notify();
+    }
+
+    fn unfold_at(&mut self, offset: usize) {
+        self.folds.retain(|fold| !(fold.start < offset && offset <= fold.end));
+    }
+
+    /// `offset`, moved out of any folded section it's hidden in: past it
+    /// when moving `forward`, otherwise back to its heading.
+    fn skip_folds(&self, offset: usize, forward: bool) -> usize {
+        match self.folds.iter().find(|fold| fold.start < offset && offset <= fold.end) {
+            Some(fold) if forward && fold.end < self.content.len() => fold.end + 1,
+            Some(fold) => fold.start,
+            None => offset,
+        }
+    }
+
+    /// Keeps the folds in step with an edit. One that reaches into hidden
+    /// text unfolds its section.
+    fn shift_folds(&mut self, range: &Range<usize>, new_len: usize) {
+        let grow = |offset: usize| offset + new_len - range.len();
+        self.folds.retain_mut(|fold| {
+            if range.end <= fold.start {
+                *fold = grow(fold.start)..grow(fold.end);
+                true
+            } else {
+                range.start > fold.end
+            }
+        });
+    }
+
     pub fn is_modified(&self) -> bool {
         self.modified
     }
@@ -84,6 +551,580 @@ impl EditorView {
         self.modified = false;
     }
 
+    /// Turns spell checking on with `checker`, or off with `None`. Words the
+    /// checker learns are picked up straight away.
+    pub fn set_spell_checker(&mut self, checker: Option<Entity<SpellChecker>>, cx: &mut Context<Self>) {
+        if let Some(checker) = &checker {
+            cx.observe(checker, |_, _, cx| cx.notify()).detach();
+        }
+        self.spell = checker;
+        self.misspelled.clear();
+        self.spelled = None;
+        self.spell_cache = SpellCache::default();
+        cx.notify();
+    }
+
+    /// Rechecks the document if it or the checker's words changed since
+    /// the last check. Only blocks that changed are read again.
+    fn refresh_spelling(&mut self, cx: &App) {
+        let Some(checker) = &self.spell else { return };
+        let checker = checker.read(cx);
+        let key = (self.content_hash(), checker.generation());
+        if self.spelled != Some(key) {
+            self.misspelled = self.spell_cache.misspellings(checker, &self.content);
+            self.spelled = Some(key);
+        }
+    }
+
+    /// A hash of the text, for noticing whether it changed.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.content.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn set_smart_punctuation(&mut self, quotes: Option<Quotes>) {
+        self.smart_punctuation = quotes;
+    }
+
+    pub fn set_auto_pair(&mut self, enabled: bool) {
+        self.auto_pair = enabled;
+        self.pairs.clear();
+    }
+
+    /// The innermost auto-inserted pair the cursor is inside, forgetting
+    /// the ones it has left.
+    fn open_pair(&mut self) -> Option<Range<usize>> {
+        let cursor = self.cursor_offset;
+        self.pairs.retain(|pair| pair.start < cursor && cursor < pair.end);
+        self.pairs.last().cloned()
+    }
+
+    /// Handles `typed` for auto-pairing: wraps the selection, inserts a
+    /// pair, or steps over the closer of an open one. Returns whether the
+    /// character was dealt with.
+    fn auto_pair_typed(&mut self, typed: char, cx: &mut Context<Self>) -> bool {
+        if !self.auto_pair || self.forward_only {
+            return false;
+        }
+        let cursor = self.cursor_offset;
+        let selection = self.selected_range.clone();
+        let pair = pair_for(typed, self.smart_punctuation.as_ref());
+
+        if let Some(open) = self.open_pair().filter(|_| selection.is_empty()) {
+            let closer = self.content[..open.end].chars().next_back().unwrap_or_default();
+            let opener_len = self.content[open.start..].chars().next().map_or(0, char::len_utf8);
+            let empty = open.start + opener_len == cursor && cursor + closer.len_utf8() == open.end;
+            let closes = typed == closer || pair.is_some_and(|(_, close)| close == closer);
+            // `**` and ``` `` ``` nest rather than close, for bold and code.
+            if closes && !(empty && matches!(typed, '*' | '`')) && cursor + closer.len_utf8() == open.end {
+                self.pairs.pop();
+                self.move_to(open.end, cx);
+                return true;
+            }
+            // `* ` at the start of a line is a bullet, not emphasis.
+            if typed == ' ' && empty && closer == '*' {
+                self.pairs.pop();
+                self.replace_text(&(cursor..open.end), " ", cx);
+                return true;
+            }
+        }
+
+        let Some((open, close)) = pair else { return false };
+        if selection.is_empty() {
+            let nesting = self.open_pair().is_some_and(|pair| pair.end == cursor + close.len_utf8());
+            if !nesting && !pairs_at(&self.content, cursor, typed) {
+                return false;
+            }
+        }
+        let inner = self.content[selection.clone()].to_string();
+        let text = format!("{}{}{}", open, inner, close);
+        self.replace_text(&selection, &text, cx);
+        let inner_start = selection.start + open.len_utf8();
+        self.selected_range = inner_start..inner_start + inner.len();
+        self.selection_anchor = inner_start;
+        self.cursor_offset = self.selected_range.end;
+        self.pairs.push(selection.start..selection.start + text.len());
+        true
+    }
+
+    /// Keeps the open pairs in step with an edit, dropping any it touches
+    /// other than by changing what's between opener and closer.
+    fn shift_pairs(&mut self, range: &Range<usize>, new_len: usize) {
+        let grow = |offset: usize| offset + new_len - range.len();
+        self.pairs.retain_mut(|pair| {
+            if range.end <= pair.start {
+                *pair = grow(pair.start)..grow(pair.end);
+            } else if range.start > pair.start && range.end < pair.end {
+                pair.end = grow(pair.end);
+            } else if range.start < pair.end {
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Applies smart punctuation to the character just typed. The
+    /// conversion is an edit of its own, so undo turns it back into what
+    /// was typed.
+    fn smarten_typed(&mut self) {
+        let Some(quotes) = &self.smart_punctuation else { return };
+        let Some((range, text)) = smarten(&self.content, self.cursor_offset, quotes) else { return };
+        self.record_edit(&range, &text);
+        self.track_recent(&range, &text);
+        self.content.replace_range(range.clone(), &text);
+        let cursor = range.start + text.len();
+        self.selected_range = cursor..cursor;
+        self.selection_anchor = cursor;
+        self.cursor_offset = cursor;
+    }
+
+    pub fn set_style_analysis(&mut self, enabled: bool, cx: &mut Context<Self>) {
+        self.analyzer = enabled.then(Analyzer::default);
+        self.analysis = Analysis::default();
+        self.analyzed = None;
+        cx.notify();
+    }
+
+    pub fn toggle_style_analysis(&mut self, _: &ToggleStyleAnalysis, _window: &mut Window, cx: &mut Context<Self>) {
+        let enabled = self.analyzer.is_none();
+        self.set_style_analysis(enabled, cx);
+        cx.emit(EditorEvent::StyleAnalysisChanged(enabled));
+    }
+
+    /// The style analysis of the current text, or `None` while it's off.
+    /// Only paragraphs that changed since the last call are re-read.
+    pub fn analysis(&mut self) -> Option<&Analysis> {
+        self.analyzer.as_ref()?;
+        let hash = self.content_hash();
+        if self.analyzed != Some(hash) {
+            let analyzer = self.analyzer.as_mut()?;
+            self.analysis = analyzer.analyze(&self.content);
+            self.analyzed = Some(hash);
+        }
+        Some(&self.analysis)
+    }
+
+    /// The misspelled word the cursor is in or just after.
+    pub fn misspelling_at_cursor(&self) -> Option<Range<usize>> {
+        let cursor = self.cursor_offset;
+        self.misspelled
+            .iter()
+            .find(|range| range.start <= cursor && cursor <= range.end)
+            .cloned()
+    }
+
+    /// Replaces `range` as if the writer had selected it and typed `text`.
+    pub fn replace(&mut self, range: Range<usize>, text: &str, cx: &mut Context<Self>) {
+        self.selected_range = range.clone();
+        self.selection_anchor = range.start;
+        self.cursor_offset = range.end;
+        self.replace_text(&range, text, cx);
+    }
+
+    pub fn set_focus_mode(&mut self, enabled: bool, cx: &mut Context<Self>) {
+        self.focus_mode = enabled;
+        cx.notify();
+    }
+
+    pub fn set_plain(&mut self, plain: bool, cx: &mut Context<Self>) {
+        if self.plain != plain {
+            self.plain = plain;
+            cx.notify();
+        }
+    }
+
+    /// Turns Vim-style modal editing on, starting in normal mode, or off.
+    pub fn set_vim(&mut self, enabled: bool, cx: &mut Context<Self>) {
+        self.vim = enabled.then(Vim::default);
+        cx.notify();
+    }
+
+    /// Turns the Emacs-style Ctrl shortcuts on or off.
+    pub fn set_emacs(&mut self, enabled: bool, cx: &mut Context<Self>) {
+        self.emacs = enabled;
+        cx.notify();
+    }
+
+    /// The Vim mode the editor is in, or `None` while Vim editing is off.
+    pub fn vim_mode(&self) -> Option<Mode> {
+        self.vim.as_ref().map(|vim| vim.mode)
+    }
+
+    fn set_vim_mode(&mut self, mode: Mode, cx: &mut Context<Self>) {
+        let cursor = self.cursor_offset;
+        let Some(vim) = &mut self.vim else { return };
+        if vim.mode == mode {
+            return;
+        }
+        vim.mode = mode;
+        vim.anchor = cursor;
+        vim.insert_start = cursor;
+        cx.emit(EditorEvent::VimModeChanged);
+        cx.notify();
+    }
+
+    /// In Vim's normal and visual modes keys are commands rather than text.
+    /// Escape, which jid's own shortcuts leave alone while Vim is on, goes
+    /// back to normal mode.
+    fn on_key_down(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(mode) = self.vim_mode() else { return };
+        let keystroke = &event.keystroke;
+        let modifiers = keystroke.modifiers;
+        if keystroke.key == "escape" || (modifiers.control && keystroke.key == "[") {
+            cx.stop_propagation();
+            self.vim_escape(cx);
+            return;
+        }
+        if mode == Mode::Insert || modifiers.platform || modifiers.alt || modifiers.function {
+            return;
+        }
+        cx.stop_propagation();
+        if modifiers.control {
+            if keystroke.key == "r" {
+                for _ in 0..self.vim_count() {
+                    self.redo(&Redo, window, cx);
+                }
+                self.vim_settle(cx);
+            }
+            return;
+        }
+        let key = match keystroke.key.as_str() {
+            "left" | "backspace" => Some('h'),
+            "right" => Some('l'),
+            "up" => Some('k'),
+            "down" | "enter" => Some('j'),
+            "delete" => Some('x'),
+            _ => keystroke.key_char.as_deref().and_then(|text| {
+                let mut chars = text.chars();
+                chars.next().filter(|_| chars.next().is_none())
+            }),
+        };
+        if let Some(key) = key {
+            self.vim_key(key, window, cx);
+        }
+    }
+
+    /// Takes the count typed so far, as for `3` before Ctrl+R.
+    fn vim_count(&mut self) -> usize {
+        let Some(vim) = &mut self.vim else { return 1 };
+        let count = vim.pending.parse().unwrap_or(1);
+        vim.pending.clear();
+        count
+    }
+
+    fn vim_key(&mut self, key: char, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(vim) = &mut self.vim else { return };
+        vim.pending.push(key);
+        match parse_command(&vim.pending, vim.mode) {
+            Parse::Pending => {}
+            Parse::Invalid => vim.pending.clear(),
+            Parse::Done(command) => {
+                vim.pending.clear();
+                self.run_vim(command, window, cx);
+            }
+        }
+    }
+
+    fn run_vim(&mut self, command: VimCommand, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(mode) = self.vim_mode() else { return };
+        self.cursors.clear();
+        // Changes made in normal mode are the ones `.` repeats.
+        let repeatable = mode == Mode::Normal && command.is_change();
+        if let Some(vim) = &mut self.vim {
+            if repeatable {
+                vim.last_change = Some((command, String::new()));
+            }
+            vim.inserting_change = repeatable;
+        }
+        let cursor = self.cursor_offset;
+        match command.action {
+            VimAction::Move(motion) => {
+                let target = motion_target(&self.content, cursor, motion, command.count);
+                let target = self.skip_folds(target, target > cursor);
+                if mode == Mode::Visual {
+                    let head = if motion == VimMotion::LineEnd { target } else { self.vim_on_char(target) };
+                    self.vim_select(head, cx);
+                } else {
+                    self.move_to(target, cx);
+                }
+            }
+            VimAction::Operate(operator, target) => {
+                let (range, linewise) = self.vim_range(operator, target, command.count);
+                self.vim_operate(operator, range, linewise, command.register, cx);
+            }
+            VimAction::Select { object, around } => {
+                let (range, _) = text_object(&self.content, cursor, object, around);
+                if !range.is_empty() {
+                    if let Some(vim) = &mut self.vim {
+                        vim.anchor = vim.anchor.min(range.start);
+                    }
+                    self.vim_select(cursor.max(previous_char(&self.content, range.end)), cx);
+                }
+            }
+            VimAction::Insert(at) => self.vim_insert(at, cx),
+            VimAction::Put { before } => self.vim_put(before, command, cx),
+            VimAction::Undo => {
+                for _ in 0..command.times() {
+                    self.undo(&Undo, window, cx);
+                }
+            }
+            VimAction::Repeat => self.vim_repeat(command, window, cx),
+            VimAction::ToggleVisual => {
+                if mode == Mode::Visual {
+                    self.set_vim_mode(Mode::Normal, cx);
+                    self.move_to(cursor, cx);
+                } else {
+                    self.set_vim_mode(Mode::Visual, cx);
+                    self.vim_select(cursor, cx);
+                }
+            }
+        }
+        self.vim_settle(cx);
+    }
+
+    /// In normal mode the cursor sits on a character, so it can't be past
+    /// the end of a line that has any.
+    fn vim_on_char(&self, offset: usize) -> usize {
+        let line = line_at(&self.content, offset);
+        if offset == line.end && offset > line.start {
+            previous_char(&self.content, offset)
+        } else {
+            offset
+        }
+    }
+
+    fn vim_settle(&mut self, cx: &mut Context<Self>) {
+        if self.vim_mode() == Some(Mode::Normal) {
+            let cursor = self.vim_on_char(self.cursor_offset);
+            if cursor != self.cursor_offset || !self.selected_range.is_empty() {
+                self.move_to(cursor, cx);
+            }
+        }
+    }
+
+    /// Selects from where visual mode started to `head`, both included.
+    fn vim_select(&mut self, head: usize, cx: &mut Context<Self>) {
+        let anchor = self.vim.as_ref().map_or(head, |vim| vim.anchor);
+        self.selected_range = anchor.min(head)..next_char(&self.content, anchor.max(head));
+        self.selection_anchor = anchor;
+        self.cursor_offset = head;
+        self.pending_scroll_to_cursor = true;
+        cx.notify();
+    }
+
+    /// What `operator` acts on, and whether that's whole lines.
+    fn vim_range(&self, operator: Operator, target: Target, count: Option<usize>) -> (Range<usize>, bool) {
+        match target {
+            Target::Selection => (self.selected_range.clone(), false),
+            target => operator_range(&self.content, self.cursor_offset, operator, target, count),
+        }
+    }
+
+    fn vim_operate(
+        &mut self,
+        operator: Operator,
+        range: Range<usize>,
+        linewise: bool,
+        register: Option<char>,
+        cx: &mut Context<Self>,
+    ) {
+        // Forward-only mode keeps what's written, so `d` and `c` only copy.
+        let operator = if self.forward_only { Operator::Yank } else { operator };
+        let cursor = self.cursor_offset;
+        let text = &self.content[range.clone()];
+        let yanked = if linewise {
+            format!("{}\n", text)
+        } else {
+            text.to_string()
+        };
+        self.vim_yank(register, yanked, cx);
+        self.set_vim_mode(Mode::Normal, cx);
+        match operator {
+            Operator::Yank => {
+                let keep = linewise && range.contains(&cursor);
+                self.move_to(if keep { cursor } else { range.start }, cx);
+            }
+            Operator::Delete if linewise => {
+                let lines = with_line_break(&self.content, range);
+                self.replace_text(&lines, "", cx);
+                let start = lines.start.min(self.content.len());
+                self.move_to(first_non_blank(&self.content, start), cx);
+            }
+            Operator::Delete => self.replace_text(&range, "", cx),
+            Operator::Change => {
+                self.replace_text(&range, "", cx);
+                self.set_vim_mode(Mode::Insert, cx);
+            }
+        }
+    }
+
+    /// Puts `text` in `register`: `a` to `z`, adding to it for `A` to `Z`,
+    /// nowhere for `_`, and otherwise the clipboard.
+    fn vim_yank(&mut self, register: Option<char>, text: String, cx: &mut Context<Self>) {
+        let Some(vim) = &mut self.vim else { return };
+        match register {
+            Some('_') => {}
+            Some(name) if name.is_ascii_lowercase() => {
+                vim.registers.insert(name, text);
+            }
+            Some(name) if name.is_ascii_uppercase() => {
+                vim.registers.entry(name.to_ascii_lowercase()).or_default().push_str(&text);
+            }
+            _ => cx.write_to_clipboard(ClipboardItem::new_string(text)),
+        }
+    }
+
+    fn vim_register(&self, register: Option<char>, cx: &mut Context<Self>) -> Option<String> {
+        match register {
+            Some('_') => None,
+            Some(name) if name.is_ascii_alphabetic() => {
+                self.vim.as_ref()?.registers.get(&name.to_ascii_lowercase()).cloned()
+            }
+            _ => cx.read_from_clipboard().and_then(|item| item.text()),
+        }
+    }
+
+    /// Pastes after the cursor, or before it. Text ending in a line break
+    /// is whole lines, and goes below or above the cursor's line.
+    fn vim_put(&mut self, before: bool, command: VimCommand, cx: &mut Context<Self>) {
+        let Some(text) = self.vim_register(command.register, cx).filter(|text| !text.is_empty()) else {
+            return;
+        };
+        let text = text.repeat(command.times());
+        let cursor = self.cursor_offset;
+        let line = line_at(&self.content, cursor);
+        if !text.ends_with('\n') {
+            let at = if before {
+                cursor
+            } else {
+                next_char(&self.content, cursor).min(line.end)
+            };
+            self.replace_text(&(at..at), &text, cx);
+            self.move_to(previous_char(&self.content, at + text.len()), cx);
+        } else if before || line.end < self.content.len() {
+            let at = if before { line.start } else { line.end + 1 };
+            self.replace_text(&(at..at), &text, cx);
+            self.move_to(first_non_blank(&self.content, at), cx);
+        } else {
+            // Below the last line, which has no line break to go after.
+            let text = format!("\n{}", &text[..text.len() - 1]);
+            self.replace_text(&(line.end..line.end), &text, cx);
+            self.move_to(first_non_blank(&self.content, line.end + 1), cx);
+        }
+    }
+
+    fn vim_insert(&mut self, at: InsertAt, cx: &mut Context<Self>) {
+        let cursor = self.cursor_offset;
+        let line = line_at(&self.content, cursor);
+        match at {
+            InsertAt::Cursor => {}
+            InsertAt::After => self.move_to(next_char(&self.content, cursor).min(line.end), cx),
+            InsertAt::LineStart => self.move_to(first_non_blank(&self.content, cursor), cx),
+            InsertAt::LineEnd => self.move_to(line.end, cx),
+            InsertAt::LineBelow => self.replace_text(&(line.end..line.end), "\n", cx),
+            InsertAt::LineAbove => {
+                self.replace_text(&(line.start..line.start), "\n", cx);
+                self.move_to(line.start, cx);
+            }
+        }
+        self.set_vim_mode(Mode::Insert, cx);
+    }
+
+    /// Does the last change again, typing what was typed after it.
+    fn vim_repeat(&mut self, command: VimCommand, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((change, typed)) = self.vim.as_ref().and_then(|vim| vim.repeat(command.count)) else {
+            return;
+        };
+        self.run_vim(change, window, cx);
+        if self.vim_mode() == Some(Mode::Insert) {
+            let cursor = self.cursor_offset;
+            self.replace_text(&(cursor..cursor), &typed, cx);
+            self.vim_escape(cx);
+        }
+        if let Some(vim) = &mut self.vim {
+            vim.last_change = Some((change, typed));
+        }
+    }
+
+    /// Drops a half-typed command, or else goes back to normal mode. Leaving
+    /// insert mode steps back onto the last character typed, as in Vim.
+    fn vim_escape(&mut self, cx: &mut Context<Self>) {
+        let cursor = self.cursor_offset;
+        let Some(vim) = &mut self.vim else { return };
+        if !vim.pending.is_empty() {
+            vim.pending.clear();
+            return;
+        }
+        match vim.mode {
+            Mode::Insert => {
+                if vim.inserting_change {
+                    let typed = self.content.get(vim.insert_start..cursor).unwrap_or_default();
+                    if let Some((_, text)) = &mut vim.last_change {
+                        *text = typed.to_string();
+                    }
+                }
+                self.set_vim_mode(Mode::Normal, cx);
+                if cursor > line_at(&self.content, cursor).start {
+                    self.move_to(previous_char(&self.content, cursor), cx);
+                }
+            }
+            Mode::Visual => {
+                self.set_vim_mode(Mode::Normal, cx);
+                self.move_to(cursor, cx);
+            }
+            Mode::Normal => {
+                self.cursors.clear();
+                cx.notify();
+            }
+        }
+    }
+
+    pub fn is_forward_only(&self) -> bool {
+        self.forward_only
+    }
+
+    pub fn set_forward_only(&mut self, enabled: bool, cx: &mut Context<Self>) {
+        self.forward_only = enabled;
+        self.frontier = None;
+        self.cursors.clear();
+        cx.notify();
+    }
+
+    pub fn toggle_forward_only(&mut self, _: &ToggleForwardOnly, _window: &mut Window, cx: &mut Context<Self>) {
+        self.forward_only = !self.forward_only;
+        self.frontier = None;
+        self.cursors.clear();
+        cx.emit(EditorEvent::ForwardOnlyChanged(self.forward_only));
+        cx.notify();
+    }
+
+    /// In forward-only mode, narrows a range about to be replaced to what
+    /// may still be changed: text inside the word ending at the frontier,
+    /// or the IME composition in progress. Anything else collapses to its
+    /// end, so typing over a selection inserts after it instead. Returns
+    /// `None` when that end is behind the frontier, where nothing may be
+    /// written.
+    fn forward_range(&self, range: Range<usize>, marked: Option<&Range<usize>>) -> Option<Range<usize>> {
+        if !self.forward_only || marked == Some(&range) {
+            return Some(range);
+        }
+        let Some(frontier) = self.frontier else {
+            return Some(range.end..range.end);
+        };
+        let word_start = self.content[..frontier]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(0);
+        if range.end == frontier && range.start >= word_start {
+            Some(range)
+        } else {
+            Some(range.end..range.end).filter(|_| range.end >= frontier)
+        }
+    }
+
     pub fn toggle_focus_mode(&mut self, _: &ToggleFocusMode, _window: &mut Window, cx: &mut Context<Self>) {
         self.focus_mode = !self.focus_mode;
         cx.emit(EditorEvent::FocusModeChanged(self.focus_mode));
@@ -110,22 +1151,36 @@ impl EditorView {
     }
 
     fn left(&mut self, _: &Left, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.move_cursors(Motion::Left, false, cx);
+            return;
+        }
         if self.selected_range.is_empty() {
-            self.move_to(self.previous_boundary(self.selected_range.start), cx);
+            let offset = self.previous_boundary(self.selected_range.start);
+            self.move_to(self.skip_folds(offset, false), cx);
         } else {
             self.move_to(self.selected_range.start, cx);
         }
     }
 
     fn right(&mut self, _: &Right, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.move_cursors(Motion::Right, false, cx);
+            return;
+        }
         if self.selected_range.is_empty() {
-            self.move_to(self.next_boundary(self.selected_range.end), cx);
+            let offset = self.next_boundary(self.selected_range.end);
+            self.move_to(self.skip_folds(offset, true), cx);
         } else {
             self.move_to(self.selected_range.end, cx);
         }
     }
 
     fn up(&mut self, _: &Up, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.move_cursors(Motion::Up, false, cx);
+            return;
+        }
         if let Some(layout) = &self.last_layout {
             let cursor = self.selected_range.start;
             if let Some((line_idx, _)) = self.line_for_offset(cursor) {
@@ -143,6 +1198,10 @@ impl EditorView {
     }
 
     fn down(&mut self, _: &Down, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.move_cursors(Motion::Down, false, cx);
+            return;
+        }
         if let Some(layout) = &self.last_layout {
             let cursor = self.selected_range.start;
             if let Some((line_idx, _)) = self.line_for_offset(cursor) {
@@ -156,20 +1215,32 @@ impl EditorView {
                 }
             }
         }
-        self.move_to(self.content.len(), cx);
+        self.move_to(self.skip_folds(self.content.len(), false), cx);
     }
 
     fn select_left(&mut self, _: &SelectLeft, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.move_cursors(Motion::Left, true, cx);
+            return;
+        }
         let new_end = self.previous_boundary(self.selected_range.end);
-        self.select_to(new_end, cx);
+        self.select_to(self.skip_folds(new_end, false), cx);
     }
 
     fn select_right(&mut self, _: &SelectRight, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.move_cursors(Motion::Right, true, cx);
+            return;
+        }
         let new_end = self.next_boundary(self.selected_range.end);
-        self.select_to(new_end, cx);
+        self.select_to(self.skip_folds(new_end, true), cx);
     }
 
     fn select_up(&mut self, _: &SelectUp, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.move_cursors(Motion::Up, true, cx);
+            return;
+        }
         if let Some(layout) = &self.last_layout.clone() {
             let cursor = self.cursor_offset;
             if let Some((line_idx, _)) = self.line_for_offset(cursor) {
@@ -187,6 +1258,10 @@ impl EditorView {
     }
 
     fn select_down(&mut self, _: &SelectDown, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.move_cursors(Motion::Down, true, cx);
+            return;
+        }
         if let Some(layout) = &self.last_layout.clone() {
             let cursor = self.cursor_offset;
             if let Some((line_idx, _)) = self.line_for_offset(cursor) {
@@ -200,7 +1275,23 @@ impl EditorView {
                 }
             }
         }
-        self.select_to(self.content.len(), cx);
+        self.select_to(self.skip_folds(self.content.len(), false), cx);
+    }
+
+    fn line_start(&mut self, _: &LineStart, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.move_cursors(Motion::LineStart, false, cx);
+            return;
+        }
+        self.move_to(line_at(&self.content, self.cursor_offset).start, cx);
+    }
+
+    fn line_end(&mut self, _: &LineEnd, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.move_cursors(Motion::LineEnd, false, cx);
+            return;
+        }
+        self.move_to(line_at(&self.content, self.cursor_offset).end, cx);
     }
 
     fn select_to(&mut self, offset: usize, cx: &mut Context<Self>) {
@@ -211,6 +1302,225 @@ impl EditorView {
         cx.notify();
     }
 
+    /// The main cursor, facing the way the selection was made.
+    fn main_cursor(&self) -> Cursor {
+        let range = &self.selected_range;
+        if self.cursor_offset == range.start && !range.is_empty() {
+            Cursor {
+                anchor: range.end,
+                head: range.start,
+            }
+        } else {
+            Cursor {
+                anchor: range.start,
+                head: range.end,
+            }
+        }
+    }
+
+    fn set_main_cursor(&mut self, cursor: Cursor) {
+        self.selected_range = cursor.range();
+        self.selection_anchor = cursor.anchor;
+        self.cursor_offset = cursor.head;
+    }
+
+    /// Every cursor, the main one included, in document order.
+    fn all_cursors(&self) -> Vec<Cursor> {
+        let mut cursors = self.cursors.clone();
+        cursors.push(self.main_cursor());
+        cursors.sort_by_key(|cursor| cursor.range().start);
+        cursors
+    }
+
+    /// Joins cursors that have run into each other. The main cursor takes
+    /// in any it meets.
+    fn merge_cursors(&mut self) {
+        let mut main = self.main_cursor();
+        let mut extra = std::mem::take(&mut self.cursors);
+        extra.sort_by_key(|cursor| cursor.range().start);
+        let mut cursors: Vec<Cursor> = Vec::new();
+        for cursor in extra {
+            if cursor.overlaps(&main) {
+                main = main.union(&cursor);
+                continue;
+            }
+            match cursors.last_mut() {
+                Some(last) if last.overlaps(&cursor) => *last = last.union(&cursor),
+                _ => cursors.push(cursor),
+            }
+        }
+        cursors.retain(|cursor| !cursor.overlaps(&main));
+        self.cursors = cursors;
+        self.set_main_cursor(main);
+    }
+
+    /// The offset in the same column of the visual line above or below
+    /// `offset`, or the start or end of the document past the first or
+    /// last line.
+    fn offset_on_next_line(&self, offset: usize, below: bool) -> usize {
+        if let (Some(layout), Some((line_idx, range))) = (&self.last_layout, self.line_for_offset(offset)) {
+            let next_idx = if below { line_idx + 1 } else { line_idx.wrapping_sub(1) };
+            if let Some(next) = layout.line_ranges.get(next_idx) {
+                return next.start + (offset - range.start).min(next.len());
+            }
+        }
+        if below {
+            self.skip_folds(self.content.len(), false)
+        } else {
+            0
+        }
+    }
+
+    /// Where `cursor` ends up after `motion`, taking its selection along
+    /// when `extend` is set. Moving without extending collapses a
+    /// selection to its edge, as it does for the main cursor alone.
+    fn moved(&self, cursor: Cursor, motion: Motion, extend: bool) -> Cursor {
+        let range = cursor.range();
+        let head = match motion {
+            Motion::Left if !extend && !range.is_empty() => range.start,
+            Motion::Right if !extend && !range.is_empty() => range.end,
+            Motion::Left => self.skip_folds(self.previous_boundary(cursor.head), false),
+            Motion::Right => self.skip_folds(self.next_boundary(cursor.head), true),
+            Motion::Up => self.offset_on_next_line(cursor.head, false),
+            Motion::Down => self.offset_on_next_line(cursor.head, true),
+            Motion::LineStart => line_at(&self.content, cursor.head).start,
+            Motion::LineEnd => line_at(&self.content, cursor.head).end,
+        };
+        if extend {
+            Cursor {
+                anchor: cursor.anchor,
+                head,
+            }
+        } else {
+            Cursor::at(head)
+        }
+    }
+
+    fn move_cursors(&mut self, motion: Motion, extend: bool, cx: &mut Context<Self>) {
+        let main = self.moved(self.main_cursor(), motion, extend);
+        self.cursors = self
+            .cursors
+            .iter()
+            .map(|&cursor| self.moved(cursor, motion, extend))
+            .collect();
+        self.set_main_cursor(main);
+        self.merge_cursors();
+        self.pending_scroll_to_cursor = true;
+        cx.notify();
+    }
+
+    /// Makes an edit at every cursor as one undoable change. `edit_for` is
+    /// given each cursor's selection, in document order, and returns the
+    /// range to replace and what with. Every cursor ends up after its
+    /// replacement.
+    fn edit_cursors(
+        &mut self,
+        edit_for: impl Fn(&Self, usize, Range<usize>) -> (Range<usize>, String),
+        cx: &mut Context<Self>,
+    ) {
+        let main_start = self.selected_range.start;
+        let mut edits = Vec::new();
+        let mut previous_end = 0;
+        for (ix, cursor) in self.all_cursors().into_iter().enumerate() {
+            let selection = cursor.range();
+            let is_main = selection.start == main_start;
+            let (mut range, text) = edit_for(self, ix, selection);
+            // Deleting back from one cursor mustn't reach into the last.
+            range.start = range.start.max(previous_end);
+            range.end = range.end.max(range.start);
+            previous_end = range.end;
+            edits.push((range, text, is_main));
+        }
+
+        let start = edits.first().map_or(0, |(range, _, _)| range.start);
+        let mut new_text = String::new();
+        let mut heads = Vec::new();
+        let mut at = start;
+        for (range, text, is_main) in &edits {
+            new_text.push_str(&self.content[at..range.start]);
+            new_text.push_str(text);
+            heads.push((start + new_text.len(), *is_main));
+            at = range.end;
+        }
+        let changed = start..previous_end;
+        let modified = self.content[changed.clone()] != new_text;
+        if modified {
+            // Folds between the cursors stay folded; only the pieces that
+            // change move them.
+            for (range, text, _) in edits.iter().rev() {
+                self.shift_folds(range, text.len());
+            }
+            self.pairs.clear();
+            self.push_undo(&changed, &new_text);
+            self.content.replace_range(changed, &new_text);
+            // Text typed in several places isn't one run for write-or-lose.
+            self.recent = None;
+            self.marked_range = None;
+            self.modified = true;
+        }
+
+        self.cursors = heads
+            .iter()
+            .filter(|(_, is_main)| !is_main)
+            .map(|&(head, _)| Cursor::at(head))
+            .collect();
+        let main = heads.iter().find(|(_, is_main)| *is_main).map_or(start, |&(head, _)| head);
+        self.set_main_cursor(Cursor::at(main));
+        self.merge_cursors();
+        self.pending_scroll_to_cursor = true;
+        cx.notify();
+        if modified {
+            cx.emit(self.edit_event());
+        }
+    }
+
+    /// Adds a cursor selecting the next place the selected text appears,
+    /// wrapping around to the top. With nothing selected it selects the
+    /// word at the cursor first.
+    fn select_next_occurrence(&mut self, _: &SelectNextOccurrence, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.forward_only {
+            return;
+        }
+        let selection = self.selected_range.clone();
+        if selection.is_empty() {
+            if let Some(word) = word_at(&self.content, selection.start) {
+                self.set_main_cursor(Cursor {
+                    anchor: word.start,
+                    head: word.end,
+                });
+                cx.notify();
+            }
+            return;
+        }
+        let needle = &self.content[selection.clone()];
+        let cursors = self.all_cursors();
+        let found = self.content[selection.end..]
+            .match_indices(needle)
+            .map(|(ix, _)| selection.end + ix)
+            .chain(self.content[..selection.start].match_indices(needle).map(|(ix, _)| ix))
+            .map(|start| Cursor {
+                anchor: start,
+                head: start + needle.len(),
+            })
+            .find(|found| !cursors.iter().any(|cursor| cursor.overlaps(found)));
+        let Some(found) = found else { return };
+        self.cursors.push(self.main_cursor());
+        self.unfold_at(found.anchor);
+        self.set_main_cursor(found);
+        self.merge_cursors();
+        self.pending_scroll_to_cursor = true;
+        cx.notify();
+    }
+
+    fn clear_cursors(&mut self, _: &ClearCursors, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.cursors.is_empty() {
+            cx.propagate();
+            return;
+        }
+        self.cursors.clear();
+        cx.notify();
+    }
+
     fn line_for_offset(&self, offset: usize) -> Option<(usize, &Range<usize>)> {
         self.last_layout.as_ref().and_then(|layout| {
             layout.line_ranges.iter().enumerate().find(|(_, range)| {
@@ -220,6 +1530,30 @@ impl EditorView {
     }
 
     fn backspace(&mut self, _: &Backspace, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.edit_cursors(
+                |this, _, range| {
+                    if range.is_empty() {
+                        (this.previous_boundary(range.start)..range.start, String::new())
+                    } else {
+                        (range, String::new())
+                    }
+                },
+                cx,
+            );
+            return;
+        }
+        if self.auto_pair && !self.forward_only && self.selected_range.is_empty() {
+            // Right after a pair was inserted, backspace takes back both halves.
+            if let Some(pair) = self.open_pair() {
+                let opener = self.content[pair.start..].chars().next().map_or(0, char::len_utf8);
+                let closer = self.content[..pair.end].chars().next_back().map_or(0, char::len_utf8);
+                if pair.start + opener == self.cursor_offset && self.cursor_offset + closer == pair.end {
+                    self.replace_text(&pair, "", cx);
+                    return;
+                }
+            }
+        }
         if self.selected_range.is_empty() {
             let prev = self.previous_boundary(self.selected_range.start);
             self.selected_range = prev..self.selected_range.start;
@@ -228,6 +1562,19 @@ impl EditorView {
     }
 
     fn delete(&mut self, _: &Delete, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.edit_cursors(
+                |this, _, range| {
+                    if range.is_empty() {
+                        (range.end..this.next_boundary(range.end), String::new())
+                    } else {
+                        (range, String::new())
+                    }
+                },
+                cx,
+            );
+            return;
+        }
         if self.selected_range.is_empty() {
             let next = self.next_boundary(self.selected_range.end);
             self.selected_range = self.selected_range.end..next;
@@ -235,38 +1582,279 @@ impl EditorView {
         self.replace_text(&self.selected_range.clone(), "", cx);
     }
 
+    /// Enter carries list markers and quotes over to the new line. In
+    /// forward-only mode nothing already written is renumbered or removed.
     fn newline(&mut self, _: &Newline, _window: &mut Window, cx: &mut Context<Self>) {
-        self.replace_text(&self.selected_range.clone(), "\n", cx);
+        if !self.cursors.is_empty() {
+            self.edit_cursors(|_, _, range| (range, "\n".to_string()), cx);
+            return;
+        }
+        let list_edit = if self.selected_range.is_empty() {
+            continue_list(&self.content, self.cursor_offset, !self.forward_only)
+        } else {
+            None
+        };
+        match list_edit {
+            Some(edit) => self.apply_list_edit(edit, cx),
+            None => self.replace_text(&self.selected_range.clone(), "\n", cx),
+        }
+    }
+
+    /// Tab nests the list items in the selection; elsewhere it types a tab.
+    fn indent(&mut self, _: &Indent, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.edit_cursors(|_, _, range| (range, "\t".to_string()), cx);
+            return;
+        }
+        if !self.forward_only && in_list_item(&self.content, self.selected_range.start) {
+            if let Some(edit) = indent_list(&self.content, self.selected_range.clone(), false) {
+                self.apply_list_edit(edit, cx);
+            }
+            return;
+        }
+        self.replace_text(&self.selected_range.clone(), "\t", cx);
+    }
+
+    fn outdent(&mut self, _: &Outdent, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.forward_only {
+            return;
+        }
+        if let Some(edit) = indent_list(&self.content, self.selected_range.clone(), true) {
+            self.apply_list_edit(edit, cx);
+        }
+    }
+
+    fn toggle_bold(&mut self, _: &ToggleBold, _window: &mut Window, cx: &mut Context<Self>) {
+        let edit = toggle_emphasis(&self.content, self.selected_range.clone(), Emphasis::Bold);
+        self.apply_format_edit(edit, cx);
+    }
+
+    fn toggle_italic(&mut self, _: &ToggleItalic, _window: &mut Window, cx: &mut Context<Self>) {
+        let edit = toggle_emphasis(&self.content, self.selected_range.clone(), Emphasis::Italic);
+        self.apply_format_edit(edit, cx);
+    }
+
+    fn insert_link(&mut self, _: &InsertLink, _window: &mut Window, cx: &mut Context<Self>) {
+        let edit = insert_link(&self.content, self.selected_range.clone());
+        self.apply_format_edit(edit, cx);
+    }
+
+    fn set_heading(&mut self, action: &SetHeading, _window: &mut Window, cx: &mut Context<Self>) {
+        let edit = set_heading(&self.content, self.cursor_offset, Some(action.0));
+        self.apply_format_edit(edit, cx);
+    }
+
+    fn cycle_heading(&mut self, _: &CycleHeading, _window: &mut Window, cx: &mut Context<Self>) {
+        let edit = set_heading(&self.content, self.cursor_offset, None);
+        self.apply_format_edit(edit, cx);
+    }
+
+    /// Formatting rewrites what's already there, so it's off in
+    /// forward-only mode.
+    fn apply_format_edit(&mut self, edit: FormatEdit, cx: &mut Context<Self>) {
+        if self.forward_only || (edit.range.is_empty() && edit.text.is_empty()) {
+            return;
+        }
+        self.replace_text(&edit.range, &edit.text, cx);
+        self.selected_range = edit.selection.clone();
+        self.selection_anchor = edit.selection.start;
+        self.cursor_offset = edit.selection.end;
+    }
+
+    /// Makes a list command's edits as one undoable change. The edit at the
+    /// cursor counts as typing; renumbering elsewhere only moves things.
+    fn apply_list_edit(&mut self, edit: ListEdit, cx: &mut Context<Self>) {
+        match edit.edits.as_slice() {
+            [] => {}
+            [(range, text)] => self.replace_text(range, text, cx),
+            [(first, _), .., (last, _)] => {
+                let span = first.start..last.end;
+                let mut new_text = String::new();
+                let mut at = span.start;
+                for (range, text) in &edit.edits {
+                    new_text.push_str(&self.content[at..range.start]);
+                    new_text.push_str(text);
+                    at = range.end;
+                }
+                self.push_undo(&span, &new_text);
+                let cursor = self.cursor_offset;
+                for (range, text) in edit.edits.iter().rev() {
+                    self.shift_pairs(range, text.len());
+                    self.shift_folds(range, text.len());
+                    if range.start <= cursor && cursor <= range.end {
+                        self.track_recent(range, text);
+                    } else {
+                        let shift = |o: usize| shifted(o, range, text.len());
+                        self.recent = self.recent.take().map(|recent| shift(recent.start)..shift(recent.end));
+                    }
+                    self.content.replace_range(range.clone(), text);
+                }
+                self.cursors.clear();
+                self.marked_range = None;
+                self.modified = true;
+                self.pending_scroll_to_cursor = true;
+                cx.notify();
+                cx.emit(EditorEvent::Modified);
+            }
+        }
+        self.selected_range = edit.cursor..edit.cursor;
+        self.selection_anchor = edit.cursor;
+        self.cursor_offset = edit.cursor;
     }
 
     fn select_all(&mut self, _: &SelectAll, _window: &mut Window, cx: &mut Context<Self>) {
+        self.cursors.clear();
         self.selected_range = 0..self.content.len();
         self.selection_anchor = 0;
         self.cursor_offset = self.content.len();
         cx.notify();
     }
 
+    /// With several cursors, copies what each one selected, a line apiece.
     fn copy(&mut self, _: &Copy, _window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            let selected: Vec<&str> = self
+                .all_cursors()
+                .iter()
+                .map(|cursor| &self.content[cursor.range()])
+                .filter(|text| !text.is_empty())
+                .collect();
+            if !selected.is_empty() {
+                let text = selected.join("\n");
+                cx.write_to_clipboard(ClipboardItem::new_string(text.clone()));
+                self.kill_ring.push(text);
+            }
+            return;
+        }
         if !self.selected_range.is_empty() {
-            cx.write_to_clipboard(ClipboardItem::new_string(
-                self.content[self.selected_range.clone()].to_string(),
-            ));
+            let text = self.content[self.selected_range.clone()].to_string();
+            cx.write_to_clipboard(ClipboardItem::new_string(text.clone()));
+            self.kill_ring.push(text);
         }
     }
 
-    fn cut(&mut self, _: &Cut, _window: &mut Window, cx: &mut Context<Self>) {
+    fn cut(&mut self, _: &Cut, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.cursors.is_empty() {
+            self.copy(&Copy, window, cx);
+            self.cut = self.kill_ring.newest().map(str::to_string);
+            self.edit_cursors(|_, _, range| (range, String::new()), cx);
+            return;
+        }
         if !self.selected_range.is_empty() {
-            cx.write_to_clipboard(ClipboardItem::new_string(
-                self.content[self.selected_range.clone()].to_string(),
-            ));
+            self.copy(&Copy, window, cx);
+            self.cut = self.kill_ring.newest().map(str::to_string);
             self.replace_text(&self.selected_range.clone(), "", cx);
         }
     }
 
+    /// Text copied from a web page or word processor comes in as markdown,
+    /// keeping its links, headings, lists, emphasis and tables. The HTML is
+    /// fetched in the background, and plain text goes in if it's slow.
     fn paste(&mut self, _: &Paste, _window: &mut Window, cx: &mut Context<Self>) {
+        let Some(text) = cx.read_from_clipboard().and_then(|item| item.text()) else { return };
+        cx.spawn(async move |this, cx| {
+            let markdown = cx
+                .background_executor()
+                .spawn(async move { clipboard_html().and_then(|html| html_to_markdown(&html)) })
+                .await;
+            let _ = this.update(cx, |this, cx| this.paste_text(markdown.unwrap_or(text), cx));
+        })
+        .detach();
+    }
+
+    fn paste_plain(&mut self, _: &PastePlain, _window: &mut Window, cx: &mut Context<Self>) {
         if let Some(text) = cx.read_from_clipboard().and_then(|item| item.text()) {
-            self.replace_text(&self.selected_range.clone(), &text, cx);
+            self.paste_text(text, cx);
+        }
+    }
+
+    /// Pasting a line for each cursor gives every cursor its own line.
+    fn paste_text(&mut self, text: String, cx: &mut Context<Self>) {
+        self.kill_ring.yanked(&text);
+        self.last_yank = None;
+        self.moving = self.cut.take().is_some_and(|cut| cut == text);
+        if !self.cursors.is_empty() {
+            let lines: Vec<&str> = text.split('\n').collect();
+            let spread = lines.len() == self.cursors.len() + 1;
+            self.edit_cursors(
+                |_, ix, range| {
+                    let text = if spread { lines[ix] } else { text.as_str() };
+                    (range, text.to_string())
+                },
+                cx,
+            );
+            self.moving = false;
+            return;
+        }
+        let start = self.selected_range.start;
+        self.replace_text(&self.selected_range.clone(), &text, cx);
+        self.moving = false;
+        self.last_yank = Some(start..self.cursor_offset);
+    }
+
+    /// Straight after a paste or yank, swaps what it put in for the next
+    /// older kill.
+    fn yank_pop(&mut self, _: &YankPop, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.forward_only || !self.cursors.is_empty() || !self.selected_range.is_empty() {
+            return;
+        }
+        let Some(range) = self.last_yank.clone() else { return };
+        if range.end != self.cursor_offset || self.content.get(range.clone()) != self.kill_ring.current() {
+            return;
+        }
+        let Some(text) = self.kill_ring.rotate().map(str::to_string) else { return };
+        self.replace_text(&range, &text, cx);
+        self.last_yank = Some(range.start..self.cursor_offset);
+    }
+
+    /// Kills from the cursor to the end of the line, or the line break when
+    /// the cursor is already there. Killing again without moving adds to
+    /// the same kill.
+    fn kill_to_end_of_line(&mut self, _: &KillToEndOfLine, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.forward_only {
+            return;
+        }
+        let cursor = self.cursor_offset;
+        let mut range = cursor..line_at(&self.content, cursor).end;
+        if range.is_empty() {
+            range.end = next_char(&self.content, cursor);
+        }
+        if range.is_empty() {
+            return;
+        }
+        let text = &self.content[range.clone()];
+        if self.last_kill == Some((cursor, self.content.len())) {
+            self.kill_ring.append(text);
+        } else {
+            self.kill_ring.push(text.to_string());
+        }
+        if let Some(kill) = self.kill_ring.newest() {
+            cx.write_to_clipboard(ClipboardItem::new_string(kill.to_string()));
+        }
+        self.replace_text(&range, "", cx);
+        self.last_kill = Some((self.cursor_offset, self.content.len()));
+    }
+
+    /// Swaps the characters either side of the cursor and moves past them,
+    /// or the two before it at the end of a line.
+    fn transpose(&mut self, _: &Transpose, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.forward_only {
+            return;
+        }
+        let cursor = self.cursor_offset;
+        let line = line_at(&self.content, cursor);
+        let end = if cursor == line.end {
+            cursor
+        } else {
+            self.next_boundary(cursor)
+        };
+        let middle = self.previous_boundary(end);
+        let start = self.previous_boundary(middle);
+        if start < line.start || start == middle {
+            return;
         }
+        let swapped = format!("{}{}", &self.content[middle..end], &self.content[start..middle]);
+        self.replace_text(&(start..end), &swapped, cx);
     }
 
     fn move_to(&mut self, offset: usize, cx: &mut Context<Self>) {
@@ -286,7 +1874,7 @@ impl EditorView {
         }
         self.content[..offset]
             .grapheme_indices(true)
-            .last()
+            .next_back()
             .map(|(i, _)| i)
             .unwrap_or(0)
     }
@@ -305,7 +1893,19 @@ impl EditorView {
             .unwrap_or(self.content.len())
     }
 
+    /// Replaces `range` around the main cursor. Any other cursors are
+    /// dropped, since only the main one takes part.
     fn replace_text(&mut self, range: &Range<usize>, new_text: &str, cx: &mut Context<Self>) {
+        self.cursors.clear();
+        let range = self.forward_range(range.clone(), self.marked_range.as_ref());
+        let Some(range) = &range.filter(|range| !range.is_empty() || !new_text.is_empty()) else {
+            self.selected_range = self.cursor_offset..self.cursor_offset;
+            self.selection_anchor = self.cursor_offset;
+            cx.notify();
+            return;
+        };
+        self.record_edit(range, new_text);
+        self.track_recent(range, new_text);
         self.content = format!(
             "{}{}{}",
             &self.content[..range.start],
@@ -320,30 +1920,170 @@ impl EditorView {
         self.modified = true;
         self.pending_scroll_to_cursor = true;
         cx.notify();
-        cx.emit(EditorEvent::Modified);
+        cx.emit(self.edit_event());
+    }
+
+    fn edit_event(&self) -> EditorEvent {
+        if self.moving {
+            EditorEvent::Rearranged
+        } else {
+            EditorEvent::Modified
+        }
+    }
+
+    fn record_edit(&mut self, range: &Range<usize>, new_text: &str) {
+        self.shift_pairs(range, new_text.len());
+        self.shift_folds(range, new_text.len());
+        self.push_undo(range, new_text);
     }
 
+    fn push_undo(&mut self, range: &Range<usize>, new_text: &str) {
+        let edit = Edit {
+            range: range.start..range.start + new_text.len(),
+            old_text: self.content[range.clone()].to_string(),
+            new_text: new_text.to_string(),
+            selection_before: self.selected_range.clone(),
+            at: Instant::now(),
+        };
+        self.redo_stack.clear();
+        if let Some(last) = self.undo_stack.last_mut() {
+            if last.absorb(&edit) {
+                return;
+            }
+        }
+        self.undo_stack.push(edit);
+    }
+
+    fn undo(&mut self, _: &Undo, _window: &mut Window, cx: &mut Context<Self>) {
+        let Some(edit) = self.undo_stack.pop() else { return };
+        self.shift_folds(&edit.range, edit.old_text.len());
+        self.content.replace_range(edit.range.clone(), &edit.old_text);
+        self.selected_range = edit.selection_before.clone();
+        self.selection_anchor = edit.selection_before.start;
+        self.cursor_offset = edit.selection_before.end;
+        self.redo_stack.push(edit);
+        self.after_history_change(cx);
+    }
+
+    fn redo(&mut self, _: &Redo, _window: &mut Window, cx: &mut Context<Self>) {
+        let Some(edit) = self.redo_stack.pop() else { return };
+        let old_range = edit.range.start..edit.range.start + edit.old_text.len();
+        self.shift_folds(&old_range, edit.new_text.len());
+        self.content.replace_range(old_range, &edit.new_text);
+        let cursor = edit.range.end;
+        self.selected_range = cursor..cursor;
+        self.selection_anchor = cursor;
+        self.cursor_offset = cursor;
+        self.undo_stack.push(edit);
+        self.after_history_change(cx);
+    }
+
+    /// Undo and redo still work in forward-only mode, as a deliberate way
+    /// back, and the draft's leading edge moves to where they leave the
+    /// cursor.
+    fn after_history_change(&mut self, cx: &mut Context<Self>) {
+        if self.forward_only {
+            self.frontier = Some(self.cursor_offset);
+        }
+        self.marked_range = None;
+        self.pairs.clear();
+        self.cursors.clear();
+        self.unfold_at(self.cursor_offset);
+        self.recent = None;
+        self.modified = true;
+        self.pending_scroll_to_cursor = true;
+        cx.notify();
+        cx.emit(EditorEvent::Rearranged);
+    }
+
+    /// Alt-click adds a cursor, and dragging on from there makes a column
+    /// selection. A plain click goes back to a single cursor.
     fn on_mouse_down(&mut self, event: &MouseDownEvent, window: &mut Window, cx: &mut Context<Self>) {
         let offset = self.index_for_position(event.position);
-        self.move_to(offset, cx);
+        if self.vim_mode() == Some(Mode::Visual) {
+            self.set_vim_mode(Mode::Normal, cx);
+        }
+        if event.modifiers.alt && !self.forward_only {
+            self.cursors.push(self.main_cursor());
+            self.column_drag = Some((event.position, self.cursors.clone()));
+            self.set_main_cursor(Cursor::at(offset));
+            self.merge_cursors();
+            cx.notify();
+        } else {
+            self.cursors.clear();
+            self.column_drag = None;
+            self.move_to(offset, cx);
+        }
         window.focus(&self.focus_handle);
     }
 
+    /// Selects the same columns on every line between where an alt-drag
+    /// started and the mouse, with the main cursor on the mouse's line.
+    fn on_mouse_move(&mut self, event: &MouseMoveEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        if event.pressed_button != Some(MouseButton::Left) {
+            return;
+        }
+        let Some((origin, before)) = self.column_drag.clone() else { return };
+        let (Some((from, from_x)), Some((to, to_x))) =
+            (self.line_at_position(origin), self.line_at_position(event.position))
+        else {
+            return;
+        };
+        let lines: Vec<usize> = if from <= to { (from..=to).collect() } else { (to..=from).rev().collect() };
+        let mut column: Vec<Cursor> = lines
+            .into_iter()
+            .map(|line| Cursor {
+                anchor: self.index_on_line(line, from_x),
+                head: self.index_on_line(line, to_x),
+            })
+            .collect();
+        let Some(main) = column.pop() else { return };
+        self.cursors = before;
+        self.cursors.extend(column);
+        self.set_main_cursor(main);
+        self.merge_cursors();
+        cx.notify();
+    }
+
+    fn on_mouse_up(&mut self, _: &MouseUpEvent, _window: &mut Window, _cx: &mut Context<Self>) {
+        self.column_drag = None;
+    }
+
+    /// Right-clicking a misspelled word offers its suggestions.
+    fn on_right_mouse_down(&mut self, event: &MouseDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        self.on_mouse_down(event, window, cx);
+        if self.misspelling_at_cursor().is_some() {
+            window.dispatch_action(Box::new(SuggestSpelling), cx);
+        }
+    }
+
     fn index_for_position(&self, position: Point<Pixels>) -> usize {
-        let Some(content_bounds) = self.last_content_bounds.as_ref() else { return 0 };
-        let Some(layout) = self.last_layout.as_ref() else { return 0 };
+        match self.line_at_position(position) {
+            Some((line_idx, x)) => self.index_on_line(line_idx, x),
+            None => 0,
+        }
+    }
+
+    /// The visual line at `position`, and how far across it that is.
+    fn line_at_position(&self, position: Point<Pixels>) -> Option<(usize, Pixels)> {
+        let content_bounds = self.last_content_bounds.as_ref()?;
+        let layout = self.last_layout.as_ref()?;
 
         if layout.lines.is_empty() || layout.line_ranges.is_empty() {
-            return 0;
+            return None;
         }
 
         let local_y = position.y - content_bounds.top() + self.scroll_y;
         let line_height = px(LINE_HEIGHT);
         let line_idx = ((local_y / line_height).floor() as usize)
-            .max(0)
             .min(layout.lines.len().saturating_sub(1));
 
         let local_x = (position.x - content_bounds.left()).max(px(0.0));
+        Some((line_idx, local_x))
+    }
+
+    fn index_on_line(&self, line_idx: usize, local_x: Pixels) -> usize {
+        let Some(layout) = self.last_layout.as_ref() else { return 0 };
         let line = &layout.lines[line_idx];
         let range = &layout.line_ranges[line_idx];
         let char_offset = line.closest_index_for_x(local_x);
@@ -396,10 +2136,39 @@ impl EntityInputHandler for EditorView {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        // Vim's normal and visual modes take keys as commands, never text.
+        if self.vim_mode().is_some_and(|mode| mode != Mode::Insert) {
+            return;
+        }
+        // Auto-pairing only applies to plain typing, never to text an input
+        // method is composing or committing.
+        let typed = range_utf16.as_ref().is_none_or(|range| *range == self.selected_range)
+            && self.marked_range.is_none();
+        // So does typing at several cursors; anything else takes the main one.
+        if !self.cursors.is_empty() {
+            if typed {
+                self.edit_cursors(|_, _, range| (range, new_text.to_string()), cx);
+                return;
+            }
+            self.cursors.clear();
+        }
+        let mut chars = new_text.chars();
+        if let (true, Some(c), None) = (typed, chars.next(), chars.next()) {
+            if self.auto_pair_typed(c, cx) {
+                return;
+            }
+        }
+        let marked = self.marked_range.take();
         let range = range_utf16
-            .or(self.marked_range.take())
+            .or(marked.clone())
             .unwrap_or(self.selected_range.clone());
+        let Some(range) = self.forward_range(range, marked.as_ref()) else {
+            self.marked_range = marked;
+            return;
+        };
 
+        self.record_edit(&range, new_text);
+        self.track_recent(&range, new_text);
         self.content = format!(
             "{}{}{}",
             &self.content[..range.start],
@@ -411,6 +2180,9 @@ impl EntityInputHandler for EditorView {
         self.selected_range = new_cursor..new_cursor;
         self.selection_anchor = new_cursor;
         self.cursor_offset = new_cursor;
+        if marked.is_none() && new_text.chars().count() == 1 {
+            self.smarten_typed();
+        }
         self.modified = true;
         cx.notify();
         cx.emit(EditorEvent::Modified);
@@ -424,10 +2196,22 @@ impl EntityInputHandler for EditorView {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        if self.vim_mode().is_some_and(|mode| mode != Mode::Insert) {
+            return;
+        }
+        // Composing happens at the main cursor only.
+        self.cursors.clear();
+        let marked = self.marked_range.take();
         let range = range_utf16
-            .or(self.marked_range.take())
+            .or(marked.clone())
             .unwrap_or(self.selected_range.clone());
+        let Some(range) = self.forward_range(range, marked.as_ref()) else {
+            self.marked_range = marked;
+            return;
+        };
 
+        self.record_edit(&range, new_text);
+        self.track_recent(&range, new_text);
         self.content = format!(
             "{}{}{}",
             &self.content[..range.start],
@@ -489,13 +2273,36 @@ actions!(
         SelectUp,
         SelectDown,
         Newline,
+        Indent,
+        Outdent,
         SelectAll,
         Copy,
         Cut,
         Paste,
+        PastePlain,
+        Undo,
+        Redo,
+        ToggleBold,
+        ToggleItalic,
+        InsertLink,
+        CycleHeading,
+        Fold,
+        Unfold,
+        SelectNextOccurrence,
+        ClearCursors,
+        LineStart,
+        LineEnd,
+        KillToEndOfLine,
+        Transpose,
+        YankPop,
     ]
 );
 
+/// Makes the current line a heading of the given level, or body text for 0.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = editor, no_json)]
+pub struct SetHeading(pub usize);
+
 impl Render for EditorView {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let content = self.content.clone();
@@ -511,10 +2318,49 @@ impl Render for EditorView {
         let scroll_y = self.scroll_y;
         let pending_scroll_to_cursor = self.pending_scroll_to_cursor;
         self.pending_scroll_to_cursor = false;
+        let cursor_to_top = std::mem::take(&mut self.scroll_cursor_to_top);
+        // A heading edited into body text no longer folds anything.
+        let text = &self.content;
+        self.folds.retain(|fold| {
+            fold.end <= text.len() && text[text[..fold.start].rfind('\n').map_or(0, |ix| ix + 1)..].starts_with('#')
+        });
+        self.refresh_spelling(cx);
+        // The word being typed isn't finished, so it isn't wrong yet.
+        let plain = self.plain;
+        let misspelled = self
+            .misspelled
+            .iter()
+            .filter(|range| !plain && (range.end != self.cursor_offset || !selected_range.is_empty()))
+            .cloned()
+            .collect();
+        // Sentence tints first so the word-level ones inside them win.
+        let mut highlights: Vec<_> = self
+            .analysis()
+            .filter(|_| !plain)
+            .map(|analysis| {
+                analysis
+                    .issues
+                    .iter()
+                    .map(|issue| (issue.kind.is_sentence(), issue.range.clone(), issue.kind.highlight()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        highlights.sort_by_key(|(sentence, _, _)| !sentence);
+        let highlights = highlights.into_iter().map(|(_, range, color)| (range, color)).collect();
+
+        let mut key_context = KeyContext::default();
+        key_context.add("Editor");
+        if let Some(mode) = self.vim_mode() {
+            key_context.add("vim");
+            key_context.set("vim_mode", mode.name());
+        }
+        if self.emacs {
+            key_context.add("emacs");
+        }
 
         div()
             .id("editor")
-            .key_context("Editor")
+            .key_context(key_context)
             .track_focus(&focus_handle)
             .on_action(cx.listener(Self::backspace))
             .on_action(cx.listener(Self::delete))
@@ -527,12 +2373,37 @@ impl Render for EditorView {
             .on_action(cx.listener(Self::select_up))
             .on_action(cx.listener(Self::select_down))
             .on_action(cx.listener(Self::newline))
+            .on_action(cx.listener(Self::indent))
+            .on_action(cx.listener(Self::outdent))
             .on_action(cx.listener(Self::select_all))
             .on_action(cx.listener(Self::copy))
             .on_action(cx.listener(Self::cut))
             .on_action(cx.listener(Self::paste))
+            .on_action(cx.listener(Self::paste_plain))
+            .on_action(cx.listener(Self::undo))
+            .on_action(cx.listener(Self::redo))
+            .on_action(cx.listener(Self::toggle_bold))
+            .on_action(cx.listener(Self::toggle_italic))
+            .on_action(cx.listener(Self::insert_link))
+            .on_action(cx.listener(Self::set_heading))
+            .on_action(cx.listener(Self::cycle_heading))
+            .on_action(cx.listener(Self::fold))
+            .on_action(cx.listener(Self::unfold))
+            .on_action(cx.listener(Self::select_next_occurrence))
+            .on_action(cx.listener(Self::clear_cursors))
+            .on_action(cx.listener(Self::line_start))
+            .on_action(cx.listener(Self::line_end))
+            .on_action(cx.listener(Self::kill_to_end_of_line))
+            .on_action(cx.listener(Self::transpose))
+            .on_action(cx.listener(Self::yank_pop))
             .on_action(cx.listener(Self::toggle_focus_mode))
+            .on_action(cx.listener(Self::toggle_forward_only))
+            .on_action(cx.listener(Self::toggle_style_analysis))
+            .on_key_down(cx.listener(Self::on_key_down))
             .on_mouse_down(MouseButton::Left, cx.listener(Self::on_mouse_down))
+            .on_mouse_down(MouseButton::Right, cx.listener(Self::on_right_mouse_down))
+            .on_mouse_move(cx.listener(Self::on_mouse_move))
+            .on_mouse_up(MouseButton::Left, cx.listener(Self::on_mouse_up))
             .on_scroll_wheel(cx.listener(Self::on_scroll))
             .size_full()
             .cursor(CursorStyle::IBeam)
@@ -540,12 +2411,19 @@ impl Render for EditorView {
                 EditorElement {
                     content,
                     selected_range,
+                    extra_selections: self.cursors.iter().map(Cursor::range).collect(),
+                    block_cursor: self.vim_mode() == Some(Mode::Normal),
                     is_focused,
                     placeholder_visible,
                     cursor_pos,
                     focus_mode,
+                    faded: self.recent.clone().filter(|_| self.fade > 0.0).map(|recent| (recent, self.fade)),
+                    misspelled,
+                    highlights,
                     scroll_y,
                     pending_scroll_to_cursor,
+                    cursor_to_top,
+                    folds: self.folds.clone(),
                     entity,
                     theme: self.theme.clone(),
                 }
@@ -556,16 +2434,94 @@ impl Render for EditorView {
 struct EditorElement {
     content: String,
     selected_range: Range<usize>,
+    /// What the cursors beside the main one select, or where they are.
+    extra_selections: Vec<Range<usize>>,
+    /// Cover the character under the cursor, as in Vim's normal mode.
+    block_cursor: bool,
     is_focused: bool,
     placeholder_visible: bool,
     cursor_pos: usize,
     focus_mode: bool,
+    /// Recent text to draw faded, and by how much.
+    faded: Option<(Range<usize>, f32)>,
+    misspelled: Vec<Range<usize>>,
+    /// Style analysis tints, later ones drawn over earlier ones.
+    highlights: Vec<(Range<usize>, Hsla)>,
     scroll_y: Pixels,
     pending_scroll_to_cursor: bool,
+    cursor_to_top: bool,
+    folds: Vec<Range<usize>>,
     entity: Entity<EditorView>,
     theme: Theme,
 }
 
+impl EditorElement {
+    /// Whether the line starting at `offset` is hidden in a folded section.
+    fn folded(&self, offset: usize) -> bool {
+        self.folds.iter().any(|fold| fold.start < offset && offset <= fold.end)
+    }
+
+    /// The runs for one visual line: split wherever the line enters or
+    /// leaves a misspelled word, which gets a wavy underline, a style
+    /// analysis tint, or the recent text write-or-lose mode is fading.
+    /// An `obscured` line gets no underlines or tints, which would show
+    /// where its misspellings and adverbs are.
+    fn line_runs(&self, line: Range<usize>, font: &Font, color: Hsla, obscured: bool) -> Vec<TextRun> {
+        let misspelled: Vec<&Range<usize>> = self
+            .misspelled
+            .iter()
+            .filter(|range| !obscured && range.start < line.end && range.end > line.start)
+            .collect();
+        let mut cuts = vec![line.start, line.end];
+        let highlights: Vec<&(Range<usize>, Hsla)> = self
+            .highlights
+            .iter()
+            .filter(|(range, _)| !obscured && range.start < line.end && range.end > line.start)
+            .collect();
+        let ranges = misspelled
+            .iter()
+            .copied()
+            .chain(highlights.iter().map(|(range, _)| range))
+            .chain(self.faded.as_ref().map(|(faded, _)| faded));
+        for range in ranges {
+            cuts.extend([range.start, range.end].map(|cut| cut.clamp(line.start, line.end)));
+        }
+        cuts.sort_unstable();
+        cuts.dedup();
+
+        cuts.windows(2)
+            .map(|cut| {
+                let (start, end) = (cut[0], cut[1]);
+                let color = match &self.faded {
+                    Some((faded, fade)) if faded.start <= start && end <= faded.end => color.opacity(1.0 - fade),
+                    _ => color,
+                };
+                let underline = misspelled
+                    .iter()
+                    .any(|range| range.start <= start && end <= range.end)
+                    .then(|| UnderlineStyle {
+                        thickness: px(1.0),
+                        color: Some(self.theme.misspelled),
+                        wavy: true,
+                    });
+                let background_color = highlights
+                    .iter()
+                    .rev()
+                    .find(|(range, _)| range.start <= start && end <= range.end)
+                    .map(|(_, color)| *color);
+                TextRun {
+                    len: end - start,
+                    font: font.clone(),
+                    color,
+                    background_color,
+                    underline,
+                    strikethrough: None,
+                }
+            })
+            .collect()
+    }
+}
+
 impl IntoElement for EditorElement {
     type Element = Self;
     fn into_element(self) -> Self::Element {
@@ -577,7 +2533,8 @@ struct EditorPrepaintState {
     lines: Vec<ShapedLine>,
     line_ranges: Vec<Range<usize>>,
     scroll_y: Pixels,
-    cursor_pos: Option<(Pixels, Pixels)>,
+    /// Where each cursor is drawn, and how wide.
+    cursors: Vec<(Pixels, Pixels, Pixels)>,
     selections: Vec<Bounds<Pixels>>,
 }
 
@@ -651,6 +2608,10 @@ impl Element for EditorElement {
             for logical_line in self.content.split('\n') {
                 let logical_len = logical_line.len();
                 let logical_end = logical_start + logical_len;
+                if self.folded(logical_start) {
+                    logical_start = logical_end + 1;
+                    continue;
+                }
 
                 if logical_line.is_empty() {
                     temp_ranges.push(logical_start..logical_start);
@@ -711,6 +2672,10 @@ impl Element for EditorElement {
             for logical_line in self.content.split('\n') {
                 let logical_len = logical_line.len();
                 let logical_end = logical_start + logical_len;
+                if self.folded(logical_start) {
+                    logical_start = logical_end + 1;
+                    continue;
+                }
 
                 if logical_line.is_empty() {
                     let is_current = current_visual_line == Some(visual_line_idx);
@@ -783,24 +2748,23 @@ impl Element for EditorElement {
                         };
 
                         let slice = &logical_line[start..break_at];
-                        let display_text = if self.focus_mode
-                            && !is_current
-                            && self.theme.mode == ThemeMode::Midnight
-                        {
-                            rot13(slice)
-                        } else {
-                            slice.to_string()
-                        };
+                        let obscured = self.focus_mode && !is_current && self.theme.mode == ThemeMode::Midnight;
+                        let mut display_text = if obscured { rot13(slice) } else { slice.to_string() };
+                        let mut runs =
+                            self.line_runs((logical_start + start)..(logical_start + break_at), &font, color, obscured);
+                        if break_at == logical_len && self.folds.iter().any(|fold| fold.start == logical_end) {
+                            display_text.push_str(FOLD_MARKER);
+                            runs.push(TextRun {
+                                len: FOLD_MARKER.len(),
+                                font: font.clone(),
+                                color: self.theme.muted,
+                                background_color: None,
+                                underline: None,
+                                strikethrough: None,
+                            });
+                        }
                         let text: SharedString = display_text.into();
-                        let run = TextRun {
-                            len: text.len(),
-                            font: font.clone(),
-                            color,
-                            background_color: None,
-                            underline: None,
-                            strikethrough: None,
-                        };
-                        let shaped = window.text_system().shape_line(text, font_size, &[run], None);
+                        let shaped = window.text_system().shape_line(text, font_size, &runs, None);
                         visual_lines.push(shaped);
                         visual_ranges.push((logical_start + start)..(logical_start + break_at));
 
@@ -826,7 +2790,7 @@ impl Element for EditorElement {
                 let cursor_top = line_height * line_idx as f32;
                 let cursor_bottom = cursor_top + line_height;
 
-                if cursor_top < scroll_y {
+                if cursor_top < scroll_y || self.cursor_to_top {
                     scroll_y = cursor_top;
                 } else if cursor_bottom > scroll_y + viewport_height {
                     scroll_y = cursor_bottom - viewport_height;
@@ -839,75 +2803,82 @@ impl Element for EditorElement {
         }
 
         let mut selections = Vec::new();
-        let cursor_pos;
+        let mut cursors = Vec::new();
 
         if !self.placeholder_visible && self.is_focused {
-            if self.selected_range.is_empty() {
-                let cursor_offset = self.selected_range.start;
-                let mut cursor_line = 0;
-                let mut cursor_col = 0;
-
-                for (i, range) in visual_ranges.iter().enumerate() {
-                    if cursor_offset >= range.start && cursor_offset <= range.end {
-                        cursor_line = i;
-                        cursor_col = cursor_offset - range.start;
-                        break;
-                    }
-                }
-
-                let x = if cursor_line < visual_lines.len() {
-                    visual_lines[cursor_line].x_for_index(cursor_col)
-                } else {
-                    px(0.0)
-                };
-
-                let y = line_height * cursor_line as f32 - scroll_y;
-                cursor_pos = Some((x, y));
-            } else {
-                cursor_pos = None;
-
-                for (line_idx, line_range) in visual_ranges.iter().enumerate() {
-                    let sel_start = self.selected_range.start;
-                    let sel_end = self.selected_range.end;
-
-                    if sel_end <= line_range.start || sel_start > line_range.end {
-                        continue;
+            // Each cursor is drawn as a caret, or as its selection if it has one.
+            for selected_range in std::iter::once(&self.selected_range).chain(&self.extra_selections) {
+                if selected_range.is_empty() {
+                    let cursor_offset = selected_range.start;
+                    let mut cursor_line = 0;
+                    let mut cursor_col = 0;
+
+                    for (i, range) in visual_ranges.iter().enumerate() {
+                        if cursor_offset >= range.start && cursor_offset <= range.end {
+                            cursor_line = i;
+                            cursor_col = cursor_offset - range.start;
+                            break;
+                        }
                     }
 
-                    let start_in_line = if sel_start <= line_range.start {
-                        0
+                    let x = if cursor_line < visual_lines.len() {
+                        visual_lines[cursor_line].x_for_index(cursor_col)
                     } else {
-                        sel_start - line_range.start
+                        px(0.0)
                     };
 
-                    let end_in_line = if sel_end > line_range.end {
-                        line_range.end - line_range.start
-                    } else {
-                        sel_end - line_range.start
+                    let y = line_height * cursor_line as f32 - scroll_y;
+                    let next = self.content[cursor_offset..]
+                        .chars()
+                        .next()
+                        .filter(|&c| c != '\n')
+                        .map(|c| cursor_col + c.len_utf8());
+                    let width = match next {
+                        _ if !self.block_cursor => px(2.0),
+                        Some(end) if cursor_line < visual_lines.len() && end <= visual_ranges[cursor_line].len() => {
+                            visual_lines[cursor_line].x_for_index(end) - x
+                        }
+                        _ => px(FONT_SIZE / 2.0),
                     };
+                    cursors.push((x, y, width));
+                } else {
+                    for (line_idx, line_range) in visual_ranges.iter().enumerate() {
+                        let sel_start = selected_range.start;
+                        let sel_end = selected_range.end;
+
+                        if sel_end <= line_range.start || sel_start > line_range.end {
+                            continue;
+                        }
 
-                    if line_idx < visual_lines.len() {
-                        let x1 = visual_lines[line_idx].x_for_index(start_in_line);
-                        let x2 = visual_lines[line_idx].x_for_index(end_in_line);
-                        let y = line_height * line_idx as f32 - scroll_y;
+                        let start_in_line = sel_start.saturating_sub(line_range.start);
 
-                        let sel_bounds = Bounds::new(
-                            point(content_origin.x + x1, content_origin.y + y),
-                            size(x2 - x1, line_height),
-                        );
-                        selections.push(sel_bounds);
+                        let end_in_line = if sel_end > line_range.end {
+                            line_range.end - line_range.start
+                        } else {
+                            sel_end - line_range.start
+                        };
+
+                        if line_idx < visual_lines.len() {
+                            let x1 = visual_lines[line_idx].x_for_index(start_in_line);
+                            let x2 = visual_lines[line_idx].x_for_index(end_in_line);
+                            let y = line_height * line_idx as f32 - scroll_y;
+
+                            let sel_bounds = Bounds::new(
+                                point(content_origin.x + x1, content_origin.y + y),
+                                size(x2 - x1, line_height),
+                            );
+                            selections.push(sel_bounds);
+                        }
                     }
                 }
             }
-        } else {
-            cursor_pos = None;
         }
 
         EditorPrepaintState {
             lines: visual_lines,
             line_ranges: visual_ranges,
             scroll_y,
-            cursor_pos,
+            cursors,
             selections,
         }
     }
@@ -941,12 +2912,17 @@ impl Element for EditorElement {
             let _ = line.paint(origin, line_height, window, cx);
         }
 
-        if let Some((cursor_x, cursor_y)) = prepaint.cursor_pos {
+        for &(cursor_x, cursor_y, width) in &prepaint.cursors {
             let cursor_bounds = Bounds::new(
                 point(content_origin.x + cursor_x, content_origin.y + cursor_y),
-                size(px(2.0), line_height),
+                size(width, line_height),
             );
-            window.paint_quad(fill(cursor_bounds, self.theme.foreground));
+            let color = if self.block_cursor {
+                self.theme.foreground.opacity(0.4)
+            } else {
+                self.theme.foreground
+            };
+            window.paint_quad(fill(cursor_bounds, color));
         }
 
         let focus_handle = self.entity.read(cx).focus_handle.clone();
@@ -0,0 +1,782 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::ops::Range;
+use std::str::Chars;
+
+use crate::readability::sentences;
+
+/// Counts beyond this are taken as this, so a slip of the finger can't
+/// set off a billion deletions.
+const MAX_COUNT: usize = 100_000;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mode {
+    Normal,
+    Insert,
+    Visual,
+}
+
+impl Mode {
+    /// How the mode is shown in the header.
+    pub fn label(self) -> &'static str {
+        match self {
+            Mode::Normal => "NORMAL",
+            Mode::Insert => "INSERT",
+            Mode::Visual => "VISUAL",
+        }
+    }
+
+    /// Its value for `vim_mode` in the editor's key context.
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Normal => "normal",
+            Mode::Insert => "insert",
+            Mode::Visual => "visual",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum VimMotion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordStart,
+    WordBack,
+    WordEnd,
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    FirstLine,
+    LastLine,
+}
+
+impl VimMotion {
+    /// Whether an operator given this motion acts on whole lines.
+    fn linewise(self) -> bool {
+        matches!(self, VimMotion::Up | VimMotion::Down | VimMotion::FirstLine | VimMotion::LastLine)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextObject {
+    Word,
+    Sentence,
+    Paragraph,
+}
+
+/// What an operator acts on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Target {
+    Motion(VimMotion),
+    Object { object: TextObject, around: bool },
+    /// Whole lines, from doubling the operator as in `dd`.
+    Line,
+    /// The visual selection.
+    Selection,
+}
+
+/// Where `i`, `a` and the like start inserting.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InsertAt {
+    Cursor,
+    After,
+    LineStart,
+    LineEnd,
+    LineBelow,
+    LineAbove,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum VimAction {
+    Move(VimMotion),
+    Operate(Operator, Target),
+    /// Grows the visual selection over a text object.
+    Select { object: TextObject, around: bool },
+    Insert(InsertAt),
+    Put { before: bool },
+    Undo,
+    Repeat,
+    ToggleVisual,
+}
+
+/// A complete command, such as `"a3dw`: register `a`, count 3, delete a word.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct VimCommand {
+    pub count: Option<usize>,
+    pub register: Option<char>,
+    pub action: VimAction,
+}
+
+impl VimCommand {
+    pub fn times(&self) -> usize {
+        self.count.unwrap_or(1).min(MAX_COUNT)
+    }
+
+    /// Whether `.` can repeat it.
+    pub fn is_change(&self) -> bool {
+        matches!(
+            self.action,
+            VimAction::Operate(Operator::Delete | Operator::Change, _) | VimAction::Insert(_) | VimAction::Put { .. }
+        )
+    }
+}
+
+pub enum Parse {
+    /// The keys so far start a command but don't finish one.
+    Pending,
+    Invalid,
+    Done(VimCommand),
+}
+
+/// The state of Vim-style editing in one editor.
+pub struct Vim {
+    pub mode: Mode,
+    /// Keys typed towards a command that isn't complete yet.
+    pub pending: String,
+    /// Where the visual selection started.
+    pub anchor: usize,
+    /// The last change made in normal mode, and what was typed after it
+    /// in insert mode, for `.` to do again.
+    pub last_change: Option<(VimCommand, String)>,
+    /// Where insert mode started, and whether a change to be repeated
+    /// started it.
+    pub insert_start: usize,
+    pub inserting_change: bool,
+    /// Registers `a` to `z`. The unnamed register is the clipboard.
+    pub registers: HashMap<char, String>,
+}
+
+impl Default for Vim {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Normal,
+            pending: String::new(),
+            anchor: 0,
+            last_change: None,
+            insert_start: 0,
+            inserting_change: false,
+            registers: HashMap::new(),
+        }
+    }
+}
+
+impl Vim {
+    /// The last change and what was typed after it, for `.` to do again.
+    /// A count given to `.` replaces the change's own.
+    pub fn repeat(&self, count: Option<usize>) -> Option<(VimCommand, String)> {
+        let (mut change, typed) = self.last_change.clone()?;
+        if count.is_some() {
+            change.count = count;
+        }
+        Some((change, typed))
+    }
+}
+
+/// Reads the keys typed in normal or visual mode as a command.
+pub fn parse_command(keys: &str, mode: Mode) -> Parse {
+    match read_command(&mut keys.chars().peekable(), mode) {
+        Ok(command) => Parse::Done(command),
+        Err(parse) => parse,
+    }
+}
+
+fn read_command(keys: &mut Peekable<Chars>, mode: Mode) -> Result<VimCommand, Parse> {
+    let register = match keys.next_if_eq(&'"') {
+        Some(_) => Some(next(keys)?),
+        None => None,
+    };
+    let mut count = read_count(keys);
+    let key = next(keys)?;
+    let visual = mode == Mode::Visual;
+    let action = match key {
+        'd' | 'x' if visual => VimAction::Operate(Operator::Delete, Target::Selection),
+        'c' | 's' if visual => VimAction::Operate(Operator::Change, Target::Selection),
+        'y' if visual => VimAction::Operate(Operator::Yank, Target::Selection),
+        'i' | 'a' if visual => VimAction::Select {
+            object: object(keys)?,
+            around: key == 'a',
+        },
+        'd' | 'c' | 'y' => {
+            let operator = match key {
+                'd' => Operator::Delete,
+                'c' => Operator::Change,
+                _ => Operator::Yank,
+            };
+            // `2d3w` deletes six words.
+            count = match (count, read_count(keys)) {
+                (None, None) => None,
+                (outer, inner) => Some(outer.unwrap_or(1).saturating_mul(inner.unwrap_or(1))),
+            };
+            let target = match next(keys)? {
+                doubled if doubled == key => Target::Line,
+                around @ ('i' | 'a') => Target::Object {
+                    object: object(keys)?,
+                    around: around == 'a',
+                },
+                key => Target::Motion(motion(key, keys)?),
+            };
+            VimAction::Operate(operator, target)
+        }
+        'x' => VimAction::Operate(Operator::Delete, Target::Motion(VimMotion::Right)),
+        'X' => VimAction::Operate(Operator::Delete, Target::Motion(VimMotion::Left)),
+        's' => VimAction::Operate(Operator::Change, Target::Motion(VimMotion::Right)),
+        'D' => VimAction::Operate(Operator::Delete, Target::Motion(VimMotion::LineEnd)),
+        'C' => VimAction::Operate(Operator::Change, Target::Motion(VimMotion::LineEnd)),
+        'Y' => VimAction::Operate(Operator::Yank, Target::Line),
+        'i' => VimAction::Insert(InsertAt::Cursor),
+        'a' => VimAction::Insert(InsertAt::After),
+        'I' => VimAction::Insert(InsertAt::LineStart),
+        'A' => VimAction::Insert(InsertAt::LineEnd),
+        'o' => VimAction::Insert(InsertAt::LineBelow),
+        'O' => VimAction::Insert(InsertAt::LineAbove),
+        'p' => VimAction::Put { before: false },
+        'P' => VimAction::Put { before: true },
+        'u' => VimAction::Undo,
+        '.' => VimAction::Repeat,
+        'v' => VimAction::ToggleVisual,
+        key => VimAction::Move(motion(key, keys)?),
+    };
+    Ok(VimCommand {
+        count,
+        register,
+        action,
+    })
+}
+
+fn next(keys: &mut Peekable<Chars>) -> Result<char, Parse> {
+    keys.next().ok_or(Parse::Pending)
+}
+
+/// A count typed before a command. A leading `0` is the motion, not a count.
+fn read_count(keys: &mut Peekable<Chars>) -> Option<usize> {
+    let mut count: Option<usize> = None;
+    while let Some(digit) = keys.peek().and_then(|c| c.to_digit(10)) {
+        if digit == 0 && count.is_none() {
+            break;
+        }
+        keys.next();
+        count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize));
+    }
+    count
+}
+
+fn motion(key: char, keys: &mut Peekable<Chars>) -> Result<VimMotion, Parse> {
+    Ok(match key {
+        'h' => VimMotion::Left,
+        'l' | ' ' => VimMotion::Right,
+        'k' => VimMotion::Up,
+        'j' => VimMotion::Down,
+        'w' => VimMotion::WordStart,
+        'b' => VimMotion::WordBack,
+        'e' => VimMotion::WordEnd,
+        '0' => VimMotion::LineStart,
+        '^' => VimMotion::FirstNonBlank,
+        '$' => VimMotion::LineEnd,
+        'G' => VimMotion::LastLine,
+        'g' => match next(keys)? {
+            'g' => VimMotion::FirstLine,
+            _ => return Err(Parse::Invalid),
+        },
+        _ => return Err(Parse::Invalid),
+    })
+}
+
+fn object(keys: &mut Peekable<Chars>) -> Result<TextObject, Parse> {
+    Ok(match next(keys)? {
+        'w' => TextObject::Word,
+        's' => TextObject::Sentence,
+        'p' => TextObject::Paragraph,
+        _ => return Err(Parse::Invalid),
+    })
+}
+
+/// The line `offset` is on, without its line break.
+pub fn line_at(content: &str, offset: usize) -> Range<usize> {
+    let start = content[..offset].rfind('\n').map_or(0, |ix| ix + 1);
+    let end = content[offset..].find('\n').map_or(content.len(), |ix| offset + ix);
+    start..end
+}
+
+/// The lines from the one `a` is on to the one `b` is on, without the
+/// last one's line break.
+pub fn line_span(content: &str, a: usize, b: usize) -> Range<usize> {
+    line_at(content, a.min(b)).start..line_at(content, a.max(b)).end
+}
+
+pub fn first_non_blank(content: &str, offset: usize) -> usize {
+    let line = line_at(content, offset);
+    let text = &content[line.clone()];
+    line.start + text.len() - text.trim_start().len()
+}
+
+/// The offset of the character after the one at `offset`.
+pub fn next_char(content: &str, offset: usize) -> usize {
+    content[offset..].chars().next().map_or(offset, |c| offset + c.len_utf8())
+}
+
+pub fn previous_char(content: &str, offset: usize) -> usize {
+    content[..offset].chars().next_back().map_or(offset, |c| offset - c.len_utf8())
+}
+
+/// Vim's kinds of character: white space, word characters and the rest.
+/// A word is a run of one kind.
+fn class(c: char) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if c.is_alphanumeric() || c == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+/// Where `motion` takes the cursor from `cursor`, `count` times over. For
+/// `gg` and `G` the count is a line number instead.
+pub fn motion_target(content: &str, cursor: usize, motion: VimMotion, count: Option<usize>) -> usize {
+    let times = count.unwrap_or(1).min(MAX_COUNT);
+    let mut offset = cursor;
+    match motion {
+        VimMotion::FirstLine | VimMotion::LastLine => {
+            let last = content.split('\n').count();
+            let number = match motion {
+                VimMotion::FirstLine => count.unwrap_or(1),
+                _ => count.unwrap_or(last),
+            };
+            let start = content
+                .split('\n')
+                .take(number.clamp(1, last) - 1)
+                .map(|line| line.len() + 1)
+                .sum();
+            return first_non_blank(content, start);
+        }
+        VimMotion::LineStart => return line_at(content, cursor).start,
+        VimMotion::FirstNonBlank => return first_non_blank(content, cursor),
+        VimMotion::LineEnd => return line_at(content, cursor).end,
+        VimMotion::Up | VimMotion::Down => {
+            let mut line = line_at(content, cursor);
+            for _ in 0..times {
+                line = match motion {
+                    VimMotion::Up if line.start > 0 => line_at(content, line.start - 1),
+                    VimMotion::Down if line.end < content.len() => line_at(content, line.end + 1),
+                    _ => break,
+                };
+            }
+            return same_column(content, cursor, line);
+        }
+        _ => {}
+    }
+    for _ in 0..times {
+        let line = line_at(content, offset);
+        offset = match motion {
+            VimMotion::Left if offset > line.start => previous_char(content, offset),
+            VimMotion::Right if offset < line.end => next_char(content, offset),
+            VimMotion::WordStart => next_word_start(content, offset),
+            VimMotion::WordBack => previous_word_start(content, offset),
+            VimMotion::WordEnd => word_end(content, offset),
+            _ => break,
+        };
+    }
+    offset
+}
+
+/// The offset on `line` as many characters in as `offset` is on its own.
+fn same_column(content: &str, offset: usize, line: Range<usize>) -> usize {
+    let column = content[line_at(content, offset).start..offset].chars().count();
+    content[line.clone()]
+        .char_indices()
+        .nth(column)
+        .map_or(line.end, |(ix, _)| line.start + ix)
+}
+
+/// The start of the next word. An empty line counts as a word.
+fn next_word_start(content: &str, offset: usize) -> usize {
+    let mut chars = content[offset..].char_indices().map(|(ix, c)| (offset + ix, c)).peekable();
+    let Some(&(_, first)) = chars.peek() else {
+        return content.len();
+    };
+    let kind = class(first);
+    if kind != 0 {
+        while chars.next_if(|&(_, c)| class(c) == kind).is_some() {}
+    }
+    let mut after_newline = false;
+    for (ix, c) in chars {
+        if !c.is_whitespace() || (c == '\n' && after_newline) {
+            return ix;
+        }
+        after_newline = c == '\n';
+    }
+    content.len()
+}
+
+/// The start of this word, or the one before if already at its start.
+fn previous_word_start(content: &str, offset: usize) -> usize {
+    let mut chars = content[..offset].char_indices().rev().peekable();
+    let mut after_newline = false;
+    while let Some(&(ix, c)) = chars.peek() {
+        if !c.is_whitespace() {
+            break;
+        }
+        if c == '\n' && after_newline {
+            return ix + 1;
+        }
+        after_newline = c == '\n';
+        chars.next();
+    }
+    let Some(&(mut start, first)) = chars.peek() else {
+        return 0;
+    };
+    let kind = class(first);
+    while let Some((ix, _)) = chars.next_if(|&(_, c)| class(c) == kind) {
+        start = ix;
+    }
+    start
+}
+
+/// The last character of this word, or of the next if already there.
+fn word_end(content: &str, offset: usize) -> usize {
+    let mut chars = content[offset..]
+        .char_indices()
+        .map(|(ix, c)| (offset + ix, c))
+        .skip(1)
+        .peekable();
+    while chars.next_if(|&(_, c)| c.is_whitespace()).is_some() {}
+    let Some(&(mut end, first)) = chars.peek() else {
+        return offset;
+    };
+    let kind = class(first);
+    while let Some((ix, _)) = chars.next_if(|&(_, c)| class(c) == kind) {
+        end = ix;
+    }
+    end
+}
+
+/// What an operator moving the cursor from `cursor` to `target` acts on,
+/// and whether that's whole lines. Whole lines leave out the last line
+/// break.
+pub fn motion_range(content: &str, cursor: usize, target: usize, motion: VimMotion) -> (Range<usize>, bool) {
+    let (start, end) = (cursor.min(target), cursor.max(target));
+    if motion.linewise() {
+        (line_span(content, start, end), true)
+    } else if motion == VimMotion::WordEnd {
+        (start..next_char(content, end), false)
+    } else {
+        (start..end, false)
+    }
+}
+
+/// What `operator` acts on from `cursor` for any target but the visual
+/// selection, and whether that's whole lines.
+pub fn operator_range(
+    content: &str,
+    cursor: usize,
+    operator: Operator,
+    target: Target,
+    count: Option<usize>,
+) -> (Range<usize>, bool) {
+    match target {
+        Target::Selection => (cursor..cursor, false),
+        Target::Line => {
+            let last = motion_target(content, cursor, VimMotion::Down, Some(count.unwrap_or(1) - 1));
+            (line_span(content, cursor, last), true)
+        }
+        Target::Object { object, around } => text_object(content, cursor, object, around),
+        Target::Motion(motion) => {
+            // `cw` on a word changes to its end and keeps the space after it.
+            let on_word = content[cursor..].chars().next().is_some_and(|c| !c.is_whitespace());
+            let motion = if operator == Operator::Change && motion == VimMotion::WordStart && on_word {
+                VimMotion::WordEnd
+            } else {
+                motion
+            };
+            let mut target = motion_target(content, cursor, motion, count);
+            // `dw` on the last word of a line leaves the line break.
+            let line = line_at(content, cursor);
+            if motion == VimMotion::WordStart && cursor < line.end {
+                target = target.min(line.end);
+            }
+            motion_range(content, cursor, target, motion)
+        }
+    }
+}
+
+/// Whole lines to delete, with a line break: the one after them, or the
+/// one before for the last line.
+pub fn with_line_break(content: &str, lines: Range<usize>) -> Range<usize> {
+    if lines.end < content.len() {
+        lines.start..lines.end + 1
+    } else {
+        lines.start.saturating_sub(1)..lines.end
+    }
+}
+
+/// The range of `object` at `cursor`, and whether it's whole lines. With
+/// `around` a word or sentence takes the white space after it, and a
+/// paragraph the blank lines after it.
+pub fn text_object(content: &str, cursor: usize, object: TextObject, around: bool) -> (Range<usize>, bool) {
+    match object {
+        TextObject::Word => (word_object(content, cursor, around), false),
+        TextObject::Sentence => (sentence_object(content, cursor, around), false),
+        TextObject::Paragraph => (paragraph_object(content, cursor, around), true),
+    }
+}
+
+fn word_object(content: &str, cursor: usize, around: bool) -> Range<usize> {
+    let line = line_at(content, cursor);
+    let text = &content[line.clone()];
+    let at = cursor - line.start;
+    let Some(c) = text[at..].chars().next().or_else(|| text[..at].chars().next_back()) else {
+        return cursor..cursor;
+    };
+    let kind = class(c);
+    let run = |from: usize, kind: u8| {
+        text[from..]
+            .char_indices()
+            .find(|&(_, c)| class(c) != kind)
+            .map_or(text.len(), |(ix, _)| from + ix)
+    };
+    let mut start = text[..at]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| class(c) == kind)
+        .last()
+        .map_or(at, |(ix, _)| ix);
+    let mut end = run(at, kind);
+    if around {
+        let next = text[end..].chars().next().map(class);
+        if kind == 0 {
+            end = next.map_or(end, |next| run(end, next));
+        } else if next == Some(0) {
+            end = run(end, 0);
+        } else {
+            start = text[..start].trim_end().len();
+        }
+    }
+    line.start + start..line.start + end
+}
+
+/// The paragraph at `offset`: the run of lines around it that are all
+/// blank or all not.
+fn paragraph_lines(content: &str, offset: usize) -> Range<usize> {
+    let blank = |line: &Range<usize>| content[line.clone()].trim().is_empty();
+    let line = line_at(content, offset);
+    let kind = blank(&line);
+    let mut start = line.clone();
+    while start.start > 0 {
+        let previous = line_at(content, start.start - 1);
+        if blank(&previous) != kind {
+            break;
+        }
+        start = previous;
+    }
+    let mut end = line;
+    while end.end < content.len() {
+        let next = line_at(content, end.end + 1);
+        if blank(&next) != kind {
+            break;
+        }
+        end = next;
+    }
+    start.start..end.end
+}
+
+fn paragraph_object(content: &str, cursor: usize, around: bool) -> Range<usize> {
+    let paragraph = paragraph_lines(content, cursor);
+    if !around || paragraph.end >= content.len() {
+        return paragraph;
+    }
+    paragraph.start..paragraph_lines(content, paragraph.end + 1).end
+}
+
+fn sentence_object(content: &str, cursor: usize, around: bool) -> Range<usize> {
+    let paragraph = paragraph_lines(content, cursor);
+    let text = &content[paragraph.clone()];
+    let trimmed = |range: &Range<usize>| range.start + text[range.clone()].len() - text[range.clone()].trim_start().len();
+    let spans = sentences(text);
+    let at = cursor - paragraph.start;
+    let Some(ix) = spans.iter().position(|span| at < span.end).or(spans.len().checked_sub(1)) else {
+        return cursor..cursor;
+    };
+    let start = trimmed(&spans[ix]);
+    let end = if around {
+        spans.get(ix + 1).map_or(text.len(), trimmed)
+    } else {
+        start + text[start..spans[ix].end].trim_end().len()
+    };
+    paragraph.start + start..paragraph.start + end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(keys: &str) -> VimCommand {
+        match parse_command(keys, Mode::Normal) {
+            Parse::Done(command) => command,
+            Parse::Pending => panic!("{keys:?} is pending"),
+            Parse::Invalid => panic!("{keys:?} is invalid"),
+        }
+    }
+
+    fn target(content: &str, cursor: usize, keys: &str) -> usize {
+        let command = parse(keys);
+        let VimAction::Move(motion) = command.action else { panic!("{keys:?} isn't a motion") };
+        motion_target(content, cursor, motion, command.count)
+    }
+
+    /// What `keys` would act on with the cursor at `cursor`.
+    fn operated<'a>(content: &'a str, cursor: usize, keys: &str) -> (&'a str, bool) {
+        let command = parse(keys);
+        let VimAction::Operate(operator, target) = command.action else { panic!("{keys:?} isn't an operator") };
+        let (range, linewise) = operator_range(content, cursor, operator, target, command.count);
+        (&content[range], linewise)
+    }
+
+    #[test]
+    fn counts_multiply_around_an_operator() {
+        let command = parse("2d3w");
+        assert_eq!(command.count, Some(6));
+        assert_eq!(command.action, VimAction::Operate(Operator::Delete, Target::Motion(VimMotion::WordStart)));
+        assert_eq!(parse("d3w").count, Some(3));
+        assert_eq!(parse("3dw").count, Some(3));
+        assert_eq!(parse("dw").count, None);
+        let command = parse("\"a12yy");
+        assert_eq!(command.register, Some('a'));
+        assert_eq!(command.count, Some(12));
+        assert_eq!(command.action, VimAction::Operate(Operator::Yank, Target::Line));
+        assert_eq!(parse("999999999j").times(), MAX_COUNT);
+    }
+
+    #[test]
+    fn zero_is_a_motion_unless_it_continues_a_count() {
+        assert_eq!(parse("0").action, VimAction::Move(VimMotion::LineStart));
+        assert_eq!(parse("0").count, None);
+        assert_eq!(parse("10j").count, Some(10));
+        assert_eq!(parse("d0").action, VimAction::Operate(Operator::Delete, Target::Motion(VimMotion::LineStart)));
+        assert_eq!(parse("2d0").count, Some(2));
+    }
+
+    #[test]
+    fn incomplete_and_unknown_keys() {
+        for keys in ["", "2", "d", "\"", "\"a", "g", "ci", "2d3"] {
+            assert!(matches!(parse_command(keys, Mode::Normal), Parse::Pending), "{keys:?}");
+        }
+        for keys in ["q", "dq", "gx", "diq"] {
+            assert!(matches!(parse_command(keys, Mode::Normal), Parse::Invalid), "{keys:?}");
+        }
+        let visual = parse_command("iw", Mode::Visual);
+        assert!(matches!(
+            visual,
+            Parse::Done(VimCommand { action: VimAction::Select { object: TextObject::Word, around: false }, .. })
+        ));
+    }
+
+    #[test]
+    fn only_changes_repeat() {
+        for keys in ["dw", "cc", "x", "i", "o", "p", "3dd"] {
+            assert!(parse(keys).is_change(), "{keys:?}");
+        }
+        for keys in ["yw", "j", "u", ".", "v"] {
+            assert!(!parse(keys).is_change(), "{keys:?}");
+        }
+    }
+
+    #[test]
+    fn dot_repeats_the_last_change_with_a_new_count() {
+        let mut vim = Vim::default();
+        assert!(vim.repeat(None).is_none());
+        vim.last_change = Some((parse("3cw"), "new".to_string()));
+        let (change, typed) = vim.repeat(None).unwrap();
+        assert_eq!((change.count, typed.as_str()), (Some(3), "new"));
+        assert_eq!(vim.repeat(Some(2)).unwrap().0.count, Some(2));
+        assert_eq!(parse("4.").action, VimAction::Repeat);
+    }
+
+    #[test]
+    fn word_motions() {
+        let text = "one two, three\n\nfour";
+        assert_eq!(target(text, 0, "w"), 4);
+        assert_eq!(target(text, 0, "2w"), 7);
+        assert_eq!(target(text, 9, "w"), 15);
+        assert_eq!(target(text, 15, "w"), 16);
+        assert_eq!(target(text, 0, "e"), 2);
+        assert_eq!(target(text, 2, "e"), 6);
+        assert_eq!(target(text, 9, "b"), 7);
+        assert_eq!(target(text, 10, "b"), 9);
+        assert_eq!(target(text, 16, "b"), 15);
+        assert_eq!(target(text, 16, "w"), text.len());
+    }
+
+    #[test]
+    fn line_motions() {
+        let text = "  indented\nshort\nlonger line";
+        assert_eq!(target(text, 5, "0"), 0);
+        assert_eq!(target(text, 5, "^"), 2);
+        assert_eq!(target(text, 5, "$"), 10);
+        assert_eq!(target(text, 7, "j"), 16);
+        assert_eq!(target(text, 7, "2j"), 24);
+        assert_eq!(target(text, 24, "5k"), 7);
+        assert_eq!(target(text, 24, "gg"), 2);
+        assert_eq!(target(text, 0, "G"), 17);
+        assert_eq!(target(text, 0, "2G"), 11);
+        assert_eq!(target(text, 3, "h"), 2);
+        assert_eq!(target(text, 0, "h"), 0);
+        assert_eq!(target(text, 9, "5l"), 10);
+    }
+
+    #[test]
+    fn change_word_stops_at_the_end_of_the_word() {
+        let text = "one two\nthree";
+        assert_eq!(operated(text, 0, "cw"), ("one", false));
+        assert_eq!(operated(text, 0, "dw"), ("one ", false));
+        assert_eq!(operated(text, 0, "c2w"), ("one two", false));
+        assert_eq!(operated(text, 3, "cw"), (" ", false));
+        assert_eq!(operated(text, 4, "dw"), ("two", false));
+        assert_eq!(operated(text, 4, "de"), ("two", false));
+        assert_eq!(operated(text, 5, "d$"), ("wo", false));
+        assert_eq!(operated(text, 0, "dj"), ("one two\nthree", true));
+    }
+
+    #[test]
+    fn deleting_lines_takes_a_line_break() {
+        let text = "a\nb\nc";
+        let lines = |cursor: usize, keys: &str| {
+            let command = parse(keys);
+            let VimAction::Operate(operator, target) = command.action else { unreachable!() };
+            let (range, linewise) = operator_range(text, cursor, operator, target, command.count);
+            assert!(linewise);
+            let mut rest = text.to_string();
+            rest.replace_range(with_line_break(text, range), "");
+            rest
+        };
+        assert_eq!(lines(0, "dd"), "b\nc");
+        assert_eq!(lines(2, "dd"), "a\nc");
+        assert_eq!(lines(4, "dd"), "a\nb");
+        assert_eq!(lines(2, "5dd"), "a");
+        assert_eq!(with_line_break("only", 0..4), 0..4);
+    }
+
+    #[test]
+    fn word_objects() {
+        let text = "one two\nthree  four";
+        let object = |cursor: usize, around: bool| &text[text_object(text, cursor, TextObject::Word, around).0];
+        assert_eq!(object(5, false), "two");
+        assert_eq!(object(0, true), "one ");
+        // At the end of a line there's no space after, so `aw` takes the one before.
+        assert_eq!(object(5, true), " two");
+        assert_eq!(object(7, false), "two");
+        assert_eq!(object(3, false), " ");
+        assert_eq!(object(3, true), " two");
+        assert_eq!(object(13, false), "  ");
+        assert_eq!(object(8, true), "three  ");
+        assert_eq!(text_object("", 0, TextObject::Word, true).0, 0..0);
+    }
+}
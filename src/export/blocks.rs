@@ -0,0 +1,297 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+use crate::export::markdown_options;
+
+/// A flattened view of a markdown document for the word-processor writers:
+/// a sequence of styled paragraphs plus footnote bodies keyed by label.
+pub struct Manuscript {
+    pub paragraphs: Vec<Paragraph>,
+    pub footnotes: Vec<(String, Vec<Paragraph>)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ParagraphKind {
+    Body,
+    Heading(usize),
+    Quote,
+    Code,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct ListLevel {
+    /// Distinguishes separate lists, so numbering restarts for each one.
+    pub id: usize,
+    pub ordered: bool,
+    pub start: u64,
+}
+
+pub struct Paragraph {
+    pub kind: ParagraphKind,
+    /// The lists this paragraph sits in, outermost first.
+    pub list: Vec<ListLevel>,
+    /// Whether this paragraph begins a new list item, as opposed to
+    /// continuing the previous one.
+    pub new_item: bool,
+    pub inlines: Vec<Inline>,
+}
+
+pub enum Inline {
+    Text(Run),
+    Footnote(String),
+    Break,
+}
+
+#[derive(Clone, Default)]
+pub struct Run {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub strike: bool,
+    pub code: bool,
+    pub link: Option<String>,
+}
+
+#[derive(Default)]
+struct Builder {
+    paragraphs: Vec<Paragraph>,
+    footnotes: Vec<(String, Vec<Paragraph>)>,
+    current: Option<Paragraph>,
+    heading: Option<usize>,
+    quote_depth: usize,
+    in_code: bool,
+    /// Whether the last text in a code block ended its line. Indented code
+    /// comes one line per event, so the break goes before the next one.
+    code_line_ended: bool,
+    in_metadata: bool,
+    in_footnote: bool,
+    lists: Vec<ListLevel>,
+    next_list_id: usize,
+    item_pending: bool,
+    bold: usize,
+    italic: usize,
+    strike: usize,
+    link: Option<String>,
+}
+
+impl Builder {
+    fn flush(&mut self) {
+        let Some(paragraph) = self.current.take() else { return };
+        if self.in_footnote {
+            if let Some((_, body)) = self.footnotes.last_mut() {
+                body.push(paragraph);
+            }
+        } else {
+            self.paragraphs.push(paragraph);
+        }
+    }
+
+    fn begin(&mut self) {
+        self.flush();
+        let kind = if let Some(level) = self.heading {
+            ParagraphKind::Heading(level)
+        } else if self.in_code {
+            ParagraphKind::Code
+        } else if self.quote_depth > 0 {
+            ParagraphKind::Quote
+        } else {
+            ParagraphKind::Body
+        };
+        self.current = Some(Paragraph {
+            kind,
+            list: self.lists.clone(),
+            new_item: std::mem::take(&mut self.item_pending),
+            inlines: Vec::new(),
+        });
+    }
+
+    fn push(&mut self, inline: Inline) {
+        if self.current.is_none() {
+            self.begin();
+        }
+        if let Some(paragraph) = self.current.as_mut() {
+            paragraph.inlines.push(inline);
+        }
+    }
+
+    fn text(&mut self, text: &str, code: bool) {
+        let run = Run {
+            text: text.to_string(),
+            bold: self.bold > 0,
+            italic: self.italic > 0,
+            strike: self.strike > 0,
+            code,
+            link: self.link.clone(),
+        };
+        self.push(Inline::Text(run));
+    }
+
+    fn event(&mut self, event: Event) {
+        if self.in_metadata {
+            if let Event::End(TagEnd::MetadataBlock(_)) = event {
+                self.in_metadata = false;
+            }
+            return;
+        }
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) if self.in_code => {
+                if self.code_line_ended {
+                    self.push(Inline::Break);
+                }
+                for (i, line) in text.strip_suffix('\n').unwrap_or(&text).split('\n').enumerate() {
+                    if i > 0 {
+                        self.push(Inline::Break);
+                    }
+                    self.text(line, true);
+                }
+                self.code_line_ended = text.ends_with('\n');
+            }
+            Event::Text(text) => self.text(&text, false),
+            Event::Code(text) => self.text(&text, true),
+            Event::SoftBreak => self.text(" ", false),
+            Event::HardBreak => self.push(Inline::Break),
+            Event::FootnoteReference(label) => self.push(Inline::Footnote(label.to_string())),
+            Event::TaskListMarker(checked) => self.text(if checked { "☒ " } else { "☐ " }, false),
+            Event::Rule => {
+                self.begin();
+                self.text("* * *", false);
+                self.flush();
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.begin(),
+            Tag::Heading { level, .. } => {
+                self.heading = Some(level as usize);
+                self.begin();
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.in_code = true;
+                self.code_line_ended = false;
+                self.begin();
+            }
+            Tag::List(start) => {
+                self.flush();
+                self.lists.push(ListLevel {
+                    id: self.next_list_id,
+                    ordered: start.is_some(),
+                    start: start.unwrap_or(1),
+                });
+                self.next_list_id += 1;
+            }
+            Tag::Item => {
+                self.flush();
+                self.item_pending = true;
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.flush();
+                self.footnotes.push((label.to_string(), Vec::new()));
+                self.in_footnote = true;
+            }
+            Tag::TableHead | Tag::TableRow => self.begin(),
+            Tag::TableCell if self.current.as_ref().is_some_and(|p| !p.inlines.is_empty()) => {
+                self.text("\t", false);
+            }
+            Tag::Emphasis => self.italic += 1,
+            Tag::Strong => self.bold += 1,
+            Tag::Strikethrough => self.strike += 1,
+            Tag::Link { dest_url, .. } => self.link = Some(dest_url.to_string()),
+            Tag::MetadataBlock(_) => self.in_metadata = true,
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::TableHead | TagEnd::TableRow | TagEnd::Item => self.flush(),
+            TagEnd::Heading(_) => {
+                self.flush();
+                self.heading = None;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => {
+                self.flush();
+                self.in_code = false;
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+            }
+            TagEnd::FootnoteDefinition => {
+                self.flush();
+                self.in_footnote = false;
+            }
+            TagEnd::Emphasis => self.italic = self.italic.saturating_sub(1),
+            TagEnd::Strong => self.bold = self.bold.saturating_sub(1),
+            TagEnd::Strikethrough => self.strike = self.strike.saturating_sub(1),
+            TagEnd::Link => self.link = None,
+            _ => {}
+        }
+    }
+}
+
+pub fn manuscript(markdown: &str) -> Manuscript {
+    let mut builder = Builder::default();
+    for event in Parser::new_ext(markdown, markdown_options()) {
+        builder.event(event);
+    }
+    builder.flush();
+    Manuscript {
+        paragraphs: builder.paragraphs,
+        footnotes: builder.footnotes,
+    }
+}
+
+impl Manuscript {
+    pub fn footnote(&self, label: &str) -> Option<&[Paragraph]> {
+        self.footnotes
+            .iter()
+            .find(|(name, _)| name == label)
+            .map(|(_, body)| body.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The lines of the one code paragraph in `markdown`.
+    fn code_lines(markdown: &str) -> Vec<String> {
+        let manuscript = manuscript(markdown);
+        let code = manuscript
+            .paragraphs
+            .iter()
+            .find(|paragraph| paragraph.kind == ParagraphKind::Code)
+            .unwrap();
+        let mut lines = vec![String::new()];
+        for inline in &code.inlines {
+            match inline {
+                Inline::Text(run) => lines.last_mut().unwrap().push_str(&run.text),
+                Inline::Break => lines.push(String::new()),
+                Inline::Footnote(_) => {}
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn fenced_code_keeps_its_lines() {
+        assert_eq!(code_lines("```\nlet a = 1;\n\nlet b = 2;\n```\n"), ["let a = 1;", "", "let b = 2;"]);
+    }
+
+    #[test]
+    fn indented_code_keeps_its_lines() {
+        assert_eq!(code_lines("Text\n\n    one\n    two\n\n    three\n"), ["one", "two", "", "three"]);
+    }
+}
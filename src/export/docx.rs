@@ -0,0 +1,368 @@
+use std::io;
+use std::mem;
+use std::path::Path;
+
+use chrono::Utc;
+
+use crate::document::front_matter;
+use crate::export::blocks::{Inline, Manuscript, Paragraph, ParagraphKind, Run, manuscript};
+use crate::export::{escape_xml, write_zip};
+
+const NAMESPACES: &str = "xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\" \
+     xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\"";
+const RELATIONSHIP: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+/// Twips of indent per list level.
+const LIST_INDENT: usize = 720;
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+/// Built-in Word style names, so headings land in the navigation pane and
+/// restyling "Heading 1" in Word restyles every chapter title.
+const STYLES: &str = r#"<w:docDefaults>
+<w:rPrDefault><w:rPr><w:sz w:val="24"/><w:szCs w:val="24"/><w:lang w:val="en-US"/></w:rPr></w:rPrDefault>
+<w:pPrDefault><w:pPr><w:spacing w:after="160" w:line="276" w:lineRule="auto"/></w:pPr></w:pPrDefault>
+</w:docDefaults>
+<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/></w:style>
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="480" w:after="240"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="40"/><w:szCs w:val="40"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="360" w:after="160"/><w:outlineLvl w:val="1"/></w:pPr><w:rPr><w:b/><w:sz w:val="32"/><w:szCs w:val="32"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading3"><w:name w:val="heading 3"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="120"/><w:outlineLvl w:val="2"/></w:pPr><w:rPr><w:b/><w:sz w:val="28"/><w:szCs w:val="28"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading4"><w:name w:val="heading 4"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="120"/><w:outlineLvl w:val="3"/></w:pPr><w:rPr><w:b/><w:i/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading5"><w:name w:val="heading 5"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="200" w:after="80"/><w:outlineLvl w:val="4"/></w:pPr><w:rPr><w:b/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading6"><w:name w:val="heading 6"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="200" w:after="80"/><w:outlineLvl w:val="5"/></w:pPr><w:rPr><w:i/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:ind w:left="720" w:right="720"/></w:pPr><w:rPr><w:i/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="ListParagraph"><w:name w:val="List Paragraph"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:ind w:left="720"/><w:contextualSpacing/></w:pPr></w:style>
+<w:style w:type="paragraph" w:customStyle="1" w:styleId="SourceCode"><w:name w:val="Source Code"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="160" w:line="240" w:lineRule="auto"/></w:pPr><w:rPr><w:rFonts w:ascii="Courier New" w:hAnsi="Courier New" w:cs="Courier New"/><w:sz w:val="20"/><w:szCs w:val="20"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="FootnoteText"><w:name w:val="footnote text"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="0" w:line="240" w:lineRule="auto"/></w:pPr><w:rPr><w:sz w:val="20"/><w:szCs w:val="20"/></w:rPr></w:style>
+<w:style w:type="character" w:default="1" w:styleId="DefaultParagraphFont"><w:name w:val="Default Paragraph Font"/></w:style>
+<w:style w:type="character" w:styleId="FootnoteReference"><w:name w:val="footnote reference"/><w:rPr><w:vertAlign w:val="superscript"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr><w:color w:val="0563C1"/><w:u w:val="single"/></w:rPr></w:style>
+<w:style w:type="character" w:customStyle="1" w:styleId="VerbatimChar"><w:name w:val="Verbatim Char"/><w:rPr><w:rFonts w:ascii="Courier New" w:hAnsi="Courier New" w:cs="Courier New"/></w:rPr></w:style>
+"#;
+
+/// One XML part of the package, with the hyperlinks it refers to. Word keeps
+/// relationships per part, so footnotes need their own.
+#[derive(Default)]
+struct Part {
+    xml: String,
+    links: Vec<String>,
+}
+
+impl Part {
+    fn link_id(&mut self, url: &str) -> String {
+        let ix = match self.links.iter().position(|link| link == url) {
+            Some(ix) => ix,
+            None => {
+                self.links.push(url.to_string());
+                self.links.len() - 1
+            }
+        };
+        format!("rLink{}", ix + 1)
+    }
+
+    fn relationships(&self, fixed: &str) -> String {
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\n{}",
+            fixed
+        );
+        for (ix, url) in self.links.iter().enumerate() {
+            xml.push_str(&format!(
+                "<Relationship Id=\"rLink{}\" Type=\"{}/hyperlink\" Target=\"{}\" TargetMode=\"External\"/>\n",
+                ix + 1,
+                RELATIONSHIP,
+                escape_xml(url)
+            ));
+        }
+        xml.push_str("</Relationships>\n");
+        xml
+    }
+}
+
+struct Writer<'a> {
+    manuscript: &'a Manuscript,
+    notes: Part,
+    next_note: usize,
+    /// Ordered lists each get their own numbering instance so they count
+    /// from their own start: (list id, numId, level, start).
+    ordered: Vec<(usize, usize, usize, u64)>,
+}
+
+impl Writer<'_> {
+    fn num_id(&mut self, paragraph: &Paragraph) -> usize {
+        let depth = paragraph.list.len() - 1;
+        let Some(list) = paragraph.list.last().filter(|list| list.ordered) else {
+            return 1;
+        };
+        if let Some((_, num, _, _)) = self.ordered.iter().find(|(id, ..)| *id == list.id) {
+            return *num;
+        }
+        let num = self.ordered.len() + 2;
+        self.ordered.push((list.id, num, depth, list.start));
+        num
+    }
+
+    fn paragraph(&mut self, part: &mut Part, paragraph: &Paragraph, in_note: bool) {
+        let style = match paragraph.kind {
+            _ if in_note => "FootnoteText".to_string(),
+            ParagraphKind::Heading(level) => format!("Heading{}", level.clamp(1, 6)),
+            ParagraphKind::Quote => "Quote".to_string(),
+            ParagraphKind::Code => "SourceCode".to_string(),
+            ParagraphKind::Body if !paragraph.list.is_empty() => "ListParagraph".to_string(),
+            ParagraphKind::Body => "Normal".to_string(),
+        };
+        part.xml.push_str(&format!("<w:p><w:pPr><w:pStyle w:val=\"{}\"/>", style));
+        if !paragraph.list.is_empty() && !in_note {
+            let depth = paragraph.list.len() - 1;
+            if paragraph.new_item {
+                let num = self.num_id(paragraph);
+                part.xml.push_str(&format!(
+                    "<w:numPr><w:ilvl w:val=\"{}\"/><w:numId w:val=\"{}\"/></w:numPr>",
+                    depth, num
+                ));
+            } else {
+                part.xml.push_str(&format!("<w:ind w:left=\"{}\"/>", LIST_INDENT * (depth + 1)));
+            }
+        }
+        part.xml.push_str("</w:pPr>");
+        self.inlines(part, &paragraph.inlines, in_note);
+        part.xml.push_str("</w:p>\n");
+    }
+
+    fn inlines(&mut self, part: &mut Part, inlines: &[Inline], in_note: bool) {
+        for inline in inlines {
+            match inline {
+                Inline::Text(run) => match &run.link {
+                    Some(url) => {
+                        let id = part.link_id(url);
+                        part.xml.push_str(&format!("<w:hyperlink r:id=\"{}\">", id));
+                        part.xml.push_str(&run_xml(run));
+                        part.xml.push_str("</w:hyperlink>");
+                    }
+                    None => part.xml.push_str(&run_xml(run)),
+                },
+                Inline::Break => part.xml.push_str("<w:r><w:br/></w:r>"),
+                Inline::Footnote(label) => match self.manuscript.footnote(label) {
+                    Some(body) if !in_note => {
+                        let id = self.footnote(body);
+                        part.xml.push_str(&format!(
+                            "<w:r><w:rPr><w:rStyle w:val=\"FootnoteReference\"/></w:rPr>\
+                             <w:footnoteReference w:id=\"{}\"/></w:r>",
+                            id
+                        ));
+                    }
+                    _ => part.xml.push_str(&run_xml(&Run {
+                        text: format!("[{}]", label),
+                        ..Run::default()
+                    })),
+                },
+            }
+        }
+    }
+
+    /// Writes a footnote body and returns its id. Every reference gets its
+    /// own note, since Word can't point two references at one.
+    fn footnote(&mut self, body: &[Paragraph]) -> usize {
+        let id = self.next_note;
+        self.next_note += 1;
+        let mut notes = mem::take(&mut self.notes);
+        notes.xml.push_str(&format!("<w:footnote w:id=\"{}\">\n", id));
+        let mark = "<w:r><w:rPr><w:rStyle w:val=\"FootnoteReference\"/></w:rPr><w:footnoteRef/></w:r>\
+                    <w:r><w:t xml:space=\"preserve\"> </w:t></w:r>";
+        if body.is_empty() {
+            notes.xml.push_str(&format!(
+                "<w:p><w:pPr><w:pStyle w:val=\"FootnoteText\"/></w:pPr>{}</w:p>\n",
+                mark
+            ));
+        }
+        for (ix, paragraph) in body.iter().enumerate() {
+            let start = notes.xml.len();
+            self.paragraph(&mut notes, paragraph, true);
+            if ix == 0 {
+                // The reference mark goes right after the first paragraph's properties.
+                if let Some(at) = notes.xml[start..].find("</w:pPr>") {
+                    notes.xml.insert_str(start + at + "</w:pPr>".len(), mark);
+                }
+            }
+        }
+        notes.xml.push_str("</w:footnote>\n");
+        self.notes = notes;
+        id
+    }
+
+    fn numbering(&self) -> String {
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:numbering {}>\n",
+            NAMESPACES
+        );
+        for (abstract_id, ordered) in [(0, false), (1, true)] {
+            xml.push_str(&format!(
+                "<w:abstractNum w:abstractNumId=\"{}\"><w:multiLevelType w:val=\"hybridMultilevel\"/>\n",
+                abstract_id
+            ));
+            for level in 0..9 {
+                let (format, text) = if ordered {
+                    ("decimal", format!("%{}.", level + 1))
+                } else {
+                    ("bullet", BULLETS[level % BULLETS.len()].to_string())
+                };
+                xml.push_str(&format!(
+                    "<w:lvl w:ilvl=\"{level}\"><w:start w:val=\"1\"/><w:numFmt w:val=\"{format}\"/>\
+                     <w:lvlText w:val=\"{text}\"/><w:lvlJc w:val=\"left\"/>\
+                     <w:pPr><w:ind w:left=\"{left}\" w:hanging=\"360\"/></w:pPr></w:lvl>\n",
+                    level = level,
+                    format = format,
+                    text = text,
+                    left = LIST_INDENT * (level + 1)
+                ));
+            }
+            xml.push_str("</w:abstractNum>\n");
+        }
+        xml.push_str("<w:num w:numId=\"1\"><w:abstractNumId w:val=\"0\"/></w:num>\n");
+        for (_, num, level, start) in &self.ordered {
+            xml.push_str(&format!(
+                "<w:num w:numId=\"{}\"><w:abstractNumId w:val=\"1\"/>\
+                 <w:lvlOverride w:ilvl=\"{}\"><w:startOverride w:val=\"{}\"/></w:lvlOverride></w:num>\n",
+                num, level, start
+            ));
+        }
+        xml.push_str("</w:numbering>\n");
+        xml
+    }
+}
+
+fn run_xml(run: &Run) -> String {
+    let mut props = String::new();
+    if run.link.is_some() {
+        props.push_str("<w:rStyle w:val=\"Hyperlink\"/>");
+    } else if run.code {
+        props.push_str("<w:rStyle w:val=\"VerbatimChar\"/>");
+    }
+    if run.bold {
+        props.push_str("<w:b/>");
+    }
+    if run.italic {
+        props.push_str("<w:i/>");
+    }
+    if run.strike {
+        props.push_str("<w:strike/>");
+    }
+
+    let mut xml = String::from("<w:r>");
+    if !props.is_empty() {
+        xml.push_str(&format!("<w:rPr>{}</w:rPr>", props));
+    }
+    for (ix, piece) in run.text.split('\t').enumerate() {
+        if ix > 0 {
+            xml.push_str("<w:tab/>");
+        }
+        if !piece.is_empty() {
+            xml.push_str(&format!("<w:t xml:space=\"preserve\">{}</w:t>", escape_xml(piece)));
+        }
+    }
+    xml.push_str("</w:r>");
+    xml
+}
+
+/// Writes `markdown` as a Word document. Headings, quotes, code, lists and
+/// footnotes use Word's own styles and numbering, so the result can be
+/// restyled from Word's style gallery rather than by hand.
+pub fn write_docx(markdown: &str, title: &str, output: &Path) -> io::Result<()> {
+    let manuscript = manuscript(markdown);
+    let mut writer = Writer {
+        manuscript: &manuscript,
+        notes: Part::default(),
+        next_note: 1,
+        ordered: Vec::new(),
+    };
+
+    let mut document = Part::default();
+    for paragraph in &manuscript.paragraphs {
+        writer.paragraph(&mut document, paragraph, false);
+    }
+    if manuscript.paragraphs.is_empty() {
+        document.xml.push_str("<w:p/>\n");
+    }
+
+    let document_xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:document {}>\n<w:body>\n{}\
+         <w:sectPr><w:pgSz w:w=\"12240\" w:h=\"15840\"/>\
+         <w:pgMar w:top=\"1440\" w:right=\"1440\" w:bottom=\"1440\" w:left=\"1440\" w:header=\"720\" w:footer=\"720\" w:gutter=\"0\"/>\
+         </w:sectPr>\n</w:body>\n</w:document>\n",
+        NAMESPACES, document.xml
+    );
+    let footnotes_xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:footnotes {}>\n\
+         <w:footnote w:type=\"separator\" w:id=\"-1\"><w:p><w:pPr><w:spacing w:after=\"0\"/></w:pPr><w:r><w:separator/></w:r></w:p></w:footnote>\n\
+         <w:footnote w:type=\"continuationSeparator\" w:id=\"0\"><w:p><w:pPr><w:spacing w:after=\"0\"/></w:pPr><w:r><w:continuationSeparator/></w:r></w:p></w:footnote>\n\
+         {}</w:footnotes>\n",
+        NAMESPACES, writer.notes.xml
+    );
+    let styles_xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:styles {}>\n{}</w:styles>\n",
+        NAMESPACES, STYLES
+    );
+    let settings_xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:settings {}>\n\
+         <w:footnotePr><w:footnote w:id=\"-1\"/><w:footnote w:id=\"0\"/></w:footnotePr>\n</w:settings>\n",
+        NAMESPACES
+    );
+
+    let author = front_matter(markdown)
+        .0
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("author"))
+        .map(|(_, author)| format!("<dc:creator>{}</dc:creator>", escape_xml(author)))
+        .unwrap_or_default();
+    let core_xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <cp:coreProperties xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:dcterms=\"http://purl.org/dc/terms/\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\n<dc:title>{}</dc:title>{}\
+         <dcterms:created xsi:type=\"dcterms:W3CDTF\">{}</dcterms:created>\n</cp:coreProperties>\n",
+        escape_xml(title),
+        author,
+        Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+    );
+
+    let content_types = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\n\
+<Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\n\
+<Default Extension=\"xml\" ContentType=\"application/xml\"/>\n\
+<Override PartName=\"/word/document.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml\"/>\n\
+<Override PartName=\"/word/styles.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml\"/>\n\
+<Override PartName=\"/word/numbering.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml\"/>\n\
+<Override PartName=\"/word/footnotes.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.footnotes+xml\"/>\n\
+<Override PartName=\"/word/settings.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.settings+xml\"/>\n\
+<Override PartName=\"/docProps/core.xml\" ContentType=\"application/vnd.openxmlformats-package.core-properties+xml\"/>\n\
+</Types>\n";
+    let package_rels = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\n\
+         <Relationship Id=\"rId1\" Type=\"{}/officeDocument\" Target=\"word/document.xml\"/>\n\
+         <Relationship Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties\" Target=\"docProps/core.xml\"/>\n\
+         </Relationships>\n",
+        RELATIONSHIP
+    );
+    let document_rels = document.relationships(&format!(
+        "<Relationship Id=\"rId1\" Type=\"{r}/styles\" Target=\"styles.xml\"/>\n\
+         <Relationship Id=\"rId2\" Type=\"{r}/numbering\" Target=\"numbering.xml\"/>\n\
+         <Relationship Id=\"rId3\" Type=\"{r}/footnotes\" Target=\"footnotes.xml\"/>\n\
+         <Relationship Id=\"rId4\" Type=\"{r}/settings\" Target=\"settings.xml\"/>\n",
+        r = RELATIONSHIP
+    ));
+    let footnote_rels = writer.notes.relationships("");
+
+    let files = vec![
+        ("[Content_Types].xml".to_string(), content_types.as_bytes().to_vec()),
+        ("_rels/.rels".to_string(), package_rels.into_bytes()),
+        ("docProps/core.xml".to_string(), core_xml.into_bytes()),
+        ("word/document.xml".to_string(), document_xml.into_bytes()),
+        ("word/_rels/document.xml.rels".to_string(), document_rels.into_bytes()),
+        ("word/styles.xml".to_string(), styles_xml.into_bytes()),
+        ("word/numbering.xml".to_string(), writer.numbering().into_bytes()),
+        ("word/footnotes.xml".to_string(), footnotes_xml.into_bytes()),
+        ("word/_rels/footnotes.xml.rels".to_string(), footnote_rels.into_bytes()),
+        ("word/settings.xml".to_string(), settings_xml.into_bytes()),
+    ];
+    write_zip(output, None, files)
+}
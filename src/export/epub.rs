@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use pulldown_cmark::{CowStr, Event, Parser, Tag, TagEnd, html};
+
+use crate::document::{document_title, front_matter};
+use crate::export::{escape_xml, markdown_options, write_zip};
+
+const STYLESHEET: &str = "\
+body { font-family: serif; line-height: 1.5; margin: 0 5%; }
+h1, h2, h3, h4, h5, h6 { font-family: sans-serif; line-height: 1.2; }
+blockquote { margin-left: 1em; padding-left: 1em; border-left: 2px solid #999; font-style: italic; }
+pre, code { font-family: monospace; font-size: 0.9em; }
+pre { white-space: pre-wrap; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #999; padding: 0.2em 0.5em; }
+aside { font-size: 0.9em; }
+.cover { text-align: center; margin: 0; padding: 0; }
+.cover img { max-width: 100%; max-height: 100%; }
+";
+
+/// Book-level details, taken from the front matter of the first chapter
+/// that provides each one.
+struct Metadata {
+    title: String,
+    author: Option<String>,
+    language: String,
+    description: Option<String>,
+    cover: Option<PathBuf>,
+}
+
+struct TocEntry {
+    level: usize,
+    text: String,
+    href: String,
+}
+
+fn metadata(chapters: &[(PathBuf, String)]) -> Metadata {
+    let mut found: HashMap<String, String> = HashMap::new();
+    let mut cover = None;
+    for (path, content) in chapters {
+        for (key, value) in front_matter(content).0 {
+            let key = key.to_ascii_lowercase();
+            if key == "cover" && cover.is_none() {
+                cover = path.parent().map(|dir| dir.join(value));
+            }
+            found.entry(key).or_insert_with(|| value.to_string());
+        }
+    }
+
+    let title = found.remove("title").unwrap_or_else(|| {
+        chapters
+            .first()
+            .map(|(_, content)| document_title(content).to_string())
+            .filter(|title| !title.is_empty())
+            .unwrap_or_else(|| "Untitled".to_string())
+    });
+    Metadata {
+        title,
+        author: found.remove("author"),
+        language: found
+            .remove("language")
+            .or_else(|| found.remove("lang"))
+            .unwrap_or_else(|| "en".to_string()),
+        description: found.remove("description"),
+        cover,
+    }
+}
+
+fn image_media_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        "gif" => Some("image/gif"),
+        "svg" => Some("image/svg+xml"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+fn xhtml_page(title: &str, language: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" \
+         xml:lang=\"{lang}\" lang=\"{lang}\">\n<head>\n<meta charset=\"UTF-8\"/>\n<title>{title}</title>\n\
+         <link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n<body>\n{body}</body>\n</html>\n",
+        lang = escape_xml(language),
+        title = escape_xml(title),
+        body = body
+    )
+}
+
+/// Renders one chapter to an XHTML body, giving every heading an id and
+/// recording it in `toc` so the navigation document can link to it. Raw
+/// HTML in the markdown is shown as text, since a stray `<br>` or unclosed
+/// tag would make the whole book invalid XHTML.
+fn render_chapter(markdown: &str, file: &str, toc: &mut Vec<TocEntry>) -> String {
+    let mut events: Vec<Event> = Parser::new_ext(markdown, markdown_options()).collect();
+    // Footnotes are numbered in order of first appearance, and ids come
+    // from the number since labels can be anything.
+    let mut footnotes: HashMap<String, usize> = HashMap::new();
+    let mut footnote_number = |label: &str| {
+        let next = footnotes.len() + 1;
+        *footnotes.entry(label.to_string()).or_insert(next)
+    };
+    let mut heading: Option<(usize, usize, String)> = None;
+    let mut headings = 0;
+
+    for ix in 0..events.len() {
+        let replacement = match &events[ix] {
+            Event::Start(Tag::Heading { level, .. }) => {
+                headings += 1;
+                heading = Some((ix, *level as usize, String::new()));
+                None
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, _, heading_text)) = heading.as_mut() {
+                    heading_text.push_str(text);
+                }
+                None
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((start, level, text)) = heading.take() {
+                    let id = format!("h{}", headings);
+                    if let Event::Start(Tag::Heading { id: heading_id, .. }) = &mut events[start] {
+                        *heading_id = Some(CowStr::from(id.clone()));
+                    }
+                    toc.push(TocEntry {
+                        level,
+                        text,
+                        href: format!("{}#{}", file, id),
+                    });
+                }
+                None
+            }
+            Event::FootnoteReference(label) => {
+                let number = footnote_number(label);
+                Some(Event::InlineHtml(CowStr::from(format!(
+                    "<sup><a epub:type=\"noteref\" href=\"#fn-{}\">{}</a></sup>",
+                    number, number
+                ))))
+            }
+            Event::Start(Tag::FootnoteDefinition(label)) => Some(Event::Html(CowStr::from(format!(
+                "<aside epub:type=\"footnote\" id=\"fn-{}\">\n",
+                footnote_number(label)
+            )))),
+            Event::Html(raw) | Event::InlineHtml(raw) => Some(Event::Text(raw.clone())),
+            Event::End(TagEnd::FootnoteDefinition) => Some(Event::Html("</aside>\n".into())),
+            _ => None,
+        };
+        if let Some(replacement) = replacement {
+            events[ix] = replacement;
+        }
+    }
+
+    let mut body = String::new();
+    html::push_html(&mut body, events.into_iter());
+    body
+}
+
+/// Builds the EPUB 3 navigation document, nesting entries by heading level.
+fn nav_document(meta: &Metadata, toc: &[TocEntry]) -> String {
+    let mut body = String::from("<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n<ol>\n");
+    let mut depth: Vec<usize> = Vec::new();
+    for entry in toc {
+        while depth.len() > 1 && depth.last().is_some_and(|&level| level > entry.level) {
+            depth.pop();
+            body.push_str("</li>\n</ol>\n");
+        }
+        match depth.last_mut() {
+            None => depth.push(entry.level),
+            Some(top) if entry.level > *top => {
+                body.push_str("<ol>\n");
+                depth.push(entry.level);
+            }
+            Some(top) => {
+                body.push_str("</li>\n");
+                *top = entry.level;
+            }
+        }
+        body.push_str(&format!(
+            "<li><a href=\"{}\">{}</a>",
+            entry.href,
+            escape_xml(&entry.text)
+        ));
+    }
+    for ix in (0..depth.len()).rev() {
+        body.push_str("</li>\n");
+        if ix > 0 {
+            body.push_str("</ol>\n");
+        }
+    }
+    body.push_str("</ol>\n</nav>\n");
+    xhtml_page(&meta.title, &meta.language, &body)
+}
+
+fn book_identifier(meta: &Metadata, chapters: &[(PathBuf, String)]) -> String {
+    let mut hasher = DefaultHasher::new();
+    meta.title.hash(&mut hasher);
+    for (path, _) in chapters {
+        path.hash(&mut hasher);
+    }
+    let a = hasher.finish();
+    chapters.len().hash(&mut hasher);
+    let b = hasher.finish();
+    format!(
+        "urn:uuid:{:08x}-{:04x}-4{:03x}-8{:03x}-{:012x}",
+        a >> 32,
+        (a >> 16) & 0xffff,
+        a & 0xfff,
+        b >> 52,
+        b & 0xffff_ffff_ffff
+    )
+}
+
+/// Writes `chapters`, in order, as an EPUB 3 book. Headings across all
+/// chapters become the table of contents; title, author, language,
+/// description and cover image come from front matter.
+pub fn write_epub(chapters: &[(PathBuf, String)], output: &Path) -> io::Result<()> {
+    let meta = metadata(chapters);
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut manifest = String::new();
+    let mut spine = String::new();
+    let mut toc = Vec::new();
+
+    let cover = match &meta.cover {
+        Some(path) => {
+            let media_type = image_media_type(path).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unsupported cover image {}", path.display()),
+                )
+            })?;
+            let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+            Some((format!("cover.{}", ext.to_ascii_lowercase()), media_type, fs::read(path)?))
+        }
+        None => None,
+    };
+    if let Some((name, media_type, bytes)) = cover {
+        manifest.push_str(&format!(
+            "<item id=\"cover-image\" href=\"{}\" media-type=\"{}\" properties=\"cover-image\"/>\n",
+            name, media_type
+        ));
+        manifest.push_str(
+            "<item id=\"cover\" href=\"cover.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+        );
+        spine.push_str("<itemref idref=\"cover\" linear=\"no\"/>\n");
+        let page = format!(
+            "<div class=\"cover\"><img src=\"{}\" alt=\"{}\"/></div>\n",
+            name,
+            escape_xml(&meta.title)
+        );
+        files.push(("OEBPS/cover.xhtml".into(), xhtml_page(&meta.title, &meta.language, &page).into_bytes()));
+        files.push((format!("OEBPS/{}", name), bytes));
+    }
+
+    for (ix, (_, content)) in chapters.iter().enumerate() {
+        let file = format!("chapter-{:03}.xhtml", ix + 1);
+        let first_entry = toc.len();
+        let body = render_chapter(content, &file, &mut toc);
+        let title = match document_title(content) {
+            "" => format!("Chapter {}", ix + 1),
+            title => title.to_string(),
+        };
+        if toc.len() == first_entry {
+            toc.push(TocEntry {
+                level: 1,
+                text: title.clone(),
+                href: file.clone(),
+            });
+        }
+        manifest.push_str(&format!(
+            "<item id=\"chapter-{n}\" href=\"{file}\" media-type=\"application/xhtml+xml\"/>\n",
+            n = ix + 1,
+            file = file
+        ));
+        spine.push_str(&format!("<itemref idref=\"chapter-{}\"/>\n", ix + 1));
+        files.push((format!("OEBPS/{}", file), xhtml_page(&title, &meta.language, &body).into_bytes()));
+    }
+
+    files.push(("OEBPS/nav.xhtml".into(), nav_document(&meta, &toc).into_bytes()));
+    files.push(("OEBPS/style.css".into(), STYLESHEET.as_bytes().to_vec()));
+
+    let mut dc = format!(
+        "<dc:identifier id=\"book-id\">{}</dc:identifier>\n<dc:title>{}</dc:title>\n<dc:language>{}</dc:language>\n",
+        book_identifier(&meta, chapters),
+        escape_xml(&meta.title),
+        escape_xml(&meta.language)
+    );
+    if let Some(author) = &meta.author {
+        dc.push_str(&format!("<dc:creator>{}</dc:creator>\n", escape_xml(author)));
+    }
+    if let Some(description) = &meta.description {
+        dc.push_str(&format!("<dc:description>{}</dc:description>\n", escape_xml(description)));
+    }
+    let opf = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n{dc}\
+         <meta property=\"dcterms:modified\">{modified}</meta>\n</metadata>\n\
+         <manifest>\n<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+         <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n{manifest}</manifest>\n\
+         <spine>\n{spine}</spine>\n</package>\n",
+        dc = dc,
+        modified = Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+        manifest = manifest,
+        spine = spine
+    );
+    files.push(("OEBPS/content.opf".into(), opf.into_bytes()));
+    files.push((
+        "META-INF/container.xml".into(),
+        b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n\
+<rootfiles>\n<rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>\n</rootfiles>\n\
+</container>\n"
+            .to_vec(),
+    ));
+
+    write_zip(output, Some("application/epub+zip"), files)
+}
@@ -0,0 +1,77 @@
+use gpui::Hsla;
+use pulldown_cmark::{Parser, html};
+
+use crate::editor::{FONT_SIZE, LINE_HEIGHT};
+use crate::export::{escape_xml, markdown_options};
+use crate::theme::Theme;
+
+/// Same measure as the editor column: its 800px width less 48px padding a side.
+const CONTENT_WIDTH: f32 = 704.0;
+
+fn css_color(color: Hsla) -> String {
+    format!(
+        "hsla({:.0}, {:.0}%, {:.0}%, {:.2})",
+        color.h * 360.0,
+        color.s * 100.0,
+        color.l * 100.0,
+        color.a
+    )
+}
+
+fn stylesheet(theme: &Theme) -> String {
+    let background = css_color(theme.background);
+    let foreground = css_color(theme.foreground);
+    let muted = css_color(theme.muted);
+    let selection = css_color(theme.selection);
+    let tint = css_color(Hsla {
+        a: 0.08,
+        ..theme.foreground
+    });
+    format!(
+        r#"
+html {{ background: {background}; }}
+body {{
+  max-width: {CONTENT_WIDTH}px;
+  margin: 0 auto;
+  padding: 48px 24px;
+  background: {background};
+  color: {foreground};
+  font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", system-ui, sans-serif;
+  font-size: {FONT_SIZE}px;
+  line-height: {LINE_HEIGHT}px;
+}}
+h1, h2, h3, h4, h5, h6 {{ line-height: 1.3; }}
+a {{ color: inherit; text-decoration-color: {muted}; }}
+::selection {{ background: {selection}; }}
+blockquote {{ margin: 0; padding-left: 1em; border-left: 3px solid {muted}; color: {muted}; }}
+code, pre {{ font-family: ui-monospace, Menlo, Consolas, monospace; font-size: 0.85em; }}
+code {{ background: {tint}; padding: 0.1em 0.3em; border-radius: 3px; }}
+pre {{ background: {tint}; padding: 1em; border-radius: 6px; overflow-x: auto; line-height: 1.5; }}
+pre code {{ background: none; padding: 0; }}
+table {{ border-collapse: collapse; width: 100%; line-height: 1.5; }}
+th, td {{ border: 1px solid {muted}; padding: 0.4em 0.75em; text-align: left; }}
+th {{ background: {tint}; }}
+hr {{ border: none; border-top: 1px solid {muted}; }}
+img {{ max-width: 100%; }}
+.footnote-definition {{ font-size: 0.85em; line-height: 1.5; color: {muted}; }}
+.footnote-definition p {{ display: inline; }}
+.footnote-definition-label {{ margin-right: 0.5em; }}
+"#
+    )
+}
+
+/// Renders a markdown document as a standalone HTML page styled to match
+/// `theme`, so an exported draft looks the way it did in the editor.
+pub fn render_html(markdown: &str, title: &str, theme: &Theme) -> String {
+    let mut body = String::new();
+    html::push_html(&mut body, Parser::new_ext(markdown, markdown_options()));
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_xml(title),
+        stylesheet(theme),
+        body
+    )
+}
@@ -0,0 +1,154 @@
+mod blocks;
+mod docx;
+mod epub;
+mod html;
+mod odt;
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::Options;
+use zip::CompressionMethod;
+use zip::write::{SimpleFileOptions, ZipWriter};
+
+use crate::document::document_title;
+use crate::theme::Theme;
+
+pub use docx::*;
+pub use epub::*;
+pub use html::*;
+pub use odt::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportFormat {
+    Html,
+    Epub,
+    Docx,
+    Odt,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "html" => Some(Self::Html),
+            "epub" => Some(Self::Epub),
+            "docx" | "word" => Some(Self::Docx),
+            "odt" => Some(Self::Odt),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Epub => "epub",
+            Self::Docx => "docx",
+            Self::Odt => "odt",
+        }
+    }
+}
+
+/// The markdown dialect jid documents are written in.
+pub fn markdown_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+}
+
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes a zip-based document package. Formats that carry a `mimetype`
+/// entry (EPUB, ODF) need it first and uncompressed so it can be sniffed.
+pub fn write_zip(
+    output: &Path,
+    mimetype: Option<&str>,
+    files: Vec<(String, Vec<u8>)>,
+) -> io::Result<()> {
+    let mut zip = ZipWriter::new(fs::File::create(output)?);
+    if let Some(mimetype) = mimetype {
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        zip.start_file("mimetype", stored)?;
+        zip.write_all(mimetype.as_bytes())?;
+    }
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, bytes) in files {
+        zip.start_file(name, deflated)?;
+        zip.write_all(&bytes)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// Exports several documents, in order, as one book. Only EPUB supports
+/// more than one document; other formats take the first.
+pub fn export_book(
+    chapters: &[(PathBuf, String)],
+    format: ExportFormat,
+    theme: &Theme,
+    output: Option<&Path>,
+) -> io::Result<PathBuf> {
+    let Some((first, content)) = chapters.first() else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "nothing to export"));
+    };
+    if format != ExportFormat::Epub {
+        return export_document(first, content, format, theme, output);
+    }
+    let output = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| first.with_extension(format.extension()));
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_epub(chapters, &output)?;
+    Ok(output)
+}
+
+/// Exports the markdown document at `source` and returns where the result
+/// was written: `output` if given, otherwise next to the source file.
+pub fn export_document(
+    source: &Path,
+    content: &str,
+    format: ExportFormat,
+    theme: &Theme,
+    output: Option<&Path>,
+) -> io::Result<PathBuf> {
+    let output = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| source.with_extension(format.extension()));
+    let title = match document_title(content) {
+        "" => source
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("Untitled"),
+        title => title,
+    };
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match format {
+        ExportFormat::Html => fs::write(&output, render_html(content, title, theme))?,
+        ExportFormat::Epub => {
+            let chapters = [(source.to_path_buf(), content.to_string())];
+            return export_book(&chapters, format, theme, Some(&output));
+        }
+        ExportFormat::Docx => write_docx(content, title, &output)?,
+        ExportFormat::Odt => write_odt(content, title, &output)?,
+    }
+    Ok(output)
+}
@@ -0,0 +1,320 @@
+use std::io;
+use std::path::Path;
+
+use chrono::Local;
+
+use crate::document::front_matter;
+use crate::export::blocks::{Inline, ListLevel, Manuscript, Paragraph, ParagraphKind, Run, manuscript};
+use crate::export::{escape_xml, write_zip};
+
+const MIMETYPE: &str = "application/vnd.oasis.opendocument.text";
+const NAMESPACES: &str = "xmlns:office=\"urn:oasis:names:tc:opendocument:xmlns:office:1.0\" \
+     xmlns:style=\"urn:oasis:names:tc:opendocument:xmlns:style:1.0\" \
+     xmlns:text=\"urn:oasis:names:tc:opendocument:xmlns:text:1.0\" \
+     xmlns:fo=\"urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0\" \
+     xmlns:svg=\"urn:oasis:names:tc:opendocument:xmlns:svg-compatible:1.0\" \
+     xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+     xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+     xmlns:meta=\"urn:oasis:names:tc:opendocument:xmlns:meta:1.0\" office:version=\"1.3\"";
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+/// Named after LibreOffice's own styles, so they merge with the ones Writer
+/// already offers instead of showing up as strangers.
+const STYLES: &str = r##"<style:default-style style:family="paragraph"><style:paragraph-properties fo:margin-bottom="0.25cm"/><style:text-properties fo:font-size="12pt"/></style:default-style>
+<style:style style:name="Standard" style:family="paragraph" style:class="text"/>
+<style:style style:name="Text_20_body" style:display-name="Text body" style:family="paragraph" style:parent-style-name="Standard" style:class="text"><style:paragraph-properties fo:margin-top="0cm" fo:margin-bottom="0.25cm" fo:line-height="115%"/></style:style>
+<style:style style:name="Heading" style:family="paragraph" style:parent-style-name="Standard" style:next-style-name="Text_20_body" style:class="text"><style:paragraph-properties fo:margin-top="0.42cm" fo:margin-bottom="0.21cm" fo:keep-with-next="always"/><style:text-properties fo:font-weight="bold"/></style:style>
+<style:style style:name="Heading_20_1" style:display-name="Heading 1" style:family="paragraph" style:parent-style-name="Heading" style:next-style-name="Text_20_body" style:default-outline-level="1" style:class="text"><style:text-properties fo:font-size="20pt"/></style:style>
+<style:style style:name="Heading_20_2" style:display-name="Heading 2" style:family="paragraph" style:parent-style-name="Heading" style:next-style-name="Text_20_body" style:default-outline-level="2" style:class="text"><style:text-properties fo:font-size="16pt"/></style:style>
+<style:style style:name="Heading_20_3" style:display-name="Heading 3" style:family="paragraph" style:parent-style-name="Heading" style:next-style-name="Text_20_body" style:default-outline-level="3" style:class="text"><style:text-properties fo:font-size="14pt"/></style:style>
+<style:style style:name="Heading_20_4" style:display-name="Heading 4" style:family="paragraph" style:parent-style-name="Heading" style:next-style-name="Text_20_body" style:default-outline-level="4" style:class="text"><style:text-properties fo:font-size="12pt" fo:font-style="italic"/></style:style>
+<style:style style:name="Heading_20_5" style:display-name="Heading 5" style:family="paragraph" style:parent-style-name="Heading" style:next-style-name="Text_20_body" style:default-outline-level="5" style:class="text"><style:text-properties fo:font-size="12pt"/></style:style>
+<style:style style:name="Heading_20_6" style:display-name="Heading 6" style:family="paragraph" style:parent-style-name="Heading" style:next-style-name="Text_20_body" style:default-outline-level="6" style:class="text"><style:text-properties fo:font-size="12pt" fo:font-weight="normal" fo:font-style="italic"/></style:style>
+<style:style style:name="Quotations" style:family="paragraph" style:parent-style-name="Standard" style:class="html"><style:paragraph-properties fo:margin-left="1cm" fo:margin-right="1cm" fo:margin-top="0cm" fo:margin-bottom="0.25cm"/><style:text-properties fo:font-style="italic"/></style:style>
+<style:style style:name="Preformatted_20_Text" style:display-name="Preformatted Text" style:family="paragraph" style:parent-style-name="Standard" style:class="html"><style:paragraph-properties fo:margin-top="0cm" fo:margin-bottom="0.25cm"/><style:text-properties style:font-name="Liberation Mono" fo:font-size="10pt"/></style:style>
+<style:style style:name="List" style:family="paragraph" style:parent-style-name="Text_20_body" style:class="list"><style:paragraph-properties fo:margin-bottom="0.1cm"/></style:style>
+<style:style style:name="Footnote" style:family="paragraph" style:parent-style-name="Standard" style:class="extra"><style:paragraph-properties fo:margin-left="0.6cm" fo:margin-bottom="0cm" fo:text-indent="-0.6cm"/><style:text-properties fo:font-size="10pt"/></style:style>
+<style:style style:name="Internet_20_link" style:display-name="Internet Link" style:family="text"><style:text-properties fo:color="#000080" style:text-underline-style="solid" style:text-underline-width="auto" style:text-underline-color="font-color"/></style:style>
+<style:style style:name="Source_20_Text" style:display-name="Source Text" style:family="text"><style:text-properties style:font-name="Liberation Mono"/></style:style>
+"##;
+
+fn list_styles() -> String {
+    let mut xml = String::new();
+    for (name, display, ordered) in [("Bullets", "Bullets", false), ("Numbering_20_123", "Numbering 123", true)] {
+        xml.push_str(&format!(
+            "<text:list-style style:name=\"{}\" style:display-name=\"{}\">\n",
+            name, display
+        ));
+        for level in 1..=10 {
+            let tag = if ordered {
+                format!("<text:list-level-style-number text:level=\"{}\" style:num-suffix=\".\" style:num-format=\"1\">", level)
+            } else {
+                format!(
+                    "<text:list-level-style-bullet text:level=\"{}\" text:bullet-char=\"{}\">",
+                    level,
+                    BULLETS[(level - 1) % BULLETS.len()]
+                )
+            };
+            let indent = 0.635 * level as f32;
+            xml.push_str(&format!(
+                "{}<style:list-level-properties text:list-level-position-and-space-mode=\"label-alignment\">\
+                 <style:list-level-label-alignment text:label-followed-by=\"listtab\" \
+                 text:list-tab-stop-position=\"{indent:.3}cm\" fo:text-indent=\"-0.635cm\" fo:margin-left=\"{indent:.3}cm\"/>\
+                 </style:list-level-properties>{}\n",
+                tag,
+                if ordered { "</text:list-level-style-number>" } else { "</text:list-level-style-bullet>" },
+                indent = indent
+            ));
+        }
+        xml.push_str("</text:list-style>\n");
+    }
+    xml
+}
+
+/// Automatic text styles for every mix of bold, italic, strikethrough and
+/// code, named `T` plus the bit mask `run_style` computes.
+fn text_styles() -> String {
+    let mut xml = String::new();
+    for mask in 1..16 {
+        let parent = if mask & 8 != 0 {
+            " style:parent-style-name=\"Source_20_Text\""
+        } else {
+            ""
+        };
+        let mut props = String::new();
+        if mask & 1 != 0 {
+            props.push_str(" fo:font-weight=\"bold\"");
+        }
+        if mask & 2 != 0 {
+            props.push_str(" fo:font-style=\"italic\"");
+        }
+        if mask & 4 != 0 {
+            props.push_str(" style:text-line-through-style=\"solid\" style:text-line-through-type=\"single\"");
+        }
+        xml.push_str(&format!(
+            "<style:style style:name=\"T{}\" style:family=\"text\"{}><style:text-properties{}/></style:style>\n",
+            mask, parent, props
+        ));
+    }
+    xml
+}
+
+fn run_style(run: &Run) -> usize {
+    run.bold as usize | (run.italic as usize) << 1 | (run.strike as usize) << 2 | (run.code as usize) << 3
+}
+
+/// Escapes text for a paragraph, spelling out the tabs and runs of spaces
+/// that ODF would otherwise collapse. Code keeps every space.
+fn odf_text(text: &str, code: bool) -> String {
+    let mut xml = String::new();
+    let mut spaces = 0;
+    let flush = |xml: &mut String, spaces: &mut usize| {
+        let literal = if code { 0 } else { (*spaces).min(1) };
+        xml.push_str(&" ".repeat(literal));
+        match *spaces - literal {
+            0 => {}
+            1 => xml.push_str("<text:s/>"),
+            n => xml.push_str(&format!("<text:s text:c=\"{}\"/>", n)),
+        }
+        *spaces = 0;
+    };
+    for c in text.chars() {
+        if c == ' ' {
+            spaces += 1;
+            continue;
+        }
+        flush(&mut xml, &mut spaces);
+        match c {
+            '\t' => xml.push_str("<text:tab/>"),
+            c => xml.push_str(&escape_xml(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    flush(&mut xml, &mut spaces);
+    xml
+}
+
+struct Writer<'a> {
+    manuscript: &'a Manuscript,
+    xml: String,
+    next_note: usize,
+    /// Lists currently open around the write position, outermost first,
+    /// each with an open list item.
+    open: Vec<ListLevel>,
+}
+
+impl Writer<'_> {
+    fn close_list(&mut self) {
+        self.open.pop();
+        self.xml.push_str("</text:list-item></text:list>\n");
+    }
+
+    /// Opens and closes lists and list items so the write position matches
+    /// the lists `paragraph` sits in.
+    fn enter_lists(&mut self, paragraph: &Paragraph) {
+        let common = self
+            .open
+            .iter()
+            .zip(&paragraph.list)
+            .take_while(|(open, list)| open.id == list.id)
+            .count();
+        while self.open.len() > common {
+            self.close_list();
+        }
+        if paragraph.new_item && common == paragraph.list.len() && common > 0 {
+            self.xml.push_str("</text:list-item>\n<text:list-item>");
+        }
+        for list in &paragraph.list[common..] {
+            let style = if list.ordered { "Numbering_20_123" } else { "Bullets" };
+            let start = if list.ordered && list.start != 1 {
+                format!(" text:start-value=\"{}\"", list.start)
+            } else {
+                String::new()
+            };
+            self.xml.push_str(&format!(
+                "<text:list text:style-name=\"{}\">\n<text:list-item{}>",
+                style, start
+            ));
+            self.open.push(*list);
+        }
+    }
+
+    fn paragraph(&mut self, paragraph: &Paragraph, in_note: bool) {
+        let (tag, style, level) = match paragraph.kind {
+            _ if in_note => ("text:p", "Footnote".to_string(), None),
+            ParagraphKind::Heading(level) => {
+                let level = level.clamp(1, 6);
+                ("text:h", format!("Heading_20_{}", level), Some(level))
+            }
+            ParagraphKind::Quote => ("text:p", "Quotations".to_string(), None),
+            ParagraphKind::Code => ("text:p", "Preformatted_20_Text".to_string(), None),
+            ParagraphKind::Body if !paragraph.list.is_empty() => ("text:p", "List".to_string(), None),
+            ParagraphKind::Body => ("text:p", "Text_20_body".to_string(), None),
+        };
+        match level {
+            Some(level) => self.xml.push_str(&format!(
+                "<{} text:style-name=\"{}\" text:outline-level=\"{}\">",
+                tag, style, level
+            )),
+            None => self.xml.push_str(&format!("<{} text:style-name=\"{}\">", tag, style)),
+        }
+        self.inlines(&paragraph.inlines, in_note);
+        self.xml.push_str(&format!("</{}>\n", tag));
+    }
+
+    fn inlines(&mut self, inlines: &[Inline], in_note: bool) {
+        for inline in inlines {
+            match inline {
+                Inline::Text(run) => {
+                    let mut text = odf_text(&run.text, run.code);
+                    let style = run_style(run);
+                    if style != 0 {
+                        text = format!("<text:span text:style-name=\"T{}\">{}</text:span>", style, text);
+                    }
+                    if let Some(url) = &run.link {
+                        text = format!(
+                            "<text:a xlink:type=\"simple\" xlink:href=\"{}\" text:style-name=\"Internet_20_link\">{}</text:a>",
+                            escape_xml(url),
+                            text
+                        );
+                    }
+                    self.xml.push_str(&text);
+                }
+                Inline::Break => self.xml.push_str("<text:line-break/>"),
+                Inline::Footnote(label) => match self.manuscript.footnote(label) {
+                    Some(body) if !in_note => {
+                        let number = self.next_note;
+                        self.next_note += 1;
+                        self.xml.push_str(&format!(
+                            "<text:note text:id=\"ftn{n}\" text:note-class=\"footnote\">\
+                             <text:note-citation>{n}</text:note-citation><text:note-body>",
+                            n = number
+                        ));
+                        for paragraph in body {
+                            self.paragraph(paragraph, true);
+                        }
+                        if body.is_empty() {
+                            self.xml.push_str("<text:p text:style-name=\"Footnote\"/>");
+                        }
+                        self.xml.push_str("</text:note-body></text:note>");
+                    }
+                    _ => self.xml.push_str(&odf_text(&format!("[{}]", label), false)),
+                },
+            }
+        }
+    }
+}
+
+/// Writes `markdown` as an OpenDocument text file, using Writer's standard
+/// paragraph, list and footnote styles.
+pub fn write_odt(markdown: &str, title: &str, output: &Path) -> io::Result<()> {
+    let manuscript = manuscript(markdown);
+    let mut writer = Writer {
+        manuscript: &manuscript,
+        xml: String::new(),
+        next_note: 1,
+        open: Vec::new(),
+    };
+    for paragraph in &manuscript.paragraphs {
+        writer.enter_lists(paragraph);
+        writer.paragraph(paragraph, false);
+    }
+    while !writer.open.is_empty() {
+        writer.close_list();
+    }
+
+    let font_faces = "<office:font-face-decls>\
+         <style:font-face style:name=\"Liberation Mono\" svg:font-family=\"&apos;Liberation Mono&apos;\" \
+         style:font-family-generic=\"modern\" style:font-pitch=\"fixed\"/></office:font-face-decls>\n";
+    let content = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<office:document-content {}>\n{}\
+         <office:automatic-styles>\n{}</office:automatic-styles>\n\
+         <office:body>\n<office:text>\n{}</office:text>\n</office:body>\n</office:document-content>\n",
+        NAMESPACES,
+        font_faces,
+        text_styles(),
+        writer.xml
+    );
+    let styles = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<office:document-styles {}>\n{}\
+         <office:styles>\n{}{}</office:styles>\n</office:document-styles>\n",
+        NAMESPACES,
+        font_faces,
+        STYLES,
+        list_styles()
+    );
+
+    let author = front_matter(markdown)
+        .0
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("author"))
+        .map(|(_, author)| format!("<meta:initial-creator>{}</meta:initial-creator>", escape_xml(author)))
+        .unwrap_or_default();
+    let meta = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<office:document-meta {}>\n<office:meta>\
+         <meta:generator>jid/{}</meta:generator><dc:title>{}</dc:title>{}\
+         <meta:creation-date>{}</meta:creation-date></office:meta>\n</office:document-meta>\n",
+        NAMESPACES,
+        env!("CARGO_PKG_VERSION"),
+        escape_xml(title),
+        author,
+        Local::now().format("%Y-%m-%dT%H:%M:%S")
+    );
+    let manifest = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <manifest:manifest xmlns:manifest=\"urn:oasis:names:tc:opendocument:xmlns:manifest:1.0\" manifest:version=\"1.3\">\n\
+         <manifest:file-entry manifest:full-path=\"/\" manifest:version=\"1.3\" manifest:media-type=\"{}\"/>\n\
+         <manifest:file-entry manifest:full-path=\"content.xml\" manifest:media-type=\"text/xml\"/>\n\
+         <manifest:file-entry manifest:full-path=\"styles.xml\" manifest:media-type=\"text/xml\"/>\n\
+         <manifest:file-entry manifest:full-path=\"meta.xml\" manifest:media-type=\"text/xml\"/>\n\
+         </manifest:manifest>\n",
+        MIMETYPE
+    );
+
+    let files = vec![
+        ("content.xml".to_string(), content.into_bytes()),
+        ("styles.xml".to_string(), styles.into_bytes()),
+        ("meta.xml".to_string(), meta.into_bytes()),
+        ("META-INF/manifest.xml".to_string(), manifest.into_bytes()),
+    ];
+    write_zip(output, Some(MIMETYPE), files)
+}
@@ -1,48 +1,129 @@
 mod actions;
 mod app;
+mod cli;
 mod config;
 mod document;
 mod editor;
+mod export;
+mod markdown;
+mod outline;
+mod pressure;
+mod readability;
+mod search;
+mod session;
+mod spell;
+mod sprint;
+mod stats;
 mod theme;
 
 use actions::*;
 use editor::*;
 use gpui::*;
+use search::{Dismiss, OpenHit, SelectNextHit, SelectPreviousHit};
+use session::Session;
+use spell::{ConfirmSuggestion, DismissSuggestions, SelectNextSuggestion, SelectPreviousSuggestion};
+use sprint::{CancelSprint, ConfirmSprint, LongerSprint, ShorterSprint};
+use stats::CloseStats;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     Application::new().run(|cx| {
+        // In Vim's normal and visual modes these keys are commands instead.
+        let typing = Some("Editor && (!vim || vim_mode == insert)");
         cx.bind_keys([
-            KeyBinding::new("backspace", Backspace, Some("Editor")),
-            KeyBinding::new("delete", Delete, Some("Editor")),
-            KeyBinding::new("left", Left, Some("Editor")),
-            KeyBinding::new("right", Right, Some("Editor")),
-            KeyBinding::new("up", Up, Some("Editor")),
-            KeyBinding::new("down", Down, Some("Editor")),
+            KeyBinding::new("backspace", Backspace, typing),
+            KeyBinding::new("delete", Delete, typing),
+            KeyBinding::new("left", Left, typing),
+            KeyBinding::new("right", Right, typing),
+            KeyBinding::new("up", Up, typing),
+            KeyBinding::new("down", Down, typing),
             KeyBinding::new("shift-left", SelectLeft, Some("Editor")),
             KeyBinding::new("shift-right", SelectRight, Some("Editor")),
             KeyBinding::new("shift-up", SelectUp, Some("Editor")),
             KeyBinding::new("shift-down", SelectDown, Some("Editor")),
-            KeyBinding::new("enter", Newline, Some("Editor")),
+            KeyBinding::new("enter", Newline, typing),
+            KeyBinding::new("tab", Indent, typing),
+            KeyBinding::new("shift-tab", Outdent, typing),
             KeyBinding::new("cmd-a", SelectAll, Some("Editor")),
             KeyBinding::new("cmd-c", Copy, Some("Editor")),
             KeyBinding::new("cmd-x", Cut, Some("Editor")),
             KeyBinding::new("cmd-v", Paste, Some("Editor")),
+            KeyBinding::new("cmd-shift-v", PastePlain, Some("Editor")),
+            KeyBinding::new("cmd-z", Undo, Some("Editor")),
+            KeyBinding::new("cmd-shift-z", Redo, Some("Editor")),
+            KeyBinding::new("cmd-b", ToggleBold, Some("Editor")),
+            KeyBinding::new("cmd-i", ToggleItalic, Some("Editor")),
+            KeyBinding::new("cmd-k", InsertLink, Some("Editor")),
+            KeyBinding::new("cmd-shift-h", CycleHeading, Some("Editor")),
+            KeyBinding::new("cmd-alt-[", Fold, Some("Editor")),
+            KeyBinding::new("cmd-alt-]", Unfold, Some("Editor")),
+            KeyBinding::new("cmd-d", SelectNextOccurrence, Some("Editor")),
+            KeyBinding::new("escape", ClearCursors, Some("Editor && !vim")),
+            KeyBinding::new("ctrl-a", LineStart, Some("Editor && emacs")),
+            KeyBinding::new("ctrl-e", LineEnd, Some("Editor && emacs")),
+            KeyBinding::new("ctrl-f", Right, Some("Editor && emacs")),
+            KeyBinding::new("ctrl-b", Left, Some("Editor && emacs")),
+            KeyBinding::new("ctrl-n", Down, Some("Editor && emacs")),
+            KeyBinding::new("ctrl-p", Up, Some("Editor && emacs")),
+            KeyBinding::new("ctrl-d", Delete, Some("Editor && emacs")),
+            KeyBinding::new("ctrl-k", KillToEndOfLine, Some("Editor && emacs")),
+            KeyBinding::new("ctrl-y", Paste, Some("Editor && emacs")),
+            KeyBinding::new("alt-y", YankPop, Some("Editor && emacs")),
+            KeyBinding::new("ctrl-t", Transpose, Some("Editor && emacs")),
             KeyBinding::new("cmd-s", Save, Some("jid")),
             KeyBinding::new("cmd-shift-t", ToggleTheme, Some("jid")),
-            KeyBinding::new("cmd-shift-f", ToggleFocusMode, Some("Editor")),
+            KeyBinding::new("cmd-alt-f", ToggleFocusMode, Some("Editor")),
+            KeyBinding::new("cmd-shift-d", ToggleForwardOnly, Some("Editor")),
+            KeyBinding::new("cmd-shift-a", ToggleStyleAnalysis, Some("Editor")),
             KeyBinding::new("cmd-,", OpenConfig, Some("jid")),
+            KeyBinding::new("cmd-n", NewDocument, Some("jid")),
+            KeyBinding::new("cmd-w", CloseDocument, Some("jid")),
+            KeyBinding::new("ctrl-tab", NextTab, Some("jid")),
+            KeyBinding::new("ctrl-shift-tab", PreviousTab, Some("jid")),
+            KeyBinding::new("cmd-shift-f", SearchLibrary, Some("jid")),
+            KeyBinding::new("cmd-shift-o", ToggleOutline, Some("jid")),
+            KeyBinding::new("cmd-e", ExportHtml, Some("jid")),
+            KeyBinding::new("cmd-shift-e", ExportEpub, Some("jid")),
+            KeyBinding::new("cmd-alt-e", ExportDocx, Some("jid")),
+            KeyBinding::new("cmd-alt-shift-e", ExportOdt, Some("jid")),
+            KeyBinding::new("up", SelectPreviousHit, Some("Search")),
+            KeyBinding::new("down", SelectNextHit, Some("Search")),
+            KeyBinding::new("enter", OpenHit, Some("Search")),
+            KeyBinding::new("escape", Dismiss, Some("Search")),
+            KeyBinding::new("cmd-shift-s", ShowStats, Some("jid")),
+            KeyBinding::new("escape", CloseStats, Some("Stats")),
+            KeyBinding::new("cmd-shift-r", StartSprint, Some("jid")),
+            KeyBinding::new("cmd-shift-w", ToggleWriteOrLose, Some("jid")),
+            KeyBinding::new("cmd-.", SuggestSpelling, Some("Editor")),
+            KeyBinding::new("up", SelectPreviousSuggestion, Some("Spelling")),
+            KeyBinding::new("down", SelectNextSuggestion, Some("Spelling")),
+            KeyBinding::new("enter", ConfirmSuggestion, Some("Spelling")),
+            KeyBinding::new("escape", DismissSuggestions, Some("Spelling")),
+            KeyBinding::new("up", LongerSprint, Some("Sprint")),
+            KeyBinding::new("down", ShorterSprint, Some("Sprint")),
+            KeyBinding::new("enter", ConfirmSprint, Some("Sprint")),
+            KeyBinding::new("escape", CancelSprint, Some("Sprint")),
             KeyBinding::new("cmd-q", Quit, None),
         ]);
+        cx.bind_keys((1..=6).map(|level| {
+            KeyBinding::new(&format!("cmd-{}", level), SetHeading(level), Some("Editor"))
+        }));
 
         cx.on_action(|_: &Quit, cx| cx.quit());
 
+        let session = Session::load();
+        let bounds = session
+            .window
+            .map(|state| state.bounds())
+            .unwrap_or_else(|| Bounds::centered(None, size(px(900.0), px(700.0)), cx));
+
         cx.open_window(
             WindowOptions {
-                window_bounds: Some(WindowBounds::Windowed(Bounds::centered(
-                    None,
-                    size(px(900.0), px(700.0)),
-                    cx,
-                ))),
+                window_bounds: Some(WindowBounds::Windowed(bounds)),
                 titlebar: Some(TitlebarOptions {
                     title: Some("jid".into()),
                     appears_transparent: true,
@@ -51,7 +132,7 @@ fn main() {
                 ..Default::default()
             },
             |window, cx| {
-                let view = cx.new(|cx| app::Jid::new(window, cx));
+                let view = cx.new(|cx| app::Jid::new(session, window, cx));
                 window.focus(&view.read(cx).editor().focus_handle(cx));
                 view
             },
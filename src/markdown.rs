@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+use std::rc::Rc;
+
+/// What a line of a markdown document belongs to.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineKind {
+    /// The YAML block at the very top, `---` lines included.
+    FrontMatter,
+    /// A fenced code block, fence lines included.
+    Fence,
+    /// Anything else: prose, headings, lists, blank lines.
+    Text,
+}
+
+/// One line of a document, without its line break.
+pub struct Line<'a> {
+    pub text: &'a str,
+    pub range: Range<usize>,
+    pub kind: LineKind,
+}
+
+/// The lines of a markdown document, each marked as front matter, fenced
+/// code or text. A fence opened with backticks is only closed by at least
+/// as many backticks, and likewise for tildes.
+pub fn lines(markdown: &str) -> impl Iterator<Item = Line<'_>> {
+    let mut front_matter = markdown.starts_with("---\n");
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+    markdown.split('\n').enumerate().map(move |(ix, text)| {
+        let range = offset..offset + text.len();
+        offset = range.end + 1;
+        let trimmed = text.trim_start();
+        let kind = if front_matter {
+            front_matter = ix == 0 || trimmed.trim_end() != "---";
+            LineKind::FrontMatter
+        } else if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            LineKind::Fence
+        } else if let Some(marker) = fence_marker(trimmed) {
+            fence = Some(marker);
+            LineKind::Fence
+        } else {
+            LineKind::Text
+        };
+        Line { text, range, kind }
+    })
+}
+
+/// The run of three or more backticks or tildes opening a fenced code block.
+fn fence_marker(trimmed: &str) -> Option<&str> {
+    let c = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let len = trimmed.len() - trimmed.trim_start_matches(c).len();
+    (len >= 3).then(|| &trimmed[..len])
+}
+
+/// Byte ranges of the blank-line-separated blocks of a markdown document.
+/// Front matter and fenced code stay in one block, blank lines and all,
+/// so each block parses on its own the way it does in place.
+pub fn blocks(markdown: &str) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+    for line in lines(markdown) {
+        match &mut current {
+            _ if line.kind == LineKind::Text && line.text.trim().is_empty() => blocks.extend(current.take()),
+            Some(block) => block.end = line.range.end,
+            None => current = Some(line.range),
+        }
+    }
+    blocks.extend(current);
+    blocks
+}
+
+/// Whatever was worked out for each block of a document, remembered by the
+/// block's text so that after a keystroke only the block it changed has to
+/// be worked out again. A block at the very start is kept apart from the
+/// same text elsewhere, since only there can it be front matter.
+pub struct BlockCache<T> {
+    blocks: HashMap<(u64, bool), Rc<T>>,
+}
+
+impl<T> Default for BlockCache<T> {
+    fn default() -> Self {
+        Self { blocks: HashMap::new() }
+    }
+}
+
+impl<T> BlockCache<T> {
+    /// The result for each of `ranges` in `markdown`, calling `work` with the
+    /// text of the blocks it hasn't seen and whether they start the document.
+    /// Blocks no longer in the document are forgotten, so the cache can't
+    /// grow without bound over a long session.
+    pub fn get(
+        &mut self,
+        markdown: &str,
+        ranges: Vec<Range<usize>>,
+        mut work: impl FnMut(&str, bool) -> T,
+    ) -> Vec<(Range<usize>, Rc<T>)> {
+        let mut seen = HashMap::new();
+        let found = ranges
+            .into_iter()
+            .map(|range| {
+                let text = &markdown[range.clone()];
+                let first = range.start == 0;
+                let mut hasher = DefaultHasher::new();
+                text.hash(&mut hasher);
+                let key = (hasher.finish(), first);
+                let result = self.blocks.get(&key).cloned().unwrap_or_else(|| Rc::new(work(text, first)));
+                seen.insert(key, result.clone());
+                (range, result)
+            })
+            .collect();
+        self.blocks = seen;
+        found
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(markdown: &str) -> Vec<LineKind> {
+        lines(markdown).map(|line| line.kind).collect()
+    }
+
+    #[test]
+    fn lines_mark_front_matter_and_fences() {
+        use LineKind::*;
+        let text = "---\ntitle: A\n---\nOne\n```rust\ncode\n```\nTwo";
+        assert_eq!(kinds(text), [FrontMatter, FrontMatter, FrontMatter, Text, Fence, Fence, Fence, Text]);
+        assert_eq!(kinds("Intro\n---\nmore"), [Text, Text, Text]);
+        assert_eq!(kinds("---\nnever closed\n# Heading"), [FrontMatter, FrontMatter, FrontMatter]);
+    }
+
+    #[test]
+    fn fences_close_only_on_their_own_marker() {
+        use LineKind::*;
+        assert_eq!(kinds("```\n~~~\nstill code\n```\nText"), [Fence, Fence, Fence, Fence, Text]);
+        assert_eq!(kinds("~~~~\n~~~\n~~~~\nText"), [Fence, Fence, Fence, Text]);
+        assert_eq!(kinds("``inline``\nText"), [Text, Text]);
+    }
+
+    #[test]
+    fn blocks_keep_front_matter_and_fences_whole() {
+        let text = "---\ntitle: A\n\nb: c\n---\n\nOne\ntwo\n\n```\ncode\n\nmore\n```\n\n\nLast";
+        let found: Vec<&str> = blocks(text).into_iter().map(|range| &text[range]).collect();
+        assert_eq!(found, ["---\ntitle: A\n\nb: c\n---", "One\ntwo", "```\ncode\n\nmore\n```", "Last"]);
+    }
+
+    #[test]
+    fn cache_works_out_only_new_blocks() {
+        let mut cache = BlockCache::default();
+        let mut worked = Vec::new();
+        let mut run = |cache: &mut BlockCache<usize>, text: &str| {
+            cache.get(text, blocks(text), |block, _| {
+                worked.push(block.to_string());
+                block.len()
+            });
+        };
+        run(&mut cache, "One\n\nTwo");
+        run(&mut cache, "One\n\nTwo!\n\nTwo");
+        assert_eq!(worked, ["One", "Two", "Two!"]);
+    }
+}
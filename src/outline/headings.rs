@@ -0,0 +1,171 @@
+use std::ops::Range;
+
+use crate::markdown::{LineKind, lines};
+
+/// A markdown heading: its level (1 to 6), its text and where its line
+/// starts in the document.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Heading {
+    pub level: usize,
+    pub title: String,
+    pub start: usize,
+}
+
+/// The `#` headings of a document, in order. Lines inside fenced code and
+/// front matter don't count.
+pub fn headings(content: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    for line in lines(content).filter(|line| line.kind == LineKind::Text) {
+        let trimmed = line.text.trim_start();
+        // Up to three spaces of indent; four make it code.
+        if line.text.len() - trimmed.len() > 3 {
+            continue;
+        }
+        let level = trimmed.bytes().take_while(|&b| b == b'#').count();
+        let rest = &trimmed[level..];
+        if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+            continue;
+        }
+        // A closing run of `#`s isn't part of the title.
+        let title = rest.trim().trim_end_matches('#').trim_end();
+        headings.push(Heading {
+            level,
+            title: title.to_string(),
+            start: line.range.start,
+        });
+    }
+    headings
+}
+
+/// The last heading in the section under heading `ix`, which is `ix` itself
+/// when the section has no subsections.
+pub fn last_in_section(headings: &[Heading], ix: usize) -> usize {
+    let level = headings[ix].level;
+    headings[ix + 1..].iter().take_while(|next| next.level > level).count() + ix
+}
+
+/// The section under heading `ix`: from its line to the next heading of the
+/// same or a higher level, so it takes its subsections with it.
+pub fn section_range(content: &str, headings: &[Heading], ix: usize) -> Range<usize> {
+    let heading = &headings[ix];
+    let end = headings[ix + 1..]
+        .iter()
+        .find(|next| next.level <= heading.level)
+        .map_or(content.len(), |next| next.start);
+    heading.start..end
+}
+
+/// Moves section `from` to where section `to` is: before it when moving up,
+/// after it when moving down. Returns the part of the document that changes,
+/// what it becomes and where the moved section now starts, or `None` if the
+/// move does nothing, as when `to` is inside the section being moved.
+pub fn move_section(
+    content: &str,
+    headings: &[Heading],
+    from: usize,
+    to: usize,
+) -> Option<(Range<usize>, String, usize)> {
+    if from >= headings.len() || to >= headings.len() {
+        return None;
+    }
+    let moved = section_range(content, headings, from);
+    let target = section_range(content, headings, to);
+    if from == to || moved.contains(&target.start) {
+        return None;
+    }
+
+    // The last section may end without a blank line, or any line break;
+    // give it a blank line like the rest when it lands in the middle.
+    let separated = |range: Range<usize>| {
+        let text = &content[range.clone()];
+        if range.end == content.len() {
+            format!("{}\n\n", text.trim_end())
+        } else {
+            text.to_string()
+        }
+    };
+    let section = separated(moved.clone());
+    let mut moved_text = String::new();
+    let (changed, landed) = if from < to {
+        moved_text.push_str(&separated(moved.end..target.end));
+        let landed = moved.start + moved_text.len();
+        moved_text.push_str(&section);
+        (moved.start..target.end, landed)
+    } else {
+        moved_text.push_str(&section);
+        moved_text.push_str(&content[target.start..moved.start]);
+        (target.start..moved.end, target.start)
+    };
+
+    // Whatever ended the document before still ends it.
+    if changed.end == content.len() {
+        let trailing = &content[content.trim_end().len()..];
+        moved_text.truncate(moved_text.trim_end().len());
+        moved_text.push_str(trailing);
+    }
+    Some((changed, moved_text, landed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The document after moving section `from` to section `to`, and where
+    /// the moved section starts, or `None` if nothing moves.
+    fn moved(content: &str, from: usize, to: usize) -> Option<(String, usize)> {
+        let (range, text, landed) = move_section(content, &headings(content), from, to)?;
+        let mut content = content.to_string();
+        content.replace_range(range, &text);
+        Some((content, landed))
+    }
+
+    #[test]
+    fn headings_skip_front_matter_fences_and_code() {
+        let text = "---\ntitle: x\n# not\n---\n# One\n```\n# code\n```\n~~~\n# tilde\n~~~\n    # code\n#tag\n## Two ##";
+        let found: Vec<(usize, String, usize)> =
+            headings(text).into_iter().map(|heading| (heading.level, heading.title, heading.start)).collect();
+        assert_eq!(found, [(1, "One".to_string(), 23), (2, "Two".to_string(), text.len() - 9)]);
+    }
+
+    #[test]
+    fn sections_take_their_subsections() {
+        let text = "# A\n## A1\n### A2\n## A3\n# B";
+        let found = headings(text);
+        assert_eq!([0, 1, 4].map(|ix| last_in_section(&found, ix)), [3, 2, 4]);
+        assert_eq!(&text[section_range(text, &found, 1)], "## A1\n### A2\n");
+        assert_eq!(&text[section_range(text, &found, 4)], "# B");
+    }
+
+    #[test]
+    fn moving_up_lands_before_the_target() {
+        let text = "# A\na\n\n# B\nb\n\n# C\nc\n";
+        assert_eq!(moved(text, 2, 0), Some(("# C\nc\n\n# A\na\n\n# B\nb\n".to_string(), 0)));
+    }
+
+    #[test]
+    fn moving_down_lands_after_the_target() {
+        let text = "# A\na\n\n# B\nb\n\n# C\nc\n";
+        assert_eq!(moved(text, 0, 1), Some(("# B\nb\n\n# A\na\n\n# C\nc\n".to_string(), 7)));
+    }
+
+    #[test]
+    fn the_last_section_moves_without_a_trailing_newline() {
+        let text = "# A\na\n\n# B\nb";
+        assert_eq!(moved(text, 1, 0), Some(("# B\nb\n\n# A\na".to_string(), 0)));
+        assert_eq!(moved(text, 0, 1), Some(("# B\nb\n\n# A\na".to_string(), 7)));
+    }
+
+    #[test]
+    fn subsections_move_with_their_section() {
+        let text = "# A\n## A1\n\n# B\n";
+        assert_eq!(moved(text, 2, 0), Some(("# B\n\n# A\n## A1\n".to_string(), 0)));
+    }
+
+    #[test]
+    fn a_section_does_not_move_into_itself() {
+        let text = "# A\n## A1\n# B\n";
+        assert_eq!(moved(text, 0, 1), None);
+        assert_eq!(moved(text, 0, 0), None);
+        assert_eq!(moved(text, 0, 5), None);
+    }
+}
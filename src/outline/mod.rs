@@ -0,0 +1,5 @@
+mod headings;
+mod view;
+
+pub use headings::*;
+pub use view::*;
@@ -0,0 +1,230 @@
+use gpui::*;
+
+use crate::editor::EditorView;
+use crate::outline::{Heading, headings, last_in_section, move_section};
+use crate::theme::Theme;
+
+const PANEL_WIDTH: f32 = 220.0;
+const LEVEL_INDENT: f32 = 12.0;
+
+/// A heading on its way to a new place in the outline.
+#[derive(Clone)]
+struct DraggedHeading {
+    ix: usize,
+    title: String,
+    theme: Theme,
+}
+
+impl Render for DraggedHeading {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .px_2()
+            .py_1()
+            .rounded_md()
+            .bg(self.theme.selection)
+            .text_color(self.theme.foreground)
+            .text_sm()
+            .child(self.title.clone())
+    }
+}
+
+/// The headings of the document being edited, in a panel beside it. It
+/// follows the editor as it changes, marks the section the cursor is in,
+/// scrolls to a heading when it's clicked and moves a whole section when
+/// its heading is dragged onto another.
+pub struct OutlineView {
+    editor: Entity<EditorView>,
+    headings: Vec<Heading>,
+    /// The hash of the content `headings` were found in, so moving the
+    /// cursor doesn't read the document again.
+    parsed: Option<u64>,
+    /// The heading of the section the cursor is in.
+    current: Option<usize>,
+    /// While a heading is being dragged, it and the heading it's over.
+    dragging: Option<(usize, usize)>,
+    theme: Theme,
+    _observation: Subscription,
+}
+
+impl OutlineView {
+    pub fn new(cx: &mut Context<Self>, theme: Theme, editor: Entity<EditorView>) -> Self {
+        let mut outline = Self {
+            _observation: cx.observe(&editor, |this, _, cx| this.refresh(cx)),
+            editor,
+            headings: Vec::new(),
+            parsed: None,
+            current: None,
+            dragging: None,
+            theme,
+        };
+        outline.refresh(cx);
+        outline
+    }
+
+    /// Follows another editor, as when switching tabs.
+    pub fn set_editor(&mut self, editor: Entity<EditorView>, cx: &mut Context<Self>) {
+        if editor == self.editor {
+            return;
+        }
+        self._observation = cx.observe(&editor, |this, _, cx| this.refresh(cx));
+        self.editor = editor;
+        self.parsed = None;
+        self.refresh(cx);
+    }
+
+    pub fn set_theme(&mut self, theme: Theme, cx: &mut Context<Self>) {
+        self.theme = theme;
+        cx.notify();
+    }
+
+    /// Finds the headings again only when the text changed; otherwise just
+    /// the current section follows the cursor.
+    fn refresh(&mut self, cx: &mut Context<Self>) {
+        let editor = self.editor.read(cx);
+        let hash = editor.content_hash();
+        let mut changed = false;
+        if self.parsed != Some(hash) {
+            let headings = headings(editor.content());
+            changed = headings != self.headings;
+            self.headings = headings;
+            self.parsed = Some(hash);
+        }
+        let cursor = editor.cursor_offset();
+        let current = self.headings.iter().rposition(|heading| heading.start <= cursor);
+        if changed || current != self.current {
+            self.current = current;
+            cx.notify();
+        }
+    }
+
+    fn jump(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(heading) = self.headings.get(ix) else { return };
+        let start = heading.start;
+        self.editor.update(cx, |editor, cx| editor.reveal(start, cx));
+        window.focus(&self.editor.focus_handle(cx));
+    }
+
+    /// Moves section `from` to where section `to` is, as one undoable edit.
+    /// Reordering rewrites what's written, so it's off in forward-only mode.
+    fn move_section(&mut self, from: usize, to: usize, cx: &mut Context<Self>) {
+        let editor = self.editor.read(cx);
+        if editor.is_forward_only() {
+            return;
+        }
+        let Some((range, text, landed)) = move_section(editor.content(), &self.headings, from, to) else {
+            return;
+        };
+        self.editor.update(cx, |editor, cx| {
+            editor.replace(range, &text, cx);
+            editor.reveal(landed, cx);
+        });
+    }
+
+    /// Where a dragged section would land, as the heading to draw a line at
+    /// and whether the line goes below it: above the target when moving up,
+    /// after the target's subsections when moving down.
+    fn drop_line(&self, cx: &App) -> Option<(usize, bool)> {
+        let (from, to) = self.dragging.filter(|_| cx.has_active_drag())?;
+        if from.max(to) >= self.headings.len() || from == to {
+            return None;
+        }
+        if from > to {
+            return Some((to, false));
+        }
+        // Onto one of its own subsections it doesn't move at all.
+        (to > last_in_section(&self.headings, from)).then(|| (last_in_section(&self.headings, to), true))
+    }
+
+    fn render_heading(
+        &self,
+        ix: usize,
+        heading: &Heading,
+        drop_line: Option<bool>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let background = if self.current == Some(ix) {
+            self.theme.selection
+        } else {
+            transparent_black()
+        };
+        let color = if heading.level == 1 {
+            self.theme.foreground
+        } else {
+            self.theme.muted
+        };
+        let line_color = self.theme.muted;
+        let title = if heading.title.is_empty() {
+            "Untitled".to_string()
+        } else {
+            heading.title.clone()
+        };
+        let dragged = DraggedHeading {
+            ix,
+            title: title.clone(),
+            theme: self.theme.clone(),
+        };
+
+        let row = div().id(("heading", ix));
+        let row = match drop_line {
+            Some(true) => row.border_b_2().border_color(line_color),
+            Some(false) => row.border_t_2().border_color(line_color),
+            None => row,
+        };
+        row.pl(px(LEVEL_INDENT * (heading.level - 1) as f32 + 8.0))
+            .pr_2()
+            .py_1()
+            .rounded_md()
+            .bg(background)
+            .text_color(color)
+            .overflow_hidden()
+            .whitespace_nowrap()
+            .text_ellipsis()
+            .cursor_pointer()
+            .on_click(cx.listener(move |this, _, window, cx| this.jump(ix, window, cx)))
+            .on_drag(dragged, |dragged, _, _window, cx| cx.new(|_| dragged.clone()))
+            .on_drag_move(cx.listener(move |this, event: &DragMoveEvent<DraggedHeading>, _window, cx| {
+                let over = (event.drag(cx).ix, ix);
+                if event.bounds.contains(&event.event.position) && this.dragging != Some(over) {
+                    this.dragging = Some(over);
+                    cx.notify();
+                }
+            }))
+            .on_drop(cx.listener(move |this, dragged: &DraggedHeading, _window, cx| {
+                this.dragging = None;
+                this.move_section(dragged.ix, ix, cx);
+            }))
+            .child(title)
+    }
+}
+
+impl Render for OutlineView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let drop_line = self.drop_line(cx);
+        let entries: Vec<_> = self
+            .headings
+            .iter()
+            .enumerate()
+            .map(|(ix, heading)| {
+                let line = drop_line.filter(|(at, _)| *at == ix).map(|(_, below)| below);
+                self.render_heading(ix, heading, line, cx).into_any_element()
+            })
+            .collect();
+
+        div()
+            .id("outline")
+            .w(px(PANEL_WIDTH))
+            .h_full()
+            .flex()
+            .flex_col()
+            .px_3()
+            .pb_3()
+            .overflow_y_scroll()
+            .text_sm()
+            .children(
+                entries
+                    .is_empty()
+                    .then(|| div().px_2().text_color(self.theme.muted).child("No headings")),
+            )
+            .children(entries)
+    }
+}
@@ -0,0 +1,45 @@
+use std::time::{Duration, Instant};
+
+/// How long the warning takes to go from the first hint of color to full alarm.
+const WARNING_RAMP: Duration = Duration::from_secs(20);
+
+/// Write-or-lose mode: keeps time since the last keystroke and turns it into
+/// a warning level once the grace period is up. The clock only starts with
+/// the first keystroke, so there's time to think before writing.
+pub struct Pressure {
+    /// When the mode was switched on, to tell its timer apart from an
+    /// earlier one's.
+    pub started: Instant,
+    last_input: Option<Instant>,
+    grace: Duration,
+    /// The level last drawn, so an idle timer doesn't redraw for nothing.
+    pub shown: f32,
+}
+
+impl Pressure {
+    pub fn new(grace_secs: u64) -> Self {
+        Self {
+            started: Instant::now(),
+            last_input: None,
+            grace: Duration::from_secs(grace_secs),
+            shown: 0.0,
+        }
+    }
+
+    pub fn input(&mut self) {
+        self.last_input = Some(Instant::now());
+    }
+
+    /// Stops the clock until the next keystroke.
+    pub fn hold(&mut self) {
+        self.last_input = None;
+    }
+
+    /// How far the warning has gone: 0 during the grace period, rising to 1
+    /// over the following `WARNING_RAMP`.
+    pub fn level(&self) -> f32 {
+        let Some(last_input) = self.last_input else { return 0.0 };
+        let overdue = last_input.elapsed().saturating_sub(self.grace);
+        (overdue.as_secs_f32() / WARNING_RAMP.as_secs_f32()).min(1.0)
+    }
+}
@@ -0,0 +1,395 @@
+use std::ops::Range;
+
+use gpui::{Hsla, hsla};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::markdown::{self, BlockCache, LineKind};
+
+/// Sentences with at least this many words are hard to read.
+const LONG_SENTENCE: usize = 20;
+/// And with at least this many, very hard.
+const VERY_LONG_SENTENCE: usize = 30;
+
+const BE_FORMS: &[&str] = &["am", "is", "are", "was", "were", "be", "been", "being"];
+
+/// Past participles that don't end in "-ed", for spotting the passive voice.
+const IRREGULAR_PARTICIPLES: &[&str] = &[
+    "been", "begun", "bitten", "blown", "born", "borne", "bought", "brought", "built", "caught", "chosen",
+    "done", "drawn", "driven", "eaten", "fallen", "felt", "forgiven", "forgotten", "found", "frozen", "given",
+    "gone", "grown", "heard", "held", "hidden", "hit", "hung", "hurt", "kept", "known", "laid", "led",
+    "left", "lent", "lost", "made", "meant", "met", "paid", "put", "read", "ridden", "risen", "run", "said",
+    "seen", "sent", "set", "shaken", "shown", "shut", "sold", "sought", "spent", "spoken", "spun", "stolen",
+    "struck", "sung", "sunk", "swept", "taken", "taught", "thought", "thrown", "told", "torn", "understood",
+    "woken", "won", "worn", "written",
+];
+
+/// Words ending in "-ly" that aren't adverbs, or are ones nobody would cut.
+const NOT_ADVERBS: &[&str] = &[
+    "ally", "anomaly", "apply", "assembly", "belly", "bully", "butterfly", "chilly", "comply", "costly",
+    "curly", "daily", "deadly", "early", "elderly", "family", "fly", "folly", "friendly", "holy", "imply",
+    "italy", "jelly", "jolly", "july", "likely", "lily", "lively", "lonely", "lovely", "monthly",
+    "multiply", "oily", "only", "orderly", "rally", "rely", "reply", "silly", "sly", "supply", "tally",
+    "ugly", "weekly", "wily", "yearly",
+];
+
+/// Words that rarely add anything.
+const FILLERS: &[&str] = &[
+    "actually", "basically", "certainly", "definitely", "just", "literally", "quite", "rather", "really",
+    "simply", "somewhat", "totally", "very",
+];
+
+/// Filler phrases, matched word by word.
+const FILLER_PHRASES: &[&[&str]] = &[&["kind", "of"], &["sort", "of"], &["a", "lot"], &["in", "order", "to"]];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum IssueKind {
+    LongSentence,
+    VeryLongSentence,
+    Adverb,
+    Passive,
+    Filler,
+}
+
+impl IssueKind {
+    /// Translucent tints that read on dark and light themes alike.
+    pub fn highlight(&self) -> Hsla {
+        match self {
+            IssueKind::LongSentence => hsla(0.13, 0.90, 0.50, 0.22),
+            IssueKind::VeryLongSentence => hsla(0.0, 0.80, 0.50, 0.22),
+            IssueKind::Adverb => hsla(0.60, 0.80, 0.55, 0.28),
+            IssueKind::Passive => hsla(0.33, 0.70, 0.45, 0.28),
+            IssueKind::Filler => hsla(0.80, 0.60, 0.55, 0.28),
+        }
+    }
+
+    /// Whether the issue covers a whole sentence rather than a few words.
+    pub fn is_sentence(&self) -> bool {
+        matches!(self, IssueKind::LongSentence | IssueKind::VeryLongSentence)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Issue {
+    pub range: Range<usize>,
+    pub kind: IssueKind,
+}
+
+/// What the analysis found in one paragraph, with ranges relative to its
+/// start so it can be reused wherever the paragraph moves.
+#[derive(Default)]
+struct ParagraphAnalysis {
+    issues: Vec<Issue>,
+    words: usize,
+    sentences: usize,
+    syllables: usize,
+}
+
+/// The style issues in a whole document and the counts behind its grade.
+#[derive(Default)]
+pub struct Analysis {
+    pub issues: Vec<Issue>,
+    pub words: usize,
+    pub sentences: usize,
+    pub syllables: usize,
+}
+
+impl Analysis {
+    /// The Flesch-Kincaid grade level, or `None` for a document with no prose.
+    pub fn grade(&self) -> Option<f32> {
+        if self.words == 0 || self.sentences == 0 {
+            return None;
+        }
+        let words = self.words as f32;
+        let grade = 0.39 * words / self.sentences as f32 + 11.8 * self.syllables as f32 / words - 15.59;
+        Some(grade.max(0.0))
+    }
+}
+
+/// Hemingway-style analysis, remembered per paragraph so that typing only
+/// costs re-reading the paragraph being typed in.
+#[derive(Default)]
+pub struct Analyzer {
+    paragraphs: BlockCache<ParagraphAnalysis>,
+}
+
+impl Analyzer {
+    pub fn analyze(&mut self, markdown: &str) -> Analysis {
+        let mut analysis = Analysis::default();
+        let paragraphs = self.paragraphs.get(markdown, prose_paragraphs(markdown), |text, _| analyze_paragraph(text));
+        for (range, paragraph) in paragraphs {
+            analysis.words += paragraph.words;
+            analysis.sentences += paragraph.sentences;
+            analysis.syllables += paragraph.syllables;
+            analysis.issues.extend(paragraph.issues.iter().map(|issue| Issue {
+                range: range.start + issue.range.start..range.start + issue.range.end,
+                kind: issue.kind,
+            }));
+        }
+        analysis
+    }
+}
+
+/// Byte ranges of the runs of prose lines in a markdown document, skipping
+/// front matter, fenced code, headings and blank lines.
+fn prose_paragraphs(markdown: &str) -> Vec<Range<usize>> {
+    let mut paragraphs = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+    for line in markdown::lines(markdown) {
+        let trimmed = line.text.trim_start();
+        let prose = line.kind == LineKind::Text
+            && !trimmed.is_empty()
+            && !trimmed.starts_with('#')
+            && !line.text.starts_with("    ")
+            && !line.text.starts_with('\t');
+        match (&mut current, prose) {
+            (Some(paragraph), true) => paragraph.end = line.range.end,
+            (None, true) => current = Some(line.range),
+            (Some(_), false) => paragraphs.extend(current.take()),
+            (None, false) => {}
+        }
+    }
+    paragraphs.extend(current);
+    paragraphs
+}
+
+/// Whether a line opens a new list item or quote, which ends any sentence
+/// left unfinished on the line before.
+fn starts_block(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let numbered = trimmed.split_once(". ").is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    numbered || ["- ", "* ", "+ ", ">"].iter().any(|marker| trimmed.starts_with(marker))
+}
+
+fn analyze_paragraph(text: &str) -> ParagraphAnalysis {
+    let mut analysis = ParagraphAnalysis::default();
+    let code = code_spans(text);
+    let words: Vec<(Range<usize>, String)> = text
+        .unicode_word_indices()
+        .map(|(start, word)| (start..start + word.len(), word.to_lowercase()))
+        .filter(|(range, word)| {
+            word.chars().any(char::is_alphabetic) && !code.iter().any(|span| span.start <= range.start && range.end <= span.end)
+        })
+        .collect();
+
+    for sentence in sentences(text) {
+        let in_sentence: Vec<&(Range<usize>, String)> = words
+            .iter()
+            .filter(|(range, _)| sentence.start <= range.start && range.end <= sentence.end)
+            .collect();
+        if in_sentence.is_empty() {
+            continue;
+        }
+        analysis.sentences += 1;
+        analysis.words += in_sentence.len();
+        analysis.syllables += in_sentence.iter().map(|(_, word)| syllables(word)).sum::<usize>();
+        let kind = match in_sentence.len() {
+            n if n >= VERY_LONG_SENTENCE => Some(IssueKind::VeryLongSentence),
+            n if n >= LONG_SENTENCE => Some(IssueKind::LongSentence),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            let range = in_sentence[0].0.start..in_sentence[in_sentence.len() - 1].0.end;
+            analysis.issues.push(Issue { range, kind });
+        }
+        word_issues(&in_sentence, &mut analysis.issues);
+    }
+    analysis
+}
+
+/// Flags fillers, adverbs and passive constructions among a sentence's words.
+fn word_issues(words: &[&(Range<usize>, String)], issues: &mut Vec<Issue>) {
+    let lowercase: Vec<&str> = words.iter().map(|(_, word)| word.as_str()).collect();
+    let mut ix = 0;
+    while ix < words.len() {
+        if let Some(phrase) = FILLER_PHRASES.iter().find(|phrase| lowercase[ix..].starts_with(phrase)) {
+            let range = words[ix].0.start..words[ix + phrase.len() - 1].0.end;
+            issues.push(Issue { range, kind: IssueKind::Filler });
+            ix += phrase.len();
+            continue;
+        }
+
+        let (range, word) = words[ix];
+        if FILLERS.contains(&word.as_str()) {
+            issues.push(Issue { range: range.clone(), kind: IssueKind::Filler });
+        } else if is_adverb(word) {
+            issues.push(Issue { range: range.clone(), kind: IssueKind::Adverb });
+        } else if BE_FORMS.contains(&word.as_str()) {
+            // "was eaten", "is being built", "were quickly forgotten"
+            let mut next = ix + 1;
+            while next < words.len() && (words[next].1 == "being" || is_adverb(&words[next].1)) {
+                next += 1;
+            }
+            if let Some((end, participle)) = words.get(next) {
+                if is_participle(participle) {
+                    issues.push(Issue { range: range.start..end.end, kind: IssueKind::Passive });
+                    ix = next + 1;
+                    continue;
+                }
+            }
+        }
+        ix += 1;
+    }
+}
+
+fn is_adverb(word: &str) -> bool {
+    word.len() > 4 && word.ends_with("ly") && !NOT_ADVERBS.contains(&word)
+}
+
+fn is_participle(word: &str) -> bool {
+    (word.len() > 4 && word.ends_with("ed")) || IRREGULAR_PARTICIPLES.contains(&word)
+}
+
+/// Byte ranges of the sentences in a paragraph, split after `.`, `!` or `?`
+/// (and any closing quotes or brackets) followed by a space, and wherever
+/// a new list item or quote begins.
+pub fn sentences(text: &str) -> Vec<Range<usize>> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((ix, c)) = chars.next() {
+        let end = if matches!(c, '.' | '!' | '?') {
+            let mut end = ix + c.len_utf8();
+            while let Some(&(next_ix, next)) = chars.peek() {
+                if matches!(next, '.' | '!' | '?' | '"' | '\'' | '”' | '’' | ')' | ']' | '*' | '_') {
+                    end = next_ix + next.len_utf8();
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            match chars.peek() {
+                Some(&(_, next)) if !next.is_whitespace() => continue,
+                _ => end,
+            }
+        } else if c == '\n' && starts_block(&text[ix + 1..]) {
+            ix
+        } else {
+            continue;
+        };
+        sentences.push(start..end);
+        start = end;
+    }
+    if start < text.len() {
+        sentences.push(start..text.len());
+    }
+    sentences
+}
+
+/// Byte ranges of inline `code` spans, which aren't prose.
+fn code_spans(text: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut open = None;
+    for (ix, c) in text.char_indices() {
+        if c == '`' {
+            match open.take() {
+                Some(start) => spans.push(start..ix + 1),
+                None => open = Some(ix),
+            }
+        }
+    }
+    spans
+}
+
+/// A rough syllable count: vowel groups, less a silent final "e" and the
+/// "-es" and "-ed" endings that don't add a sound.
+fn syllables(word: &str) -> usize {
+    let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.len() <= 3 {
+        return 1;
+    }
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for &c in &letters {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+    let [before, second_last, last] = [3, 2, 1].map(|back| letters[letters.len() - back]);
+    let silent_e = last == 'e' && second_last != 'l';
+    let silent_ending =
+        second_last == 'e' && matches!(last, 's' | 'd') && !matches!(before, 't' | 'd' | 's' | 'x' | 'z');
+    if (silent_e || silent_ending) && count > 1 {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text and kind of each issue found in a one-paragraph document.
+    fn flagged(text: &str) -> Vec<(&str, IssueKind)> {
+        analyze_paragraph(text).issues.into_iter().map(|issue| (&text[issue.range], issue.kind)).collect()
+    }
+
+    fn split(text: &str) -> Vec<&str> {
+        sentences(text).into_iter().map(|range| &text[range]).collect()
+    }
+
+    #[test]
+    fn syllables_count_vowel_groups_less_silent_endings() {
+        assert_eq!(syllables("cat"), 1);
+        assert_eq!(syllables("water"), 2);
+        assert_eq!(syllables("beautiful"), 3);
+        assert_eq!(syllables("rhythm"), 1);
+        assert_eq!(syllables("make"), 1);
+        assert_eq!(syllables("table"), 2);
+        assert_eq!(syllables("jumped"), 1);
+        assert_eq!(syllables("wanted"), 2);
+    }
+
+    #[test]
+    fn adverbs_and_participles_skip_look_alikes() {
+        assert!(is_adverb("quickly"));
+        assert!(!is_adverb("family"));
+        assert!(!is_adverb("only"));
+        assert!(is_participle("walked"));
+        assert!(is_participle("eaten"));
+        assert!(!is_participle("bed"));
+        assert!(!is_participle("happy"));
+    }
+
+    #[test]
+    fn passive_voice_spans_be_to_participle() {
+        assert_eq!(flagged("The cake was eaten by the dog."), [("was eaten", IssueKind::Passive)]);
+        assert_eq!(flagged("It is being built."), [("is being built", IssueKind::Passive)]);
+        assert_eq!(flagged("They were quickly forgotten."), [("were quickly forgotten", IssueKind::Passive)]);
+        assert!(flagged("She was happy.").is_empty());
+    }
+
+    #[test]
+    fn fillers_match_words_and_phrases() {
+        assert_eq!(
+            flagged("It is kind of really big."),
+            [("kind of", IssueKind::Filler), ("really", IssueKind::Filler)]
+        );
+    }
+
+    #[test]
+    fn grade_follows_flesch_kincaid() {
+        let analysis = Analysis { words: 100, sentences: 5, syllables: 150, ..Analysis::default() };
+        assert!((analysis.grade().unwrap() - 9.91).abs() < 0.01);
+        let simple = Analysis { words: 10, sentences: 10, syllables: 10, ..Analysis::default() };
+        assert_eq!(simple.grade(), Some(0.0));
+        assert_eq!(Analysis::default().grade(), None);
+    }
+
+    #[test]
+    fn sentences_end_at_punctuation_before_a_space() {
+        assert_eq!(split("One. Two! Three?"), ["One.", " Two!", " Three?"]);
+        assert_eq!(split("He said \"Go.\" Then left."), ["He said \"Go.\"", " Then left."]);
+        assert_eq!(split("Version 1.5 is out."), ["Version 1.5 is out."]);
+        assert_eq!(split("Intro:\n- one\n- two"), ["Intro:", "\n- one", "\n- two"]);
+    }
+
+    #[test]
+    fn prose_paragraphs_skip_everything_but_prose() {
+        let text = "---\nt: x\n---\n# Title\nOne line\nstill one\n\n```\ncode\n```\n    indented\nLast";
+        let found: Vec<&str> = prose_paragraphs(text).into_iter().map(|range| &text[range]).collect();
+        assert_eq!(found, ["One line\nstill one", "Last"]);
+    }
+}
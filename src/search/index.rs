@@ -0,0 +1,472 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::config::Config;
+use crate::document::{document_date, library_files};
+
+const INDEX_HEADER: &str = "jid-index 1";
+const SNIPPET_CONTEXT: usize = 60;
+const MAX_HITS: usize = 50;
+const MAX_HITS_PER_DOCUMENT: usize = 3;
+
+#[derive(Clone)]
+pub struct SearchHit {
+    pub path: PathBuf,
+    pub offset: usize,
+    pub date: String,
+    pub snippet: String,
+    /// Where the matched word sits inside `snippet`.
+    pub highlight: Range<usize>,
+}
+
+/// What changed in the library since it was indexed, found by
+/// `SearchIndex::scan`.
+pub struct LibraryChanges {
+    removed: Vec<PathBuf>,
+    changed: Vec<(PathBuf, String)>,
+}
+
+struct IndexedDocument {
+    path: PathBuf,
+    mtime: u64,
+    terms: BTreeSet<String>,
+}
+
+/// An inverted index over the document library, mapping each lowercased word
+/// to the documents and byte offsets where it occurs. It lives next to the
+/// config file and is kept current by `update` on every save, with `refresh`
+/// catching anything changed outside jid.
+#[derive(Default)]
+pub struct SearchIndex {
+    documents: HashMap<u32, IndexedDocument>,
+    ids: HashMap<PathBuf, u32>,
+    postings: BTreeMap<String, BTreeMap<u32, Vec<usize>>>,
+    next_id: u32,
+    dirty: bool,
+}
+
+fn mtime(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    text.unicode_word_indices()
+        .map(|(offset, word)| (offset, word.to_lowercase()))
+}
+
+fn floor_char_boundary(s: &str, mut offset: usize) -> usize {
+    offset = offset.min(s.len());
+    while !s.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+fn ceil_char_boundary(s: &str, mut offset: usize) -> usize {
+    offset = offset.min(s.len());
+    while !s.is_char_boundary(offset) {
+        offset += 1;
+    }
+    offset
+}
+
+fn snippet(content: &str, offset: usize) -> (String, Range<usize>) {
+    let offset = floor_char_boundary(content, offset);
+    let word_len = content[offset..]
+        .unicode_words()
+        .next()
+        .map(|word| word.len())
+        .unwrap_or(0);
+
+    let start = floor_char_boundary(content, offset.saturating_sub(SNIPPET_CONTEXT));
+    let end = ceil_char_boundary(content, offset + word_len + SNIPPET_CONTEXT);
+    let start = content[start..offset]
+        .find(char::is_whitespace)
+        .filter(|_| start > 0)
+        .map(|i| start + i + 1)
+        .unwrap_or(start);
+    let end = content[offset + word_len..end]
+        .rfind(char::is_whitespace)
+        .filter(|_| end < content.len())
+        .map(|i| offset + word_len + i)
+        .unwrap_or(end);
+
+    let mut text = String::new();
+    if start > 0 {
+        text.push('…');
+    }
+    let highlight_start = text.len() + (offset - start);
+    text.push_str(content[start..end].trim_end());
+    if end < content.len() {
+        text.push('…');
+    }
+    let text = text.replace(['\n', '\t'], " ");
+    (text, highlight_start..highlight_start + word_len)
+}
+
+impl SearchIndex {
+    pub fn index_path() -> PathBuf {
+        Config::config_dir().join("search.idx")
+    }
+
+    pub fn load() -> Self {
+        let path = Self::index_path();
+        if !path.exists() {
+            return Self::default();
+        }
+        match fs::File::open(&path).and_then(|file| Self::read(BufReader::new(file))) {
+            Ok(index) => index,
+            Err(e) => {
+                eprintln!("Failed to read search index: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    fn read(reader: impl BufRead) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed search index");
+        let mut lines = reader.lines();
+        if lines.next().transpose()?.as_deref() != Some(INDEX_HEADER) {
+            return Err(invalid());
+        }
+
+        let mut index = Self::default();
+        for line in lines {
+            let line = line?;
+            let mut fields = line.splitn(4, '\t');
+            match fields.next() {
+                Some("doc") => {
+                    let id: u32 = fields.next().and_then(|f| f.parse().ok()).ok_or_else(invalid)?;
+                    let mtime = fields.next().and_then(|f| f.parse().ok()).ok_or_else(invalid)?;
+                    let path = PathBuf::from(fields.next().ok_or_else(invalid)?);
+                    index.ids.insert(path.clone(), id);
+                    index.documents.insert(id, IndexedDocument {
+                        path,
+                        mtime,
+                        terms: BTreeSet::new(),
+                    });
+                    index.next_id = index.next_id.max(id + 1);
+                }
+                Some("term") => {
+                    let term = fields.next().ok_or_else(invalid)?.to_string();
+                    let mut postings = BTreeMap::new();
+                    for entry in fields.next().ok_or_else(invalid)?.split(' ') {
+                        let (id, offsets) = entry.split_once(':').ok_or_else(invalid)?;
+                        let id: u32 = id.parse().map_err(|_| invalid())?;
+                        let offsets = offsets
+                            .split(',')
+                            .map(|offset| offset.parse().map_err(|_| invalid()))
+                            .collect::<io::Result<Vec<usize>>>()?;
+                        let document = index.documents.get_mut(&id).ok_or_else(invalid)?;
+                        document.terms.insert(term.clone());
+                        postings.insert(id, offsets);
+                    }
+                    index.postings.insert(term, postings);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(index)
+    }
+
+    /// Writes the index back to disk if anything changed since it was loaded.
+    pub fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        let path = Self::index_path();
+        if let Some(parent) = path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                eprintln!("Failed to create config dir: {}", e);
+                return;
+            }
+        }
+        let result = fs::File::create(&path).and_then(|file| self.write(BufWriter::new(file)));
+        match result {
+            Ok(()) => self.dirty = false,
+            Err(e) => eprintln!("Failed to write search index: {}", e),
+        }
+    }
+
+    fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "{}", INDEX_HEADER)?;
+        for (id, document) in &self.documents {
+            writeln!(writer, "doc\t{}\t{}\t{}", id, document.mtime, document.path.display())?;
+        }
+        for (term, postings) in &self.postings {
+            write!(writer, "term\t{}\t", term)?;
+            for (i, (id, offsets)) in postings.iter().enumerate() {
+                if i > 0 {
+                    write!(writer, " ")?;
+                }
+                write!(writer, "{}:", id)?;
+                for (j, offset) in offsets.iter().enumerate() {
+                    if j > 0 {
+                        write!(writer, ",")?;
+                    }
+                    write!(writer, "{}", offset)?;
+                }
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()
+    }
+
+    /// Re-indexes `path` with its freshly saved `content`.
+    pub fn update(&mut self, path: &Path, content: &str) {
+        self.remove(path);
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut terms = BTreeSet::new();
+        for (offset, term) in tokenize(content) {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .entry(id)
+                .or_default()
+                .push(offset);
+            terms.insert(term);
+        }
+        self.ids.insert(path.to_path_buf(), id);
+        self.documents.insert(id, IndexedDocument {
+            path: path.to_path_buf(),
+            mtime: mtime(path),
+            terms,
+        });
+        self.dirty = true;
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        let Some(id) = self.ids.remove(path) else { return };
+        if let Some(document) = self.documents.remove(&id) {
+            for term in &document.terms {
+                if let Some(postings) = self.postings.get_mut(term) {
+                    postings.remove(&id);
+                    if postings.is_empty() {
+                        self.postings.remove(term);
+                    }
+                }
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// Brings the index in line with what is on disk, re-reading only the
+    /// documents whose modification time changed since they were indexed.
+    pub fn refresh(&mut self, documents_dir: &Path) {
+        let changes = Self::scan(&self.mtimes(), documents_dir);
+        self.apply(changes);
+    }
+
+    /// Each indexed document with the modification time it was indexed at.
+    pub fn mtimes(&self) -> HashMap<PathBuf, u64> {
+        self.documents
+            .values()
+            .map(|document| (document.path.clone(), document.mtime))
+            .collect()
+    }
+
+    /// Finds the documents under `documents_dir` that are gone or changed
+    /// since `indexed`, reading the changed ones. It only touches the disk,
+    /// so the app runs it off the UI thread and `apply`s the result.
+    pub fn scan(indexed: &HashMap<PathBuf, u64>, documents_dir: &Path) -> LibraryChanges {
+        let removed = indexed
+            .keys()
+            .filter(|path| !path.starts_with(documents_dir) || !path.exists())
+            .cloned()
+            .collect();
+        let mut changed = Vec::new();
+        for path in library_files(documents_dir) {
+            if indexed.get(&path) == Some(&mtime(&path)) {
+                continue;
+            }
+            match fs::read_to_string(&path) {
+                Ok(content) => changed.push((path, content)),
+                Err(e) => eprintln!("Failed to index {}: {}", path.display(), e),
+            }
+        }
+        LibraryChanges { removed, changed }
+    }
+
+    pub fn apply(&mut self, changes: LibraryChanges) {
+        for path in changes.removed {
+            self.remove(&path);
+        }
+        for (path, content) in changes.changed {
+            self.update(&path, &content);
+        }
+    }
+
+    /// Finds documents containing every word of `query`, treating the last
+    /// word as a prefix so results appear while it is still being typed.
+    /// Newest documents come first.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let terms: Vec<String> = tokenize(query).map(|(_, term)| term).collect();
+        let Some((last, rest)) = terms.split_last() else {
+            return Vec::new();
+        };
+
+        let mut matches: Option<BTreeMap<u32, Vec<usize>>> = None;
+        let mut intersect = |found: BTreeMap<u32, Vec<usize>>| {
+            matches = Some(match matches.take() {
+                None => found,
+                Some(mut existing) => {
+                    existing.retain(|id, _| found.contains_key(id));
+                    existing
+                }
+            });
+        };
+        for term in rest {
+            intersect(self.postings.get(term).cloned().unwrap_or_default());
+        }
+        let mut prefixed: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+        for (_, postings) in self
+            .postings
+            .range(last.clone()..)
+            .take_while(|(term, _)| term.starts_with(last.as_str()))
+        {
+            for (id, offsets) in postings {
+                prefixed.entry(*id).or_default().extend(offsets);
+            }
+        }
+        for offsets in prefixed.values_mut() {
+            offsets.sort_unstable();
+        }
+        intersect(prefixed);
+
+        let mut documents: Vec<(&IndexedDocument, Vec<usize>)> = matches
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(id, offsets)| Some((self.documents.get(&id)?, offsets)))
+            .collect();
+        documents.sort_by(|a, b| b.0.path.cmp(&a.0.path));
+
+        let mut hits = Vec::new();
+        for (document, offsets) in documents {
+            let Ok(content) = fs::read_to_string(&document.path) else { continue };
+            for &offset in offsets.iter().take(MAX_HITS_PER_DOCUMENT) {
+                if offset >= content.len() {
+                    continue;
+                }
+                let (snippet, highlight) = snippet(&content, offset);
+                hits.push(SearchHit {
+                    path: document.path.clone(),
+                    offset,
+                    date: document_date(&document.path),
+                    snippet,
+                    highlight,
+                });
+            }
+            if hits.len() >= MAX_HITS {
+                hits.truncate(MAX_HITS);
+                break;
+            }
+        }
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh library folder under the system temp dir.
+    fn library(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jid-search-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("2025-03-14")).unwrap();
+        dir
+    }
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join("2025-03-14").join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn snippet_highlights_the_word_between_ellipses() {
+        let content = format!("{}needle{}", "lead ".repeat(30), " tail".repeat(30));
+        let offset = content.find("needle").unwrap();
+        let (text, highlight) = snippet(&content, offset);
+        assert!(text.starts_with('…') && text.ends_with('…'));
+        assert_eq!(&text[highlight], "needle");
+        assert!(!text.contains("  "));
+    }
+
+    #[test]
+    fn snippet_of_a_short_document_is_all_of_it() {
+        let (text, highlight) = snippet("One line\nand needle\n", 13);
+        assert_eq!(text, "One line and needle");
+        assert_eq!(&text[highlight], "needle");
+    }
+
+    #[test]
+    fn index_survives_writing_and_reading_back() {
+        let mut index = SearchIndex::default();
+        index.update(Path::new("/tmp/a.md"), "Hello world, hello");
+        index.update(Path::new("/tmp/b.md"), "World peace");
+        let mut bytes = Vec::new();
+        index.write(&mut bytes).unwrap();
+        let read = SearchIndex::read(bytes.as_slice()).unwrap();
+        assert_eq!(read.postings, index.postings);
+        assert_eq!(read.mtimes(), index.mtimes());
+        assert_eq!(read.next_id, index.next_id);
+    }
+
+    #[test]
+    fn malformed_index_is_rejected() {
+        assert!(SearchIndex::read("jid-index 0\n".as_bytes()).is_err());
+        assert!(SearchIndex::read("jid-index 1\nterm\tword\t7:1\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn search_needs_every_word_and_prefixes_the_last() {
+        let dir = library("words");
+        let both = write(&dir, "both.md", "The quick brown fox");
+        let one = write(&dir, "one.md", "A quick note");
+        let mut index = SearchIndex::default();
+        index.refresh(&dir);
+
+        let paths = |query| index.search(query).into_iter().map(|hit| hit.path).collect::<Vec<_>>();
+        assert_eq!(paths("quick"), vec![one.clone(), both.clone()]);
+        assert_eq!(paths("quick bro"), vec![both.clone()]);
+        assert_eq!(paths("QUICK no"), vec![one]);
+        assert!(paths("").is_empty());
+        assert_eq!(index.search("fox")[0].date, "2025-03-14");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn scan_finds_removed_and_changed_documents() {
+        let dir = library("scan");
+        let kept = write(&dir, "kept.md", "unchanged");
+        let gone = write(&dir, "gone.md", "deleted later");
+        let mut index = SearchIndex::default();
+        index.refresh(&dir);
+
+        fs::remove_file(&gone).unwrap();
+        let added = write(&dir, "added.md", "brand new");
+        let changes = SearchIndex::scan(&index.mtimes(), &dir);
+        assert_eq!(changes.removed, vec![gone]);
+        assert_eq!(changes.changed, vec![(added.clone(), "brand new".to_string())]);
+
+        index.apply(changes);
+        assert_eq!(index.search("brand")[0].path, added);
+        assert!(index.search("deleted").is_empty());
+        assert_eq!(index.search("unchanged")[0].path, kept);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,5 @@
+mod index;
+mod view;
+
+pub use index::*;
+pub use view::*;
@@ -0,0 +1,182 @@
+use std::path::PathBuf;
+
+use gpui::*;
+
+use crate::search::SearchHit;
+use crate::theme::Theme;
+
+pub enum SearchEvent {
+    QueryChanged(String),
+    Open { path: PathBuf, offset: usize },
+    Dismissed,
+}
+
+impl EventEmitter<SearchEvent> for SearchView {}
+
+actions!(search, [SelectNextHit, SelectPreviousHit, OpenHit, Dismiss]);
+
+/// The library search overlay: a one-line query field above a list of hits.
+/// It owns no index itself; the owner answers `QueryChanged` with `set_hits`.
+pub struct SearchView {
+    focus_handle: FocusHandle,
+    query: String,
+    hits: Vec<SearchHit>,
+    selected: usize,
+    theme: Theme,
+}
+
+impl SearchView {
+    pub fn new(cx: &mut Context<Self>, theme: Theme) -> Self {
+        Self {
+            focus_handle: cx.focus_handle(),
+            query: String::new(),
+            hits: Vec::new(),
+            selected: 0,
+            theme,
+        }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn set_hits(&mut self, hits: Vec<SearchHit>, cx: &mut Context<Self>) {
+        self.hits = hits;
+        self.selected = 0;
+        cx.notify();
+    }
+
+    fn on_key_down(&mut self, event: &KeyDownEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        let keystroke = &event.keystroke;
+        if keystroke.modifiers.platform || keystroke.modifiers.control {
+            return;
+        }
+        if keystroke.key == "backspace" {
+            self.query.pop();
+        } else if let Some(text) = keystroke.key_char.as_ref().filter(|text| !text.chars().any(char::is_control)) {
+            self.query.push_str(text);
+        } else {
+            return;
+        }
+        cx.stop_propagation();
+        cx.emit(SearchEvent::QueryChanged(self.query.clone()));
+        cx.notify();
+    }
+
+    fn select_next_hit(&mut self, _: &SelectNextHit, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.selected + 1 < self.hits.len() {
+            self.selected += 1;
+            cx.notify();
+        }
+    }
+
+    fn select_previous_hit(&mut self, _: &SelectPreviousHit, _window: &mut Window, cx: &mut Context<Self>) {
+        self.selected = self.selected.saturating_sub(1);
+        cx.notify();
+    }
+
+    fn open_hit(&mut self, _: &OpenHit, _window: &mut Window, cx: &mut Context<Self>) {
+        self.open(self.selected, cx);
+    }
+
+    fn open(&mut self, ix: usize, cx: &mut Context<Self>) {
+        if let Some(hit) = self.hits.get(ix) {
+            cx.emit(SearchEvent::Open {
+                path: hit.path.clone(),
+                offset: hit.offset,
+            });
+        }
+    }
+
+    fn dismiss(&mut self, _: &Dismiss, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(SearchEvent::Dismissed);
+    }
+
+    fn render_hit(&self, ix: usize, hit: &SearchHit, cx: &mut Context<Self>) -> impl IntoElement {
+        let before = hit.snippet[..hit.highlight.start].to_string();
+        let matched = hit.snippet[hit.highlight.clone()].to_string();
+        let after = hit.snippet[hit.highlight.end..].to_string();
+        let background = if ix == self.selected {
+            self.theme.selection
+        } else {
+            transparent_black()
+        };
+
+        div()
+            .id(("hit", ix))
+            .px_3()
+            .py_2()
+            .rounded_md()
+            .bg(background)
+            .cursor_pointer()
+            .on_click(cx.listener(move |this, _, _window, cx| this.open(ix, cx)))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(self.theme.muted)
+                    .child(hit.date.clone()),
+            )
+            .child(
+                div()
+                    .flex()
+                    .text_sm()
+                    .text_color(self.theme.muted)
+                    .overflow_hidden()
+                    .whitespace_nowrap()
+                    .child(before)
+                    .child(div().text_color(self.theme.foreground).child(matched))
+                    .child(after),
+            )
+    }
+}
+
+impl Focusable for SearchView {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for SearchView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let query = if self.query.is_empty() {
+            div().text_color(self.theme.muted).child("Search all documents...")
+        } else {
+            div().child(self.query.clone())
+        };
+        let hits: Vec<_> = self
+            .hits
+            .iter()
+            .enumerate()
+            .map(|(ix, hit)| self.render_hit(ix, hit, cx).into_any_element())
+            .collect();
+
+        div()
+            .id("search")
+            .key_context("Search")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::select_next_hit))
+            .on_action(cx.listener(Self::select_previous_hit))
+            .on_action(cx.listener(Self::open_hit))
+            .on_action(cx.listener(Self::dismiss))
+            .on_key_down(cx.listener(Self::on_key_down))
+            .w(px(640.0))
+            .max_h(px(480.0))
+            .flex()
+            .flex_col()
+            .p_3()
+            .rounded_lg()
+            .border_1()
+            .border_color(self.theme.muted)
+            .bg(self.theme.background)
+            .text_color(self.theme.foreground)
+            .child(div().px_3().py_2().child(query))
+            .child(
+                div()
+                    .id("search-hits")
+                    .flex()
+                    .flex_col()
+                    .overflow_y_scroll()
+                    .children(hits),
+            )
+    }
+}
@@ -0,0 +1,89 @@
+use std::fs;
+use std::path::PathBuf;
+
+use gpui::{Bounds, Pixels, point, px, size};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub active: usize,
+    pub window: Option<WindowState>,
+    pub documents: Vec<SessionDocument>,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionDocument {
+    pub path: PathBuf,
+    pub cursor: usize,
+    pub scroll_y: f32,
+    /// Where the headings of folded sections start.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub folded: Vec<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl WindowState {
+    pub fn from_bounds(bounds: Bounds<Pixels>) -> Self {
+        Self {
+            x: bounds.origin.x.into(),
+            y: bounds.origin.y.into(),
+            width: bounds.size.width.into(),
+            height: bounds.size.height.into(),
+        }
+    }
+
+    pub fn bounds(&self) -> Bounds<Pixels> {
+        Bounds::new(
+            point(px(self.x), px(self.y)),
+            size(px(self.width), px(self.height)),
+        )
+    }
+}
+
+impl Session {
+    pub fn session_path() -> PathBuf {
+        Config::config_dir().join("session.toml")
+    }
+
+    pub fn load() -> Self {
+        let path = Self::session_path();
+        if path.exists() {
+            match fs::read_to_string(&path) {
+                Ok(content) => match toml::from_str(&content) {
+                    Ok(session) => return session,
+                    Err(e) => eprintln!("Failed to parse session: {}", e),
+                },
+                Err(e) => eprintln!("Failed to read session: {}", e),
+            }
+        }
+        Self::default()
+    }
+
+    pub fn save(&self) {
+        let path = Self::session_path();
+        if let Some(parent) = path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                eprintln!("Failed to create config dir: {}", e);
+                return;
+            }
+        }
+        match toml::to_string_pretty(self) {
+            Ok(content) => {
+                if let Err(e) = fs::write(&path, content) {
+                    eprintln!("Failed to write session: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Failed to serialize session: {}", e),
+        }
+    }
+}
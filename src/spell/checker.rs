@@ -0,0 +1,272 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+use spellbook::Dictionary;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::config::Config;
+use crate::export::markdown_options;
+use crate::markdown::{BlockCache, blocks};
+
+const MAX_SUGGESTIONS: usize = 8;
+
+/// Checks prose against a Hunspell dictionary plus the writer's own list of
+/// words. Without a dictionary every word passes.
+#[derive(Default)]
+pub struct SpellChecker {
+    dictionary: Option<Dictionary>,
+    personal: BTreeSet<String>,
+    /// Bumped whenever the set of known words changes, so editors know
+    /// their underlines are stale.
+    generation: usize,
+    /// Earlier answers by word, since a document repeats most of its words.
+    verdicts: RefCell<HashMap<String, bool>>,
+}
+
+impl SpellChecker {
+    /// Where the words added with "Add to dictionary" are kept, one per line.
+    pub fn personal_path() -> PathBuf {
+        Config::config_dir().join("dictionary.txt")
+    }
+
+    /// Folders searched for `<language>.aff` and `<language>.dic`, jid's own
+    /// first, then Hunspell's `DICPATH` and the usual system locations.
+    fn dictionary_dirs() -> Vec<PathBuf> {
+        let mut dirs = vec![Config::config_dir().join("dictionaries")];
+        if let Some(dicpath) = std::env::var_os("DICPATH") {
+            dirs.extend(std::env::split_paths(&dicpath));
+        }
+        if let Some(data) = dirs::data_dir() {
+            dirs.push(data.join("hunspell"));
+        }
+        if let Some(home) = dirs::home_dir() {
+            dirs.push(home.join("Library/Spelling"));
+        }
+        dirs.extend(
+            [
+                "/usr/share/hunspell",
+                "/usr/share/myspell",
+                "/usr/share/myspell/dicts",
+                "/usr/local/share/hunspell",
+                "/opt/homebrew/share/hunspell",
+                "/Library/Spelling",
+            ]
+            .map(PathBuf::from),
+        );
+        dirs
+    }
+
+    pub fn load(language: &str) -> Self {
+        let mut checker = Self {
+            personal: Self::load_personal(),
+            ..Self::default()
+        };
+        let found = Self::dictionary_dirs().into_iter().find_map(|dir| {
+            let aff = dir.join(format!("{}.aff", language));
+            let dic = dir.join(format!("{}.dic", language));
+            (aff.exists() && dic.exists()).then_some((aff, dic))
+        });
+        let Some((aff, dic)) = found else {
+            eprintln!("No {} dictionary found; spell checking is off", language);
+            return checker;
+        };
+        match open_dictionary(&aff, &dic) {
+            Ok(mut dictionary) => {
+                for word in &checker.personal {
+                    let _ = dictionary.add(word);
+                }
+                checker.dictionary = Some(dictionary);
+            }
+            Err(e) => eprintln!("Failed to load dictionary {}: {}", dic.display(), e),
+        }
+        checker
+    }
+
+    fn load_personal() -> BTreeSet<String> {
+        match fs::read_to_string(Self::personal_path()) {
+            Ok(content) => content
+                .lines()
+                .map(str::trim)
+                .filter(|word| !word.is_empty())
+                .map(String::from)
+                .collect(),
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    eprintln!("Failed to read personal dictionary: {}", e);
+                }
+                BTreeSet::new()
+            }
+        }
+    }
+
+    fn save_personal(&self) {
+        let path = Self::personal_path();
+        if let Some(parent) = path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                eprintln!("Failed to create config dir: {}", e);
+                return;
+            }
+        }
+        let content: String = self.personal.iter().map(|word| format!("{}\n", word)).collect();
+        if let Err(e) = fs::write(&path, content) {
+            eprintln!("Failed to write personal dictionary: {}", e);
+        }
+    }
+
+    pub fn has_dictionary(&self) -> bool {
+        self.dictionary.is_some()
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn check(&self, word: &str) -> bool {
+        let Some(dictionary) = &self.dictionary else { return true };
+        if let Some(&known) = self.verdicts.borrow().get(word) {
+            return known;
+        }
+        let known = dictionary.check(&word.replace('’', "'"));
+        self.verdicts.borrow_mut().insert(word.to_string(), known);
+        known
+    }
+
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let mut suggestions = Vec::new();
+        if let Some(dictionary) = &self.dictionary {
+            dictionary.suggest(&word.replace('’', "'"), &mut suggestions);
+        }
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+
+    /// Accepts `word` from now on, in every document.
+    pub fn add_word(&mut self, word: &str) {
+        if !self.personal.insert(word.to_string()) {
+            return;
+        }
+        if let Some(dictionary) = &mut self.dictionary {
+            let _ = dictionary.add(word);
+        }
+        self.save_personal();
+        self.verdicts.borrow_mut().clear();
+        self.generation += 1;
+    }
+
+    /// Byte ranges of the misspelled words in one block of a markdown
+    /// document. Only prose is checked: code, HTML, front matter, link
+    /// targets and anything that looks like a URL or email address are
+    /// skipped. Only the `first` block can be front matter.
+    fn block_misspellings(&self, block: &str, first: bool) -> Vec<Range<usize>> {
+        let mut misspelled = Vec::new();
+        if self.dictionary.is_none() {
+            return misspelled;
+        }
+        let mut options = markdown_options();
+        if !first {
+            options.remove(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS);
+        }
+        let mut skipping = 0usize;
+        for (event, range) in Parser::new_ext(block, options).into_offset_iter() {
+            match event {
+                Event::Start(Tag::CodeBlock(_) | Tag::HtmlBlock | Tag::MetadataBlock(_)) => skipping += 1,
+                Event::End(TagEnd::CodeBlock | TagEnd::HtmlBlock | TagEnd::MetadataBlock(_)) => {
+                    skipping = skipping.saturating_sub(1)
+                }
+                Event::Start(Tag::Link {
+                    link_type: LinkType::Autolink | LinkType::Email,
+                    ..
+                }) => skipping += 1,
+                Event::End(TagEnd::Link) if skipping > 0 => skipping -= 1,
+                Event::Text(_) if skipping == 0 => {
+                    self.check_prose(&block[range.clone()], range.start, &mut misspelled);
+                }
+                _ => {}
+            }
+        }
+        misspelled
+    }
+
+    fn check_prose(&self, text: &str, offset: usize, misspelled: &mut Vec<Range<usize>>) {
+        let addresses = address_ranges(text);
+        for (start, word) in text.unicode_word_indices() {
+            let end = start + word.len();
+            // `&nbsp;` and friends reach here as their source text.
+            let entity = text[..start].ends_with('&');
+            if entity
+                || addresses.iter().any(|address| address.start <= start && end <= address.end)
+                || word.chars().any(|c| c.is_numeric())
+            {
+                continue;
+            }
+            if !self.check(word) {
+                misspelled.push(offset + start..offset + end);
+            }
+        }
+    }
+}
+
+/// The misspellings found in each block of a document, so that after a
+/// keystroke only the block it changed is checked again.
+#[derive(Default)]
+pub struct SpellCache {
+    blocks: BlockCache<Vec<Range<usize>>>,
+    /// The checker generation the cached blocks were checked with.
+    generation: usize,
+}
+
+impl SpellCache {
+    /// Byte ranges of the misspelled words in a markdown document.
+    pub fn misspellings(&mut self, checker: &SpellChecker, markdown: &str) -> Vec<Range<usize>> {
+        if self.generation != checker.generation() {
+            self.blocks.clear();
+            self.generation = checker.generation();
+        }
+        let found = self.blocks.get(markdown, blocks(markdown), |text, first| checker.block_misspellings(text, first));
+        found
+            .iter()
+            .flat_map(|(range, words)| words.iter().map(|word| range.start + word.start..range.start + word.end))
+            .collect()
+    }
+}
+
+/// Whitespace-separated stretches of `text` that look like URLs, email
+/// addresses or file paths rather than words.
+fn address_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (ix, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(ix),
+            (true, Some(from)) => {
+                let chunk = &text[from..ix];
+                if chunk.contains("://") || chunk.starts_with("www.") || chunk.contains('@') || chunk.contains('/') {
+                    ranges.push(from..ix);
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    ranges
+}
+
+fn open_dictionary(aff: &Path, dic: &Path) -> Result<Dictionary, String> {
+    let aff = read_dictionary_file(aff).map_err(|e| e.to_string())?;
+    let dic = read_dictionary_file(dic).map_err(|e| e.to_string())?;
+    Dictionary::new(&aff, &dic).map_err(|e| e.to_string())
+}
+
+/// Reads a `.aff` or `.dic` file. Older dictionaries are often ISO 8859-1
+/// rather than UTF-8; their bytes map one to one onto the first 256 chars.
+fn read_dictionary_file(path: &Path) -> std::io::Result<String> {
+    let bytes = fs::read(path)?;
+    Ok(match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => e.into_bytes().iter().map(|&b| b as char).collect(),
+    })
+}
@@ -0,0 +1,5 @@
+mod checker;
+mod view;
+
+pub use checker::*;
+pub use view::*;
@@ -0,0 +1,160 @@
+use std::ops::Range;
+
+use gpui::*;
+
+use crate::theme::Theme;
+
+pub enum SpellingEvent {
+    Replace { range: Range<usize>, text: String },
+    Learn(String),
+    Dismissed,
+}
+
+impl EventEmitter<SpellingEvent> for SpellingView {}
+
+actions!(
+    spelling,
+    [SelectNextSuggestion, SelectPreviousSuggestion, ConfirmSuggestion, DismissSuggestions]
+);
+
+/// The suggestions for one misspelled word, with "Add to dictionary" as the
+/// last choice.
+pub struct SpellingView {
+    focus_handle: FocusHandle,
+    word: String,
+    range: Range<usize>,
+    suggestions: Vec<String>,
+    selected: usize,
+    theme: Theme,
+}
+
+impl SpellingView {
+    pub fn new(
+        cx: &mut Context<Self>,
+        theme: Theme,
+        word: String,
+        range: Range<usize>,
+        suggestions: Vec<String>,
+    ) -> Self {
+        Self {
+            focus_handle: cx.focus_handle(),
+            word,
+            range,
+            suggestions,
+            selected: 0,
+            theme,
+        }
+    }
+
+    fn select_next(&mut self, _: &SelectNextSuggestion, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.selected < self.suggestions.len() {
+            self.selected += 1;
+            cx.notify();
+        }
+    }
+
+    fn select_previous(&mut self, _: &SelectPreviousSuggestion, _window: &mut Window, cx: &mut Context<Self>) {
+        self.selected = self.selected.saturating_sub(1);
+        cx.notify();
+    }
+
+    fn confirm(&mut self, _: &ConfirmSuggestion, _window: &mut Window, cx: &mut Context<Self>) {
+        self.choose(self.selected, cx);
+    }
+
+    /// Picks the suggestion at `ix`, or learns the word when `ix` is one
+    /// past the last suggestion.
+    fn choose(&mut self, ix: usize, cx: &mut Context<Self>) {
+        match self.suggestions.get(ix) {
+            Some(text) => cx.emit(SpellingEvent::Replace {
+                range: self.range.clone(),
+                text: text.clone(),
+            }),
+            None => cx.emit(SpellingEvent::Learn(self.word.clone())),
+        }
+    }
+
+    fn dismiss(&mut self, _: &DismissSuggestions, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(SpellingEvent::Dismissed);
+    }
+
+    fn render_choice(&self, ix: usize, label: String, cx: &mut Context<Self>) -> impl IntoElement {
+        let background = if ix == self.selected {
+            self.theme.selection
+        } else {
+            transparent_black()
+        };
+        div()
+            .id(("suggestion", ix))
+            .px_3()
+            .py_1()
+            .rounded_md()
+            .bg(background)
+            .cursor_pointer()
+            .on_click(cx.listener(move |this, _, _window, cx| this.choose(ix, cx)))
+            .child(label)
+    }
+}
+
+impl Focusable for SpellingView {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for SpellingView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let learn = self.suggestions.len();
+        let mut choices: Vec<_> = self
+            .suggestions
+            .iter()
+            .enumerate()
+            .map(|(ix, suggestion)| self.render_choice(ix, suggestion.clone(), cx).into_any_element())
+            .collect();
+        if choices.is_empty() {
+            choices.push(
+                div()
+                    .px_3()
+                    .py_1()
+                    .text_color(self.theme.muted)
+                    .child("No suggestions")
+                    .into_any_element(),
+            );
+        }
+        let learn_label = format!("Add “{}” to dictionary", self.word);
+
+        div()
+            .id("spelling")
+            .key_context("Spelling")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_previous))
+            .on_action(cx.listener(Self::confirm))
+            .on_action(cx.listener(Self::dismiss))
+            .w(px(320.0))
+            .flex()
+            .flex_col()
+            .p_3()
+            .rounded_lg()
+            .border_1()
+            .border_color(self.theme.muted)
+            .bg(self.theme.background)
+            .text_color(self.theme.foreground)
+            .text_sm()
+            .child(
+                div()
+                    .px_3()
+                    .pb_2()
+                    .text_xs()
+                    .text_color(self.theme.muted)
+                    .child(self.word.clone()),
+            )
+            .children(choices)
+            .child(
+                div()
+                    .mt_2()
+                    .text_color(self.theme.muted)
+                    .child(self.render_choice(learn, learn_label, cx)),
+            )
+    }
+}
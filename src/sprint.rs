@@ -0,0 +1,250 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use gpui::*;
+
+use crate::theme::Theme;
+
+const MIN_MINUTES: u32 = 1;
+const MAX_MINUTES: u32 = 240;
+const MINUTES_STEP: u32 = 5;
+
+/// A timed writing session in progress.
+pub struct Sprint {
+    pub started: Instant,
+    pub started_at: DateTime<Local>,
+    pub duration: Duration,
+    /// The document that was active when the sprint began, which keeps the result.
+    pub path: PathBuf,
+    pub words: usize,
+}
+
+impl Sprint {
+    pub fn new(minutes: u32, path: PathBuf) -> Self {
+        Self {
+            started: Instant::now(),
+            started_at: Local::now(),
+            duration: Duration::from_secs(minutes as u64 * 60),
+            path,
+            words: 0,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.started.elapsed())
+    }
+
+    /// The countdown as `mm:ss`, rounded up so it reads 0:00 only at the end.
+    pub fn countdown(&self) -> String {
+        let remaining = self.remaining();
+        let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+
+    pub fn finish(&self) -> SprintResult {
+        SprintResult {
+            started_at: self.started_at,
+            elapsed: self.started.elapsed().min(self.duration),
+            words: self.words,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SprintResult {
+    pub started_at: DateTime<Local>,
+    pub elapsed: Duration,
+    pub words: usize,
+}
+
+impl SprintResult {
+    pub fn minutes(&self) -> f32 {
+        self.elapsed.as_secs_f32() / 60.0
+    }
+
+    pub fn words_per_minute(&self) -> usize {
+        if self.elapsed.as_secs() == 0 {
+            return 0;
+        }
+        (self.words as f32 / self.minutes()).round() as usize
+    }
+
+    /// The result as a front matter value, e.g. `2025-03-14 09:30, 25 min, 612 words, 24 wpm`.
+    pub fn metadata(&self) -> String {
+        format!(
+            "{}, {} min, {} words, {} wpm",
+            self.started_at.format("%Y-%m-%d %H:%M"),
+            self.minutes().round() as usize,
+            self.words,
+            self.words_per_minute()
+        )
+    }
+}
+
+pub enum SprintEvent {
+    Start(u32),
+    Dismissed,
+}
+
+impl EventEmitter<SprintEvent> for SprintView {}
+
+actions!(sprint, [LongerSprint, ShorterSprint, ConfirmSprint, CancelSprint]);
+
+enum Mode {
+    /// Picking a length; `typing` is set once a digit has been entered so
+    /// further digits extend the number instead of replacing it.
+    Setup { minutes: u32, typing: bool },
+    Summary(SprintResult),
+}
+
+/// The small card shown before a sprint, to pick its length, and after
+/// one, with what was written.
+pub struct SprintView {
+    focus_handle: FocusHandle,
+    mode: Mode,
+    theme: Theme,
+}
+
+impl SprintView {
+    pub fn setup(cx: &mut Context<Self>, theme: Theme, minutes: u32) -> Self {
+        Self {
+            focus_handle: cx.focus_handle(),
+            mode: Mode::Setup {
+                minutes: minutes.clamp(MIN_MINUTES, MAX_MINUTES),
+                typing: false,
+            },
+            theme,
+        }
+    }
+
+    pub fn summary(cx: &mut Context<Self>, theme: Theme, result: SprintResult) -> Self {
+        Self {
+            focus_handle: cx.focus_handle(),
+            mode: Mode::Summary(result),
+            theme,
+        }
+    }
+
+    fn adjust(&mut self, change: impl FnOnce(u32) -> u32, cx: &mut Context<Self>) {
+        if let Mode::Setup { minutes, typing } = &mut self.mode {
+            *minutes = change(*minutes).clamp(MIN_MINUTES, MAX_MINUTES);
+            *typing = false;
+            cx.notify();
+        }
+    }
+
+    fn longer(&mut self, _: &LongerSprint, _window: &mut Window, cx: &mut Context<Self>) {
+        self.adjust(|minutes| (minutes / MINUTES_STEP + 1) * MINUTES_STEP, cx);
+    }
+
+    fn shorter(&mut self, _: &ShorterSprint, _window: &mut Window, cx: &mut Context<Self>) {
+        self.adjust(|minutes| minutes.saturating_sub(1) / MINUTES_STEP * MINUTES_STEP, cx);
+    }
+
+    fn confirm(&mut self, _: &ConfirmSprint, _window: &mut Window, cx: &mut Context<Self>) {
+        match self.mode {
+            Mode::Setup { minutes, .. } => cx.emit(SprintEvent::Start(minutes.clamp(MIN_MINUTES, MAX_MINUTES))),
+            Mode::Summary(_) => cx.emit(SprintEvent::Dismissed),
+        }
+    }
+
+    fn cancel(&mut self, _: &CancelSprint, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(SprintEvent::Dismissed);
+    }
+
+    fn on_key_down(&mut self, event: &KeyDownEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        let Mode::Setup { minutes, typing } = &mut self.mode else { return };
+        let keystroke = &event.keystroke;
+        if keystroke.modifiers.platform || keystroke.modifiers.control {
+            return;
+        }
+        if keystroke.key == "backspace" {
+            *minutes /= 10;
+            *typing = true;
+        } else if let Some(digit) = keystroke.key.parse::<u32>().ok().filter(|digit| *digit < 10) {
+            *minutes = if *typing { (*minutes * 10 + digit).min(MAX_MINUTES) } else { digit };
+            *typing = true;
+        } else {
+            return;
+        }
+        // Zero minutes isn't a sprint. Show the shortest instead, and let
+        // the next digit start the number afresh.
+        if *minutes < MIN_MINUTES {
+            *minutes = MIN_MINUTES;
+            *typing = false;
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    fn render_setup(&self, minutes: u32) -> Div {
+        div()
+            .flex()
+            .flex_col()
+            .items_center()
+            .gap_2()
+            .child(div().text_xs().text_color(self.theme.muted).child("SPRINT"))
+            .child(div().text_xl().child(format!("{} min", minutes)))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(self.theme.muted)
+                    .child("Type minutes or use ↑ ↓ · Enter to start · Esc to cancel"),
+            )
+    }
+
+    fn render_summary(&self, result: &SprintResult) -> Div {
+        div()
+            .flex()
+            .flex_col()
+            .items_center()
+            .gap_2()
+            .child(div().text_xs().text_color(self.theme.muted).child("SPRINT COMPLETE"))
+            .child(div().text_xl().child(format!("{} words", result.words)))
+            .child(format!(
+                "in {} min · {} words per minute",
+                result.minutes().round() as usize,
+                result.words_per_minute()
+            ))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(self.theme.muted)
+                    .child("Saved in the document's front matter · Enter to continue"),
+            )
+    }
+}
+
+impl Focusable for SprintView {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for SprintView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let content = match &self.mode {
+            Mode::Setup { minutes, .. } => self.render_setup(*minutes),
+            Mode::Summary(result) => self.render_summary(result),
+        };
+        div()
+            .id("sprint")
+            .key_context("Sprint")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::longer))
+            .on_action(cx.listener(Self::shorter))
+            .on_action(cx.listener(Self::confirm))
+            .on_action(cx.listener(Self::cancel))
+            .on_key_down(cx.listener(Self::on_key_down))
+            .w(px(400.0))
+            .p_6()
+            .rounded_lg()
+            .border_1()
+            .border_color(self.theme.muted)
+            .bg(self.theme.background)
+            .text_color(self.theme.foreground)
+            .text_sm()
+            .child(content)
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{Days, Local, NaiveDate};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::config::Config;
+
+const LOG_HEADER: &str = "jid-stats 1";
+
+pub fn count_words(text: &str) -> usize {
+    text.unicode_words().count()
+}
+
+/// Words written to one document on one day during one run of jid.
+#[derive(Clone)]
+pub struct StatsEntry {
+    pub date: NaiveDate,
+    /// When the run of jid that wrote these words started, in Unix seconds.
+    pub session: u64,
+    pub path: PathBuf,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// One day of writing on a single document, summed over every session.
+pub struct DocumentDay {
+    pub date: NaiveDate,
+    pub added: usize,
+    pub removed: usize,
+    pub sessions: usize,
+}
+
+/// A record of writing habits: how many words went into which document on
+/// which day, fed by the editor as text is typed and deleted. It lives next
+/// to the config file as tab-separated lines, one per document, day and
+/// session.
+#[derive(Clone, Default)]
+pub struct StatsLog {
+    entries: Vec<StatsEntry>,
+    session: u64,
+    dirty: bool,
+}
+
+impl StatsLog {
+    pub fn log_path() -> PathBuf {
+        Config::config_dir().join("stats.tsv")
+    }
+
+    pub fn load() -> Self {
+        let session = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or(0);
+        let path = Self::log_path();
+        if !path.exists() {
+            return Self {
+                session,
+                ..Self::default()
+            };
+        }
+        match fs::File::open(&path).and_then(|file| Self::read(BufReader::new(file))) {
+            Ok(entries) => Self {
+                entries,
+                session,
+                dirty: false,
+            },
+            Err(e) => {
+                eprintln!("Failed to read writing stats: {}", e);
+                Self {
+                    session,
+                    ..Self::default()
+                }
+            }
+        }
+    }
+
+    fn read(reader: impl BufRead) -> io::Result<Vec<StatsEntry>> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed stats log");
+        let mut lines = reader.lines();
+        if lines.next().transpose()?.as_deref() != Some(LOG_HEADER) {
+            return Err(invalid());
+        }
+
+        let mut entries = Vec::new();
+        for line in lines {
+            let line = line?;
+            let mut fields = line.splitn(5, '\t');
+            let mut next = || fields.next().ok_or_else(invalid);
+            let date = NaiveDate::parse_from_str(next()?, "%Y-%m-%d").map_err(|_| invalid())?;
+            let session = next()?.parse().map_err(|_| invalid())?;
+            let added = next()?.parse().map_err(|_| invalid())?;
+            let removed = next()?.parse().map_err(|_| invalid())?;
+            let path = PathBuf::from(next()?);
+            entries.push(StatsEntry {
+                date,
+                session,
+                path,
+                added,
+                removed,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Writes the log back to disk if anything was recorded since it was loaded.
+    pub fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        let path = Self::log_path();
+        if let Some(parent) = path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                eprintln!("Failed to create config dir: {}", e);
+                return;
+            }
+        }
+        let result = fs::File::create(&path).and_then(|file| self.write(BufWriter::new(file)));
+        match result {
+            Ok(()) => self.dirty = false,
+            Err(e) => eprintln!("Failed to write writing stats: {}", e),
+        }
+    }
+
+    fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "{}", LOG_HEADER)?;
+        for entry in &self.entries {
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}",
+                entry.date.format("%Y-%m-%d"),
+                entry.session,
+                entry.added,
+                entry.removed,
+                entry.path.display()
+            )?;
+        }
+        writer.flush()
+    }
+
+    /// Adds a change in word count to today's entry for `path` in this session.
+    pub fn record(&mut self, path: &Path, added: usize, removed: usize) {
+        if added == 0 && removed == 0 {
+            return;
+        }
+        let date = today();
+        let session = self.session;
+        let existing = self
+            .entries
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.session == session && entry.date == date && entry.path == path);
+        match existing {
+            Some(entry) => {
+                entry.added += added;
+                entry.removed += removed;
+            }
+            None => self.entries.push(StatsEntry {
+                date,
+                session,
+                path: path.to_path_buf(),
+                added,
+                removed,
+            }),
+        }
+        self.dirty = true;
+    }
+
+    /// Words added on each day anything was written.
+    pub fn daily_totals(&self) -> BTreeMap<NaiveDate, usize> {
+        let mut totals = BTreeMap::new();
+        for entry in &self.entries {
+            *totals.entry(entry.date).or_default() += entry.added;
+        }
+        totals
+    }
+
+    pub fn words_on(&self, date: NaiveDate) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.date == date)
+            .map(|entry| entry.added)
+            .sum()
+    }
+
+    pub fn words_today(&self) -> usize {
+        self.words_on(today())
+    }
+
+    /// Words added to `path` today, across every session.
+    pub fn document_words_today(&self, path: &Path) -> usize {
+        let date = today();
+        self.entries
+            .iter()
+            .filter(|entry| entry.date == date && entry.path == path)
+            .map(|entry| entry.added)
+            .sum()
+    }
+
+    /// The current and longest runs of consecutive days with words written.
+    /// A streak that hasn't been extended yet today still counts as current.
+    pub fn streaks(&self) -> (usize, usize) {
+        let days: Vec<NaiveDate> = self
+            .daily_totals()
+            .into_iter()
+            .filter(|(_, words)| *words > 0)
+            .map(|(date, _)| date)
+            .collect();
+
+        let mut longest = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for &date in &days {
+            run = match previous {
+                Some(previous) if previous.checked_add_days(Days::new(1)) == Some(date) => run + 1,
+                _ => 1,
+            };
+            longest = longest.max(run);
+            previous = Some(date);
+        }
+
+        let today = today();
+        let yesterday = today.checked_sub_days(Days::new(1));
+        let current = match days.last() {
+            Some(&last) if last == today || Some(last) == yesterday => run,
+            _ => 0,
+        };
+        (current, longest)
+    }
+
+    /// Every day `path` was written to, newest first.
+    pub fn document_history(&self, path: &Path) -> Vec<DocumentDay> {
+        let mut days: BTreeMap<NaiveDate, DocumentDay> = BTreeMap::new();
+        for entry in self.entries.iter().filter(|entry| entry.path == path) {
+            let day = days.entry(entry.date).or_insert(DocumentDay {
+                date: entry.date,
+                added: 0,
+                removed: 0,
+                sessions: 0,
+            });
+            day.added += entry.added;
+            day.removed += entry.removed;
+            day.sessions += 1;
+        }
+        days.into_values().rev().collect()
+    }
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(date: NaiveDate, added: usize) -> StatsEntry {
+        StatsEntry {
+            date,
+            session: 1,
+            path: PathBuf::from("/notes/a.md"),
+            added,
+            removed: 0,
+        }
+    }
+
+    fn days_ago(days: u64) -> NaiveDate {
+        today().checked_sub_days(Days::new(days)).unwrap()
+    }
+
+    fn log(entries: Vec<StatsEntry>) -> StatsLog {
+        StatsLog {
+            entries,
+            ..StatsLog::default()
+        }
+    }
+
+    #[test]
+    fn read_parses_what_write_wrote() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap();
+        let mut written = entry(date, 120);
+        written.removed = 7;
+        written.path = PathBuf::from("/notes/tabs\tand spaces.md");
+        let mut bytes = Vec::new();
+        log(vec![written]).write(&mut bytes).unwrap();
+
+        let entries = StatsLog::read(bytes.as_slice()).unwrap();
+        assert_eq!(entries.len(), 1);
+        let read = &entries[0];
+        assert_eq!(read.date, date);
+        assert_eq!(read.session, 1);
+        assert_eq!((read.added, read.removed), (120, 7));
+        assert_eq!(read.path, PathBuf::from("/notes/tabs\tand spaces.md"));
+    }
+
+    #[test]
+    fn read_rejects_a_missing_header() {
+        assert!(StatsLog::read("2025-03-14\t1\t5\t0\t/notes/a.md\n".as_bytes()).is_err());
+        assert!(StatsLog::read("jid-stats 2\n".as_bytes()).is_err());
+        assert!(StatsLog::read("".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn read_rejects_a_malformed_line() {
+        assert!(StatsLog::read("jid-stats 1\n2025-03-14\tone\t5\t0\t/notes/a.md\n".as_bytes()).is_err());
+        assert!(StatsLog::read("jid-stats 1\n2025-03-14\t1\t5\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn streaks_count_runs_across_gaps() {
+        let log = log(vec![
+            entry(days_ago(10), 50),
+            entry(days_ago(9), 50),
+            entry(days_ago(8), 50),
+            entry(days_ago(5), 50),
+            entry(days_ago(1), 50),
+            entry(today(), 50),
+        ]);
+        assert_eq!(log.streaks(), (2, 3));
+    }
+
+    #[test]
+    fn a_streak_last_extended_yesterday_is_still_current() {
+        let log = log(vec![entry(days_ago(3), 50), entry(days_ago(2), 50), entry(days_ago(1), 50)]);
+        assert_eq!(log.streaks(), (3, 3));
+    }
+
+    #[test]
+    fn a_streak_broken_before_yesterday_is_over() {
+        let log = log(vec![entry(days_ago(3), 50), entry(days_ago(2), 50)]);
+        assert_eq!(log.streaks(), (0, 2));
+    }
+
+    #[test]
+    fn days_with_only_deletions_break_a_streak() {
+        let log = log(vec![entry(days_ago(2), 50), entry(days_ago(1), 0), entry(today(), 50)]);
+        assert_eq!(log.streaks(), (1, 1));
+    }
+}
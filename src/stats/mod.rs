@@ -0,0 +1,5 @@
+mod log;
+mod view;
+
+pub use log::*;
+pub use view::*;
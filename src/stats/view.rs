@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use chrono::{Datelike, Days, NaiveDate};
+use gpui::*;
+
+use crate::stats::{StatsLog, today};
+use crate::theme::Theme;
+
+/// How many weeks of history the heatmap shows.
+const HEATMAP_WEEKS: u64 = 26;
+const CELL_SIZE: f32 = 11.0;
+const RECENT_DAYS: u64 = 7;
+const HISTORY_ROWS: usize = 10;
+
+pub enum StatsEvent {
+    Dismissed,
+}
+
+impl EventEmitter<StatsEvent> for StatsView {}
+
+actions!(stats, [CloseStats]);
+
+/// The writing stats overlay: totals and streaks, a heatmap of the last half
+/// year, recent daily totals and the history of the active document. It
+/// shows a snapshot of the log taken when it was opened.
+pub struct StatsView {
+    focus_handle: FocusHandle,
+    log: StatsLog,
+    document: PathBuf,
+    theme: Theme,
+}
+
+fn plural(count: usize, one: &str, many: &str) -> String {
+    format!("{} {}", count, if count == 1 { one } else { many })
+}
+
+impl StatsView {
+    pub fn new(cx: &mut Context<Self>, theme: Theme, log: StatsLog, document: PathBuf) -> Self {
+        Self {
+            focus_handle: cx.focus_handle(),
+            log,
+            document,
+            theme,
+        }
+    }
+
+    fn close(&mut self, _: &CloseStats, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(StatsEvent::Dismissed);
+    }
+
+    fn render_figure(&self, label: &'static str, value: String) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_col()
+            .child(div().text_xs().text_color(self.theme.muted).child(label))
+            .child(div().text_lg().child(value))
+    }
+
+    /// A column per week, oldest on the left, with a cell per day shaded by
+    /// how much was written relative to the busiest day shown.
+    fn render_heatmap(&self, totals: &BTreeMap<NaiveDate, usize>) -> impl IntoElement {
+        let today = today();
+        let this_monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+        let first = this_monday - Days::new(7 * (HEATMAP_WEEKS - 1));
+        let busiest = totals.range(first..).map(|(_, words)| *words).max().unwrap_or(0);
+
+        let weeks = (0..HEATMAP_WEEKS).map(|week| {
+            let days = (0..7).map(move |day| first + Days::new(week * 7 + day));
+            div().flex().flex_col().gap(px(2.0)).children(days.map(|date| {
+                let words = totals.get(&date).copied().unwrap_or(0);
+                let color = if date > today {
+                    transparent_black()
+                } else if words == 0 || busiest == 0 {
+                    self.theme.muted.opacity(0.15)
+                } else {
+                    let level = (words * 4).div_ceil(busiest).clamp(1, 4);
+                    self.theme.foreground.opacity(level as f32 * 0.25)
+                };
+                div().size(px(CELL_SIZE)).rounded_sm().bg(color)
+            }))
+        });
+        div().flex().gap(px(2.0)).children(weeks)
+    }
+
+    fn render_row(&self, label: String, value: String) -> impl IntoElement {
+        div()
+            .flex()
+            .justify_between()
+            .gap_4()
+            .child(div().text_color(self.theme.muted).child(label))
+            .child(value)
+    }
+}
+
+impl Focusable for StatsView {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for StatsView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let totals = self.log.daily_totals();
+        let today = today();
+        let (current, longest) = self.log.streaks();
+        let monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+        let week: usize = totals.range(monday..).map(|(_, words)| words).sum();
+
+        let recent = (0..RECENT_DAYS).map(|ago| {
+            let date = today - Days::new(ago);
+            let words = totals.get(&date).copied().unwrap_or(0);
+            self.render_row(date.format("%a %b %-d").to_string(), plural(words, "word", "words"))
+        });
+        let history = self.log.document_history(&self.document);
+        let history_rows: Vec<_> = history
+            .iter()
+            .take(HISTORY_ROWS)
+            .map(|day| {
+                self.render_row(
+                    day.date.format("%Y-%m-%d").to_string(),
+                    format!(
+                        "+{} −{} · {}",
+                        day.added,
+                        day.removed,
+                        plural(day.sessions, "session", "sessions")
+                    ),
+                )
+                .into_any_element()
+            })
+            .collect();
+        let history_section = if history_rows.is_empty() {
+            div()
+                .text_color(self.theme.muted)
+                .child("Nothing written in this document yet.")
+        } else {
+            div().flex().flex_col().gap_1().children(history_rows)
+        };
+
+        div()
+            .id("stats")
+            .key_context("Stats")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::close))
+            .w(px(640.0))
+            .flex()
+            .flex_col()
+            .gap_4()
+            .p_6()
+            .rounded_lg()
+            .border_1()
+            .border_color(self.theme.muted)
+            .bg(self.theme.background)
+            .text_color(self.theme.foreground)
+            .text_sm()
+            .child(
+                div()
+                    .flex()
+                    .justify_between()
+                    .child(self.render_figure("Today", plural(self.log.words_today(), "word", "words")))
+                    .child(self.render_figure("This week", plural(week, "word", "words")))
+                    .child(self.render_figure("Streak", plural(current, "day", "days")))
+                    .child(self.render_figure("Longest streak", plural(longest, "day", "days"))),
+            )
+            .child(self.render_heatmap(&totals))
+            .child(
+                div()
+                    .flex()
+                    .gap_8()
+                    .child(
+                        div()
+                            .flex_1()
+                            .flex()
+                            .flex_col()
+                            .gap_1()
+                            .child(div().text_xs().text_color(self.theme.muted).child("LAST 7 DAYS"))
+                            .children(recent),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .flex()
+                            .flex_col()
+                            .gap_1()
+                            .child(div().text_xs().text_color(self.theme.muted).child("THIS DOCUMENT"))
+                            .child(history_section),
+                    ),
+            )
+    }
+}
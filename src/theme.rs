@@ -34,6 +34,8 @@ pub struct Theme {
     pub selection: Hsla,
     pub focus_current: Hsla,
     pub focus_dimmed: Hsla,
+    /// The wavy underline under misspelled words.
+    pub misspelled: Hsla,
 }
 
 impl Theme {
@@ -46,6 +48,7 @@ impl Theme {
             selection: hsla(0.58, 0.30, 0.35, 0.40),
             focus_current: hsla(0.17, 0.06, 0.82, 1.0),
             focus_dimmed: hsla(0.17, 0.04, 0.45, 1.0),
+            misspelled: hsla(0.0, 0.60, 0.60, 0.85),
         }
     }
 
@@ -58,6 +61,7 @@ impl Theme {
             selection: hsla(0.67, 0.10, 0.15, 0.50),
             focus_current: hsla(0.0, 0.0, 0.22, 1.0),
             focus_dimmed: hsla(0.0, 0.0, 0.15, 1.0),
+            misspelled: hsla(0.0, 0.30, 0.30, 0.85),
         }
     }
 
@@ -70,6 +74,7 @@ impl Theme {
             selection: hsla(0.10, 0.35, 0.70, 0.35),
             focus_current: hsla(0.08, 0.35, 0.25, 1.0),
             focus_dimmed: hsla(0.08, 0.20, 0.50, 1.0),
+            misspelled: hsla(0.0, 0.55, 0.45, 0.85),
         }
     }
 
@@ -82,6 +87,7 @@ impl Theme {
             selection: hsla(0.50, 0.40, 0.40, 0.40),
             focus_current: hsla(0.52, 0.12, 0.78, 1.0),
             focus_dimmed: hsla(0.52, 0.10, 0.45, 1.0),
+            misspelled: hsla(0.0, 0.50, 0.62, 0.85),
         }
     }
 
@@ -94,6 +100,7 @@ impl Theme {
             selection: hsla(0.35, 0.35, 0.35, 0.40),
             focus_current: hsla(0.25, 0.08, 0.80, 1.0),
             focus_dimmed: hsla(0.25, 0.06, 0.45, 1.0),
+            misspelled: hsla(0.02, 0.50, 0.60, 0.85),
         }
     }
 
@@ -106,6 +113,7 @@ impl Theme {
             selection: hsla(0.58, 0.30, 0.75, 0.30),
             focus_current: hsla(0.17, 0.08, 0.25, 1.0),
             focus_dimmed: hsla(0.17, 0.05, 0.55, 1.0),
+            misspelled: hsla(0.0, 0.65, 0.50, 0.85),
         }
     }
 
@@ -123,4 +131,56 @@ impl Theme {
     pub fn toggled(&self) -> Self {
         Self::from_mode(self.mode.next())
     }
+
+    /// The alarm colors write-or-lose mode shifts toward: deep red behind
+    /// pale text on dark themes, pink behind dark red text on light ones.
+    pub fn warning(&self) -> Self {
+        let (background, foreground, muted) = if self.background.l > 0.5 {
+            (hsla(0.0, 0.70, 0.85, 1.0), hsla(0.0, 0.65, 0.22, 1.0), hsla(0.0, 0.40, 0.45, 1.0))
+        } else {
+            (hsla(0.0, 0.60, 0.20, 1.0), hsla(0.02, 0.50, 0.88, 1.0), hsla(0.01, 0.35, 0.55, 1.0))
+        };
+        Self {
+            mode: self.mode,
+            background,
+            foreground,
+            muted,
+            selection: hsla(0.0, 0.50, 0.50, 0.40),
+            focus_current: foreground,
+            focus_dimmed: muted,
+            misspelled: hsla(0.14, 0.80, 0.60, 0.90),
+        }
+    }
+
+    /// Every color moved `amount` of the way toward `other`'s, from 0 (this
+    /// theme) to 1 (`other`). The mode stays this theme's.
+    pub fn blend(&self, other: &Theme, amount: f32) -> Self {
+        let amount = amount.clamp(0.0, 1.0);
+        Self {
+            mode: self.mode,
+            background: mix(self.background, other.background, amount),
+            foreground: mix(self.foreground, other.foreground, amount),
+            muted: mix(self.muted, other.muted, amount),
+            selection: mix(self.selection, other.selection, amount),
+            focus_current: mix(self.focus_current, other.focus_current, amount),
+            focus_dimmed: mix(self.focus_dimmed, other.focus_dimmed, amount),
+            misspelled: mix(self.misspelled, other.misspelled, amount),
+        }
+    }
+}
+
+/// Interpolates two colors, turning the hue the short way round the wheel.
+fn mix(from: Hsla, to: Hsla, amount: f32) -> Hsla {
+    let mut turn = to.h - from.h;
+    if turn > 0.5 {
+        turn -= 1.0;
+    } else if turn < -0.5 {
+        turn += 1.0;
+    }
+    hsla(
+        (from.h + turn * amount).rem_euclid(1.0),
+        from.s + (to.s - from.s) * amount,
+        from.l + (to.l - from.l) * amount,
+        from.a + (to.a - from.a) * amount,
+    )
 }